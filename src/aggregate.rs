@@ -0,0 +1,544 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::Serialize;
+
+use crate::{
+    history::{Entry, Outcome},
+    report::week_start,
+    stats::completion_rate,
+};
+
+/// Work sessions below which averages and the best day are left out, as
+/// too few to say anything.
+pub const MIN_SESSIONS: u32 = 5;
+/// Days covered by the recent figures, today included.
+pub const RECENT_DAYS: u64 = 30;
+/// Version of the `stats --json` document, raised when a field changes
+/// meaning or goes away.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Figures over the work sessions of a stretch of history.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Aggregates {
+    pub completed: u32,
+    pub abandoned: u32,
+    /// Time spent in completed work sessions.
+    pub focused_secs: u64,
+    /// Days with at least one work session.
+    pub active_days: u32,
+    /// Percentage of the started work sessions that completed.
+    pub completion_rate: Option<u32>,
+    /// Length of the completed work sessions on average.
+    pub average_session_secs: Option<u64>,
+    pub sessions_per_active_day: Option<f64>,
+    /// Day of the week with the most focused time, e.g. `Tuesday`.
+    pub best_weekday: Option<String>,
+    /// Interruptions per started work session on average.
+    pub interruptions_per_session: Option<f64>,
+}
+
+impl Aggregates {
+    pub fn started(&self) -> u32 {
+        self.completed + self.abandoned
+    }
+}
+
+/// Recent and lifetime figures side by side.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Overview {
+    pub last_30_days: Aggregates,
+    pub lifetime: Aggregates,
+}
+
+/// Figures over the work sessions among `entries`; breaks are left out.
+pub fn aggregate<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Aggregates {
+    let mut totals = Aggregates::default();
+    let mut days: HashSet<NaiveDate> = HashSet::new();
+    let mut weekdays: HashMap<Weekday, u64> = HashMap::new();
+    let mut interruptions = 0;
+
+    for entry in entries.into_iter().filter(|entry| !entry.kind.is_break()) {
+        let date = entry.start.date_naive();
+        days.insert(date);
+        interruptions += entry.interruptions.len();
+        match entry.outcome {
+            Outcome::Completed => totals.completed += 1,
+            Outcome::Abandoned | Outcome::Restarted => {
+                totals.abandoned += 1;
+                continue;
+            }
+        }
+        totals.focused_secs += entry.duration_secs;
+        *weekdays.entry(date.weekday()).or_default() += entry.duration_secs;
+    }
+
+    totals.active_days = days.len() as u32;
+    totals.completion_rate = completion_rate(totals.completed, totals.abandoned);
+    if totals.started() < MIN_SESSIONS {
+        return totals;
+    }
+
+    totals.average_session_secs = Some(totals.focused_secs / totals.completed.max(1) as u64);
+    totals.sessions_per_active_day =
+        Some(totals.started() as f64 / totals.active_days.max(1) as f64);
+    // Ties go to the earlier day of the week.
+    totals.best_weekday = weekdays
+        .into_iter()
+        .max_by_key(|(day, secs)| (*secs, std::cmp::Reverse(day.num_days_from_monday())))
+        .map(|(day, _)| weekday_name(day).to_string());
+    totals.interruptions_per_session = Some(interruptions as f64 / totals.started() as f64);
+    totals
+}
+
+/// The last `RECENT_DAYS` up to `today` next to the whole of `entries`.
+pub fn overview(entries: &[Entry], today: NaiveDate) -> Overview {
+    let first = today - Days::new(RECENT_DAYS - 1);
+    let recent = entries.iter().filter(|entry| {
+        let date = entry.start.date_naive();
+        first <= date && date <= today
+    });
+
+    Overview {
+        last_30_days: aggregate(recent),
+        lifetime: aggregate(entries),
+    }
+}
+
+/// Stretch of time the breakdown of focused time covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChartPeriod {
+    #[default]
+    Today,
+    /// Since the first day of the week.
+    Week,
+    /// Since the first of the month.
+    Month,
+}
+
+impl ChartPeriod {
+    pub fn next(self) -> ChartPeriod {
+        match self {
+            ChartPeriod::Today => ChartPeriod::Week,
+            ChartPeriod::Week => ChartPeriod::Month,
+            ChartPeriod::Month => ChartPeriod::Today,
+        }
+    }
+
+    /// Locale key of the name of the period.
+    pub fn key(self) -> &'static str {
+        match self {
+            ChartPeriod::Today => "stats.today",
+            ChartPeriod::Week => "stats.week",
+            ChartPeriod::Month => "stats.month",
+        }
+    }
+
+    /// First day covered on `today`, with weeks starting on `first`.
+    pub fn first_day(self, today: NaiveDate, first: Weekday) -> NaiveDate {
+        match self {
+            ChartPeriod::Today => today,
+            ChartPeriod::Week => week_start(today, first),
+            ChartPeriod::Month => today.with_day(1).unwrap_or(today),
+        }
+    }
+}
+
+/// Focused time per tag or label over a period, longest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Breakdown {
+    pub period: ChartPeriod,
+    /// `#tag` or the label, None for sessions with neither.
+    pub totals: Vec<(Option<String>, u64)>,
+}
+
+/// Focused time of the completed work sessions among `entries` from
+/// `first` to `today`. A session counts towards each of its tags, or its
+/// label when it has none.
+pub fn breakdown(
+    entries: &[Entry],
+    period: ChartPeriod,
+    first: NaiveDate,
+    today: NaiveDate,
+) -> Breakdown {
+    let mut totals: HashMap<Option<String>, u64> = HashMap::new();
+    let within = |entry: &&Entry| {
+        let date = entry.start.date_naive();
+        !entry.kind.is_break()
+            && entry.outcome == Outcome::Completed
+            && first <= date
+            && date <= today
+    };
+    for entry in entries.iter().filter(within) {
+        let label = entry
+            .label
+            .as_deref()
+            .map(str::trim)
+            .filter(|label| !label.is_empty());
+        let names: Vec<Option<String>> = match (&entry.tags[..], label) {
+            ([], label) => vec![label.map(String::from)],
+            (tags, _) => tags.iter().map(|tag| Some(format!("#{}", tag))).collect(),
+        };
+        for name in names {
+            *totals.entry(name).or_default() += entry.duration_secs;
+        }
+    }
+
+    let mut totals: Vec<(Option<String>, u64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Breakdown { period, totals }
+}
+
+/// Figures of the one-shot summary printed by `--stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Brief {
+    pub today: Aggregates,
+    /// Since `first`, the first day of the week.
+    pub week: Aggregates,
+    pub streak: u32,
+}
+
+/// Today, the week from `first` and the streak among `entries`.
+pub fn brief(entries: &[Entry], first: NaiveDate, today: NaiveDate) -> Brief {
+    let between = |from: NaiveDate| {
+        entries.iter().filter(move |entry| {
+            let date = entry.start.date_naive();
+            from <= date && date <= today
+        })
+    };
+
+    Brief {
+        today: aggregate(between(today)),
+        week: aggregate(between(first)),
+        streak: streak(entries, today),
+    }
+}
+
+/// Days in a row with a completed work session, up to today, or up to
+/// yesterday while today has none yet.
+pub fn streak(entries: &[Entry], today: NaiveDate) -> u32 {
+    let days: HashSet<NaiveDate> = entries
+        .iter()
+        .filter(|entry| !entry.kind.is_break() && entry.outcome == Outcome::Completed)
+        .map(|entry| entry.start.date_naive())
+        .collect();
+
+    let mut day = today;
+    if !days.contains(&day) {
+        day = day - Days::new(1);
+    }
+    let mut count = 0;
+    while days.contains(&day) {
+        count += 1;
+        day = day - Days::new(1);
+    }
+    count
+}
+
+/// The document printed by `stats --json`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Export {
+    pub version: u32,
+    /// `week`, `month` or `all`.
+    pub period: &'static str,
+    /// First day covered, as YYYY-MM-DD; null for the whole history.
+    pub from: Option<String>,
+    pub to: String,
+    pub stats: Aggregates,
+    /// The figures of the stats screen, whatever the period.
+    pub overview: Overview,
+}
+
+/// The figures of the work sessions among `entries` from `first`, or from
+/// the start of the history, to `today`, along with the overview of the
+/// stats screen.
+pub fn export(
+    entries: &[Entry],
+    period: &'static str,
+    first: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Export {
+    let from = first.unwrap_or(NaiveDate::MIN);
+    let within = entries.iter().filter(|entry| {
+        let date = entry.start.date_naive();
+        from <= date && date <= today
+    });
+
+    Export {
+        version: SCHEMA_VERSION,
+        period,
+        from: first.map(|first| first.format("%Y-%m-%d").to_string()),
+        to: today.format("%Y-%m-%d").to_string(),
+        stats: aggregate(within),
+        overview: overview(entries, today),
+    }
+}
+
+/// `2h05m`, or `45m` under an hour.
+pub fn hours_minutes(secs: u64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    if hours == 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h{:02}m", hours, minutes)
+    }
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration};
+
+    use super::*;
+    use crate::timer::SessionKind;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    /// A session of `minutes` starting at 10:00 on `day`.
+    fn session(day: &str, kind: SessionKind, outcome: Outcome, minutes: u64) -> Entry {
+        let start = DateTime::parse_from_rfc3339(&format!("{}T10:00:00+02:00", day)).unwrap();
+        Entry {
+            start,
+            end: start + Duration::minutes(minutes as i64),
+            duration_secs: minutes * 60,
+            kind,
+            label: None,
+            tags: Vec::new(),
+            outcome,
+            note: None,
+            interruptions: Vec::new(),
+        }
+    }
+
+    fn work(day: &str, minutes: u64) -> Entry {
+        session(day, SessionKind::Work, Outcome::Completed, minutes)
+    }
+
+    fn abandoned(day: &str, minutes: u64) -> Entry {
+        session(day, SessionKind::Work, Outcome::Abandoned, minutes)
+    }
+
+    #[test]
+    fn focused_time_counts_completed_sessions_only() {
+        let interrupted = |mut entry: Entry, count: usize| {
+            entry.interruptions = vec![entry.start; count];
+            entry
+        };
+        // 2024-05-06 is a Monday.
+        let entries = [
+            interrupted(work("2024-05-06", 25), 1),
+            work("2024-05-06", 25),
+            interrupted(abandoned("2024-05-06", 10), 2),
+            work("2024-05-07", 50),
+            session("2024-05-07", SessionKind::Work, Outcome::Restarted, 5),
+            work("2024-05-08", 25),
+            session("2024-05-08", SessionKind::ShortBreak, Outcome::Completed, 5),
+            session("2024-05-09", SessionKind::LongBreak, Outcome::Completed, 15),
+        ];
+
+        assert_eq!(
+            aggregate(&entries),
+            Aggregates {
+                completed: 4,
+                abandoned: 2,
+                focused_secs: 125 * 60,
+                active_days: 3,
+                completion_rate: Some(66),
+                average_session_secs: Some(125 * 60 / 4),
+                sessions_per_active_day: Some(2.0),
+                best_weekday: Some(String::from("Monday")),
+                interruptions_per_session: Some(0.5),
+            }
+        );
+    }
+
+    #[test]
+    fn best_weekday_goes_by_completed_time() {
+        let mut entries = vec![
+            work("2024-05-06", 25),
+            work("2024-05-07", 30),
+            work("2024-05-08", 25),
+            work("2024-05-09", 25),
+        ];
+        // A long abandoned Monday doesn't make it the best day.
+        entries.push(abandoned("2024-05-06", 60));
+        assert_eq!(aggregate(&entries).best_weekday.as_deref(), Some("Tuesday"));
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_weekday() {
+        let entries: Vec<Entry> = [
+            "2024-05-10",
+            "2024-05-07",
+            "2024-05-08",
+            "2024-05-09",
+            "2024-05-11",
+        ]
+        .iter()
+        .map(|day| work(day, 25))
+        .collect();
+        assert_eq!(aggregate(&entries).best_weekday.as_deref(), Some("Tuesday"));
+    }
+
+    #[test]
+    fn too_few_sessions_leave_the_averages_out() {
+        let entries = [
+            work("2024-05-06", 25),
+            work("2024-05-07", 25),
+            abandoned("2024-05-07", 5),
+            work("2024-05-08", 25),
+        ];
+        assert_eq!(MIN_SESSIONS, 5);
+        let totals = aggregate(&entries);
+        assert_eq!(totals.started(), 4);
+        assert_eq!(totals.focused_secs, 75 * 60);
+        assert_eq!(totals.active_days, 3);
+        assert_eq!(totals.completion_rate, Some(75));
+        assert_eq!(totals.average_session_secs, None);
+        assert_eq!(totals.sessions_per_active_day, None);
+        assert_eq!(totals.best_weekday, None);
+
+        let mut entries = entries.to_vec();
+        entries.push(abandoned("2024-05-08", 5));
+        let totals = aggregate(&entries);
+        assert_eq!(totals.average_session_secs, Some(25 * 60));
+        assert!(totals.best_weekday.is_some());
+    }
+
+    #[test]
+    fn no_sessions() {
+        let totals = aggregate(&[]);
+        assert_eq!(totals, Aggregates::default());
+        assert_eq!(totals.completion_rate, None);
+    }
+
+    #[test]
+    fn overview_splits_the_last_30_days_from_the_lifetime() {
+        let today = date("2024-05-31");
+        let entries = [
+            // A day before the 30 days.
+            work("2024-05-01", 25),
+            // The first and the last of the 30 days.
+            work("2024-05-02", 25),
+            abandoned("2024-05-02", 5),
+            work("2024-05-31", 50),
+            // Later than today, e.g. after the clock went back.
+            work("2024-06-01", 25),
+        ];
+
+        let overview = overview(&entries, today);
+        assert_eq!(overview.last_30_days.completed, 2);
+        assert_eq!(overview.last_30_days.abandoned, 1);
+        assert_eq!(overview.last_30_days.focused_secs, 75 * 60);
+        assert_eq!(overview.last_30_days.active_days, 2);
+        assert_eq!(overview.last_30_days.average_session_secs, None);
+
+        assert_eq!(overview.lifetime.completed, 4);
+        assert_eq!(overview.lifetime.focused_secs, 125 * 60);
+        assert_eq!(overview.lifetime.active_days, 4);
+        assert_eq!(overview.lifetime.average_session_secs, Some(125 * 60 / 4));
+    }
+
+    #[test]
+    fn breakdown_leaves_abandoned_sessions_out() {
+        let mut tagged = work("2024-05-06", 25);
+        tagged.tags = vec![String::from("a"), String::from("b")];
+        let mut labelled = work("2024-05-06", 50);
+        labelled.label = Some(String::from("report"));
+        let mut gone = abandoned("2024-05-06", 40);
+        gone.label = Some(String::from("report"));
+
+        let day = date("2024-05-06");
+        let breakdown = breakdown(
+            &[tagged, labelled, gone, work("2024-05-06", 10)],
+            ChartPeriod::Today,
+            day,
+            day,
+        );
+        assert_eq!(
+            breakdown.totals,
+            [
+                (Some(String::from("report")), 3000),
+                (Some(String::from("#a")), 1500),
+                (Some(String::from("#b")), 1500),
+                (None, 600),
+            ]
+        );
+    }
+
+    /// `export` as `stats --json` prints it, against the document in
+    /// `tests/golden`.
+    fn assert_golden(export: &Export, golden: &str) {
+        assert_eq!(serde_json::to_string_pretty(export).unwrap() + "\n", golden);
+    }
+
+    #[test]
+    fn export_of_a_week() {
+        let entries = [
+            work("2024-04-01", 25),
+            work("2024-05-27", 25),
+            work("2024-05-27", 25),
+            abandoned("2024-05-27", 10),
+            work("2024-05-28", 50),
+            work("2024-05-29", 25),
+            session("2024-05-29", SessionKind::ShortBreak, Outcome::Completed, 5),
+            work("2024-05-31", 25),
+        ];
+        let export = export(
+            &entries,
+            "week",
+            Some(date("2024-05-27")),
+            date("2024-05-31"),
+        );
+        assert_golden(&export, include_str!("../tests/golden/stats-week.json"));
+    }
+
+    #[test]
+    fn export_without_history() {
+        let export = export(&[], "all", None, date("2024-05-31"));
+        assert_golden(&export, include_str!("../tests/golden/stats-empty.json"));
+    }
+
+    #[test]
+    fn a_session_over_midnight_counts_on_the_day_it_started() {
+        let start = DateTime::parse_from_rfc3339("2024-05-06T23:50:00+02:00").unwrap();
+        let late = Entry {
+            start,
+            end: start + Duration::minutes(25),
+            ..work("2024-05-06", 25)
+        };
+        let entries = [late.clone()];
+        let (monday, tuesday) = (date("2024-05-06"), date("2024-05-07"));
+
+        let on_monday = breakdown(&entries, ChartPeriod::Today, monday, monday);
+        assert_eq!(on_monday.totals, [(None, 1500)]);
+        let on_tuesday = breakdown(&entries, ChartPeriod::Today, tuesday, tuesday);
+        assert!(on_tuesday.totals.is_empty());
+
+        let recent = overview(&entries, monday).last_30_days;
+        assert_eq!((recent.completed, recent.active_days), (1, 1));
+        let exported = export(&entries, "week", Some(tuesday), tuesday);
+        assert_eq!(exported.stats.completed, 0);
+        // Just after midnight where it was logged, though still Monday in
+        // UTC, it's Tuesday's, whatever the zone it's read in.
+        let after = Entry {
+            start: DateTime::parse_from_rfc3339("2024-05-07T00:10:00+02:00").unwrap(),
+            ..late
+        };
+        let on_tuesday = breakdown(&[after], ChartPeriod::Today, tuesday, tuesday);
+        assert_eq!(on_tuesday.totals, [(None, 1500)]);
+    }
+}
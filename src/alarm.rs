@@ -0,0 +1,142 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::config::AlarmStep;
+
+/// Escalating alarm of a finished session. It rings as soon as the session
+/// reaches zero, then every `repeat_secs` until the session is acknowledged,
+/// each time as the latest step that's due.
+#[derive(Debug, Default)]
+pub struct Alarm {
+    rings: u32,
+    /// Played in place of the bells of the first ring.
+    sound: Option<PathBuf>,
+}
+
+impl Alarm {
+    pub fn new(sound: Option<PathBuf>) -> Alarm {
+        Alarm { rings: 0, sound }
+    }
+
+    /// The sound of the first ring, once.
+    pub fn take_sound(&mut self) -> Option<PathBuf> {
+        self.sound.take()
+    }
+
+    pub fn has_rung(&self) -> bool {
+        self.rings > 0
+    }
+
+    /// The step to ring `elapsed` after the session reached zero, if a ring
+    /// is due. Rings missed while the app wasn't ticking are not made up.
+    pub fn due<'a>(
+        &mut self,
+        steps: &'a [AlarmStep],
+        repeat_secs: Option<u64>,
+        elapsed: Duration,
+    ) -> Option<&'a AlarmStep> {
+        let repeat_secs = repeat_secs.filter(|secs| *secs > 0);
+        let elapsed = elapsed.as_secs();
+
+        let next = match (self.rings, repeat_secs) {
+            (0, _) => 0,
+            (rings, Some(repeat)) => repeat * rings as u64,
+            (_, None) => return None,
+        };
+        if elapsed < next {
+            return None;
+        }
+
+        self.rings = match repeat_secs {
+            Some(repeat) => (elapsed / repeat) as u32 + 1,
+            None => 1,
+        };
+
+        steps
+            .iter()
+            .filter(|step| step.after_secs <= elapsed)
+            .max_by_key(|step| step.after_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps() -> Vec<AlarmStep> {
+        let step = |after_secs, bells, flash, notify| AlarmStep {
+            after_secs,
+            bells,
+            flash,
+            notify,
+        };
+        vec![
+            step(0, 1, false, false),
+            step(60, 2, false, true),
+            step(300, 2, true, false),
+        ]
+    }
+
+    /// Ticks every 250ms for `secs` of a mock clock, returning when the
+    /// alarm rang and the step it rang as.
+    fn schedule(alarm: &mut Alarm, repeat_secs: Option<u64>, secs: u64) -> Vec<(u64, u64)> {
+        let steps = steps();
+        (0..secs * 4)
+            .map(|tick| Duration::from_millis(tick * 250))
+            .filter_map(|elapsed| {
+                alarm
+                    .due(&steps, repeat_secs, elapsed)
+                    .map(|step| (elapsed.as_secs(), step.after_secs))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn escalates_while_unacknowledged() {
+        let mut alarm = Alarm::new(None);
+        let rings = schedule(&mut alarm, Some(60), 420);
+        assert_eq!(
+            rings,
+            [
+                (0, 0),
+                (60, 60),
+                (120, 60),
+                (180, 60),
+                (240, 60),
+                (300, 300),
+                (360, 300)
+            ]
+        );
+    }
+
+    #[test]
+    fn rings_once_without_repeats() {
+        let mut alarm = Alarm::new(None);
+        assert_eq!(schedule(&mut alarm, None, 600), [(0, 0)]);
+        let mut alarm = Alarm::new(None);
+        assert_eq!(schedule(&mut alarm, Some(0), 600), [(0, 0)]);
+    }
+
+    #[test]
+    fn missed_rings_are_not_made_up() {
+        let steps = steps();
+        let mut alarm = Alarm::new(None);
+        assert!(alarm.due(&steps, Some(30), Duration::ZERO).is_some());
+        // The app didn't tick for two minutes, e.g. while suspended.
+        let step = alarm.due(&steps, Some(30), Duration::from_secs(125));
+        assert_eq!(step.map(|step| step.after_secs), Some(60));
+        assert!(alarm
+            .due(&steps, Some(30), Duration::from_secs(140))
+            .is_none());
+        assert!(alarm
+            .due(&steps, Some(30), Duration::from_secs(150))
+            .is_some());
+    }
+
+    #[test]
+    fn sound_goes_with_the_first_ring() {
+        let mut alarm = Alarm::new(Some(PathBuf::from("chime.wav")));
+        assert!(!alarm.has_rung());
+        assert_eq!(alarm.take_sound(), Some(PathBuf::from("chime.wav")));
+        assert_eq!(alarm.take_sound(), None);
+    }
+}
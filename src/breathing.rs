@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::config::Breathing;
+
+/// Cells of the pacer at the top of a breath.
+const WIDTH: usize = 21;
+
+/// Where in a breath the pacer is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Inhale,
+    Hold,
+    Exhale,
+}
+
+impl Phase {
+    /// Locale key of the word shown next to the bar.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Phase::Inhale => "breathing.inhale",
+            Phase::Hold => "breathing.hold",
+            Phase::Exhale => "breathing.exhale",
+        }
+    }
+}
+
+/// The phase of the breath `elapsed` into the break and how many cells of
+/// the bar are lit, from one at the bottom of a breath to `WIDTH` at the
+/// top. None when the breath takes no time at all.
+pub fn pace(breathing: &Breathing, elapsed: Duration) -> Option<(Phase, usize)> {
+    let inhale = breathing.inhale_secs as f32;
+    let hold = breathing.hold_secs as f32;
+    let exhale = breathing.exhale_secs as f32;
+    let breath = inhale + hold + exhale;
+    if breath <= 0.0 {
+        return None;
+    }
+
+    let at = elapsed.as_secs_f32() % breath;
+    let (phase, fill) = if at < inhale {
+        (Phase::Inhale, at / inhale)
+    } else if at < inhale + hold {
+        (Phase::Hold, 1.0)
+    } else {
+        (Phase::Exhale, 1.0 - (at - inhale - hold) / exhale)
+    };
+    Some((phase, 1 + (fill * (WIDTH - 1) as f32).round() as usize))
+}
+
+/// `lit` cells of the bar, centered in its full width so the word after it
+/// stays put.
+pub fn bar(lit: usize) -> String {
+    format!("{:^width$}", "█".repeat(lit), width = WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar_fills_breathing_in_and_empties_breathing_out() {
+        let breathing = Breathing::default();
+        let at = |secs: f32| pace(&breathing, Duration::from_secs_f32(secs)).unwrap();
+        assert_eq!(at(0.0), (Phase::Inhale, 1));
+        assert_eq!(at(2.0), (Phase::Inhale, 11));
+        assert_eq!(at(5.0), (Phase::Hold, WIDTH));
+        assert_eq!(at(9.0), (Phase::Exhale, 11));
+        assert_eq!(at(12.0), (Phase::Inhale, 1));
+
+        let still = Breathing {
+            inhale_secs: 0,
+            hold_secs: 0,
+            exhale_secs: 0,
+            ..Breathing::default()
+        };
+        assert_eq!(pace(&still, Duration::from_secs(3)), None);
+        assert_eq!(bar(1).chars().count(), WIDTH);
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(not(test))]
+use arboard::Clipboard;
+
+/// Keeps the system clipboard open for the lifetime of the app, as on X11 the
+/// copied text is only served while its owner is alive.
+#[cfg(not(test))]
+#[derive(Default)]
+pub struct SystemClipboard {
+    inner: Option<Clipboard>,
+}
+
+#[cfg(not(test))]
+impl SystemClipboard {
+    pub fn copy(&mut self, text: &str) -> Result<(), arboard::Error> {
+        let clipboard = match self.inner.as_mut() {
+            Some(clipboard) => clipboard,
+            None => self.inner.insert(Clipboard::new()?),
+        };
+
+        clipboard.set_text(text.to_owned())
+    }
+}
+
+/// Stands in for the system clipboard in tests, keeping what was copied.
+#[cfg(test)]
+#[derive(Default)]
+pub struct SystemClipboard {
+    pub copied: Option<String>,
+}
+
+#[cfg(test)]
+impl SystemClipboard {
+    pub fn copy(&mut self, text: &str) -> Result<(), arboard::Error> {
+        self.copied = Some(text.to_owned());
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use crate::{keymap::Action, parse_duration};
+
+/// A line typed into the command palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Action(Action),
+    /// `start <duration> [label]`
+    StartWith {
+        duration: Duration,
+        label: Option<String>,
+    },
+    Pause,
+    Resume,
+    /// `abandon`, stopping the session even in strict mode
+    Abandon,
+    /// `timer <name>`, switching to the named timer or adding it
+    Timer(String),
+    /// `routine <name>`, starting the named routine, or `routine`, listing
+    /// them
+    Routine(Option<String>),
+    /// `theme <name>`, switching to the named theme, or `theme`, listing
+    /// them
+    Theme(Option<String>),
+    /// `config`, showing where the config file is, or `config edit`,
+    /// opening it in `$EDITOR`
+    Config {
+        edit: bool,
+    },
+}
+
+/// Commands that aren't plain keyboard actions.
+const EXTRA_NAMES: &[&str] = &[
+    "abandon", "config", "pause", "resume", "routine", "theme", "timer",
+];
+
+/// Names accepted as the first word of a command, in completion order.
+pub fn names() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = Action::ALL
+        .iter()
+        .filter(|action| **action != Action::Palette)
+        .map(|action| action.name())
+        .chain(EXTRA_NAMES.iter().copied())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+pub fn parse(input: &str) -> Result<Command, String> {
+    let mut words = input.split_whitespace();
+    let name = words.next().ok_or_else(|| String::from("empty command"))?;
+    let args: Vec<&str> = words.collect();
+
+    let command = match name {
+        "start" if !args.is_empty() => {
+            let duration = parse_duration(args[0])
+                .ok_or_else(|| format!("invalid duration `{}`", args[0]))?;
+            let label = args[1..].join(" ");
+            return Ok(Command::StartWith {
+                duration,
+                label: if label.is_empty() { None } else { Some(label) },
+            });
+        }
+        "timer" => {
+            if args.is_empty() {
+                return Err(String::from("`timer` needs a name"));
+            }
+            return Ok(Command::Timer(args.join(" ")));
+        }
+        "routine" => {
+            let name = args.join(" ");
+            return Ok(Command::Routine(if name.is_empty() {
+                None
+            } else {
+                Some(name)
+            }));
+        }
+        "theme" => {
+            let name = args.join(" ");
+            return Ok(Command::Theme(if name.is_empty() {
+                None
+            } else {
+                Some(name)
+            }));
+        }
+        "config" => {
+            return match args.as_slice() {
+                [] => Ok(Command::Config { edit: false }),
+                ["edit"] => Ok(Command::Config { edit: true }),
+                _ => Err(String::from("`config` takes nothing or `edit`")),
+            };
+        }
+        "pause" => Command::Pause,
+        "resume" => Command::Resume,
+        "abandon" => Command::Abandon,
+        "q" => Command::Action(Action::Quit),
+        _ => match Action::from_name(name) {
+            Some(action) if action != Action::Palette => Command::Action(action),
+            _ => return Err(format!("unknown command `{}`", name)),
+        },
+    };
+
+    if !args.is_empty() {
+        return Err(format!("`{}` takes no arguments", name));
+    }
+
+    Ok(command)
+}
+
+/// Command names starting with the word being typed. Arguments are not
+/// completed.
+pub fn complete(input: &str) -> Vec<&'static str> {
+    if input.contains(char::is_whitespace) {
+        return Vec::new();
+    }
+
+    names()
+        .into_iter()
+        .filter(|name| name.starts_with(input))
+        .collect()
+}
+
+/// Longest prefix shared by all `names`.
+pub fn common_prefix<'a>(names: &[&'a str]) -> &'a str {
+    let first = match names.first() {
+        Some(first) => *first,
+        None => return "",
+    };
+
+    let len = names.iter().skip(1).fold(first.len(), |len, name| {
+        first
+            .bytes()
+            .zip(name.bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+
+    &first[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_takes_a_duration_and_an_optional_label() {
+        assert_eq!(
+            parse("start 25:00 write report"),
+            Ok(Command::StartWith {
+                duration: Duration::from_secs(1500),
+                label: Some(String::from("write report")),
+            })
+        );
+        assert_eq!(
+            parse("start 05:00"),
+            Ok(Command::StartWith {
+                duration: Duration::from_secs(300),
+                label: None,
+            })
+        );
+        assert!(parse("start soon").is_err());
+    }
+
+    #[test]
+    fn actions_are_commands_without_arguments() {
+        assert_eq!(parse("pause"), Ok(Command::Pause));
+        assert_eq!(parse("q"), Ok(Command::Action(Action::Quit)));
+        assert!(parse("pause now").is_err());
+        assert!(parse("palette").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn completion_stops_at_the_shared_prefix() {
+        assert_eq!(complete("res"), vec!["reset", "resume"]);
+        assert_eq!(common_prefix(&complete("res")), "res");
+        assert_eq!(common_prefix(&["add_minute", "add_five_minutes"]), "add_");
+        assert!(complete("start 25:00").is_empty());
+    }
+
+    #[test]
+    fn config_shows_the_path_or_edits_the_file() {
+        assert_eq!(parse("config"), Ok(Command::Config { edit: false }));
+        assert_eq!(parse("config edit"), Ok(Command::Config { edit: true }));
+        assert!(parse("config show").is_err());
+    }
+}
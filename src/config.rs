@@ -0,0 +1,1506 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{NaiveTime, Weekday};
+use ratatui::{
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    widgets::BorderType,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    delay_arg,
+    keymap::{parse_key, Action, Keymap, Keys},
+    parse_duration, profile,
+    theme::{self, Theme},
+    timer::SessionKind,
+};
+
+const CONFIG_FILE: &str = "config.toml";
+const ENV_PREFIX: &str = "POMIDOR_";
+const ENV_KEY_PREFIX: &str = "POMIDOR_KEY_";
+
+/// What the big digits show while a session runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayMode {
+    Remaining,
+    Elapsed,
+    /// The wall-clock time the session ends at.
+    End,
+}
+
+/// What losing the terminal's focus during a work session does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusLost {
+    /// Shows a reminder once the focus comes back.
+    Warn,
+    /// Pauses the session.
+    Pause,
+}
+
+/// What gets a key refused by strict mode through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrictEscape {
+    /// Holding the key down for a few seconds.
+    #[default]
+    Hold,
+    /// Pressing it with alt.
+    Alt,
+}
+
+/// First day of the week in reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    /// ISO weeks.
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    pub fn weekday(&self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// How the timers treat time the system clock jumped over, e.g. while the
+/// system slept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockJump {
+    /// Running sessions pick up where they were.
+    Freeze,
+    /// Running sessions count the jump as elapsed.
+    Adjust,
+}
+
+/// How the time since a session reached zero is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OvertimeStyle {
+    /// `+05:12`, in red.
+    Plus,
+    /// `-05:12`.
+    Minus,
+    /// `05:12`, with `over` in the status line.
+    Over,
+}
+
+/// Where the banner text sits across the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    pub fn alignment(&self) -> Alignment {
+        match self {
+            Align::Left => Alignment::Left,
+            Align::Center => Alignment::Center,
+            Align::Right => Alignment::Right,
+        }
+    }
+}
+
+/// How completed sessions are announced outside the TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notifications {
+    /// Picked from `TERM_PROGRAM` and `TERM`.
+    Auto,
+    /// The `OSC 9` escape of iTerm2, kitty and WezTerm.
+    Osc9,
+    /// The `OSC 777` escape of urxvt and foot.
+    Osc777,
+    /// A desktop notification over D-Bus; needs the `dbus` feature.
+    Desktop,
+    /// The terminal bell.
+    Bell,
+}
+
+/// Line style of the borders around the edit box and the overlays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+}
+
+impl BorderStyle {
+    pub fn border_type(&self) -> BorderType {
+        match self {
+            BorderStyle::Plain => BorderType::Plain,
+            BorderStyle::Rounded => BorderType::Rounded,
+            BorderStyle::Double => BorderType::Double,
+            BorderStyle::Thick => BorderType::Thick,
+        }
+    }
+}
+
+/// Daily window during which sounds are suppressed. The window may wrap
+/// around midnight, e.g. 22:00 to 07:00.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct QuietHours {
+    #[serde(with = "clock")]
+    pub start: NaiveTime,
+    #[serde(with = "clock")]
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Style the digits take once the remaining time drops below
+/// `threshold_secs`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Warning {
+    pub threshold_secs: u64,
+    #[serde(default, with = "color::option")]
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub blink: bool,
+}
+
+impl Warning {
+    pub fn style(&self, monochrome: bool) -> Style {
+        let mut style = Style::default();
+        if let (Some(color), false) = (self.color, monochrome) {
+            style = style.fg(color);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.blink {
+            style = style.add_modifier(Modifier::SLOW_BLINK);
+        }
+        style
+    }
+}
+
+/// What the alarm does once a finished session has gone unacknowledged for
+/// `after_secs`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct AlarmStep {
+    pub after_secs: u64,
+    #[serde(default = "one")]
+    pub bells: u32,
+    /// Flash the screen with every bell.
+    #[serde(default)]
+    pub flash: bool,
+    /// Send the notification of the session again, as urgent.
+    #[serde(default)]
+    pub notify: bool,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Work/break cycling: every completed session lines up the next one.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Cycle {
+    #[serde(with = "duration")]
+    pub work: Duration,
+    #[serde(with = "duration")]
+    pub short_break: Duration,
+    #[serde(with = "duration")]
+    pub long_break: Duration,
+    /// Every this many work sessions the break is a long one.
+    pub long_break_interval: u32,
+    /// Work sessions after which the run ends with a summary; endless when
+    /// unset.
+    pub cycles: Option<u32>,
+    /// Start the next session as soon as one completes.
+    pub auto_start: bool,
+    /// Breaks skipped in a day before a nudge to take the next one; 0 for
+    /// none.
+    pub skip_nudge: u32,
+}
+
+impl Default for Cycle {
+    fn default() -> Cycle {
+        Cycle {
+            work: Duration::from_secs(25 * 60),
+            short_break: Duration::from_secs(5 * 60),
+            long_break: Duration::from_secs(15 * 60),
+            long_break_interval: 4,
+            cycles: None,
+            auto_start: false,
+            skip_nudge: 3,
+        }
+    }
+}
+
+impl Cycle {
+    pub fn duration(&self, kind: SessionKind) -> Duration {
+        match kind {
+            SessionKind::Work => self.work,
+            SessionKind::ShortBreak => self.short_break,
+            SessionKind::LongBreak => self.long_break,
+        }
+    }
+}
+
+/// A named bundle of settings started together, e.g. `[routine.writing]`.
+/// Keys left out keep the values of `[cycle]`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Routine {
+    /// Durations as `50m`, `30s` or `mm:ss`, checked when the config loads.
+    pub work: Option<String>,
+    #[serde(rename = "break")]
+    pub short_break: Option<String>,
+    pub long_break: Option<String>,
+    pub long_break_interval: Option<u32>,
+    pub cycles: Option<u32>,
+    pub auto_start: Option<bool>,
+    /// Label of the work sessions.
+    pub label: Option<String>,
+    pub bell: Option<bool>,
+    /// Sound files by kind of session, over those of `[sounds]`.
+    pub sounds: HashMap<SessionKind, PathBuf>,
+    /// Shell command run once the last cycle is done.
+    pub on_finish: Option<String>,
+}
+
+impl Routine {
+    /// The cycle the routine runs, `base` filling in what it leaves out.
+    /// Errors name the routine and the key.
+    pub fn cycle(&self, name: &str, base: Cycle) -> Result<Cycle, String> {
+        let duration = |key: &str, value: &Option<String>, default: Duration| match value {
+            Some(value) => delay_arg(value).map_err(|_| {
+                format!(
+                    "routine `{}`: invalid `{}` duration `{}`, expected 50m, 30s or mm:ss",
+                    name, key, value
+                )
+            }),
+            None => Ok(default),
+        };
+
+        Ok(Cycle {
+            work: duration("work", &self.work, base.work)?,
+            short_break: duration("break", &self.short_break, base.short_break)?,
+            long_break: duration("long_break", &self.long_break, base.long_break)?,
+            long_break_interval: self.long_break_interval.unwrap_or(base.long_break_interval),
+            cycles: self.cycles.or(base.cycles),
+            auto_start: self.auto_start.unwrap_or(base.auto_start),
+            skip_nudge: base.skip_nudge,
+        })
+    }
+}
+
+/// Pacer breathing in and out under the countdown during breaks.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Breathing {
+    pub enabled: bool,
+    pub inhale_secs: u64,
+    pub hold_secs: u64,
+    pub exhale_secs: u64,
+}
+
+impl Default for Breathing {
+    fn default() -> Breathing {
+        Breathing {
+            enabled: false,
+            inhale_secs: 4,
+            hold_secs: 2,
+            exhale_secs: 6,
+        }
+    }
+}
+
+/// Broker the state is published to when built with the `mqtt` feature.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Mqtt {
+    pub host: String,
+    pub port: u16,
+    pub topic: String,
+    pub client_id: String,
+}
+
+impl Default for Mqtt {
+    fn default() -> Mqtt {
+        Mqtt {
+            host: String::from("localhost"),
+            port: 1883,
+            topic: String::from("pomidor"),
+            client_id: String::from("pomidor"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Text shown in place of the digits when a work session completes.
+    /// An empty string disables the celebration.
+    pub celebration_text: String,
+    /// How long the celebration stays on screen, in seconds.
+    pub celebration_secs: u64,
+    /// Text shown in place of the digits once the celebration is over,
+    /// until the finished session is acknowledged; `00:00` stays otherwise.
+    pub done_text: Option<String>,
+    /// Figlet font file (`.flf`) the banner is drawn in, read at startup;
+    /// the standard font when unset.
+    pub font: Option<PathBuf>,
+    /// Blank columns between the letters of the banner.
+    pub digit_spacing: usize,
+    /// Times the banner is magnified, each character drawn as a block of
+    /// that many columns and lines; smaller when the terminal is too small.
+    pub banner_scale: u16,
+    /// Magnify the banner as far as the terminal allows instead, taking
+    /// `banner_scale` out of play.
+    pub auto_fill: bool,
+    /// Show the percentage of the session already elapsed.
+    pub show_percent: bool,
+    /// Show the elapsed time of the session.
+    pub show_elapsed: bool,
+    /// Show the total length of the session.
+    pub show_total: bool,
+    /// Show a thin bar under the banner filling up over each minute.
+    pub minute_progress: bool,
+    /// Background of the banner area, e.g. "blue" or "#1e1e2e".
+    #[serde(with = "color::option")]
+    pub banner_bg: Option<Color>,
+    /// `gray`, `adaptive` (the terminal's own foreground) or the name of a
+    /// file in the themes directory.
+    pub theme: String,
+    /// Themes read from the themes directory, by name.
+    #[serde(skip)]
+    pub themes: BTreeMap<String, Theme>,
+    /// Where the banner text sits.
+    pub alignment: Align,
+    /// Line style of the borders.
+    pub border_style: BorderStyle,
+    /// Color of the borders (the terminal's default by default).
+    #[serde(with = "color::option")]
+    pub border_color: Option<Color>,
+    /// Renders without any colors; also enabled by the `NO_COLOR` variable.
+    pub monochrome: bool,
+    /// Draw in the normal screen buffer instead of the alternate screen, so
+    /// the last frame stays in the scrollback after quitting.
+    pub inline: bool,
+    /// Show the time left and the kind of session in the terminal title.
+    pub terminal_title: bool,
+    /// Start out with everything but the digits dimmed.
+    pub focus_mode: bool,
+    /// Start out showing seconds; minutes only otherwise.
+    pub show_seconds: bool,
+    /// Round partial minutes up when showing minutes only, so the digits
+    /// never read 0 while time remains; truncate them otherwise.
+    pub round_up_minutes: bool,
+    /// Show tenths of a second during the last ten seconds, and throughout
+    /// sessions shorter than a minute.
+    pub tenths: bool,
+    /// Whether the digits start out showing remaining or elapsed time.
+    pub display: DisplayMode,
+    /// Reacts to the terminal losing focus during work sessions when set;
+    /// breaks are left alone.
+    pub focus_lost: Option<FocusLost>,
+    /// Older name of `strict` with `strict_escape = "alt"`, still read.
+    #[serde(skip_serializing)]
+    pub focus_lock: bool,
+    /// Refuse to pause, restart, shorten, stop or quit during a work
+    /// session, unless `strict_escape` gets the key through or the session
+    /// is abandoned from the palette.
+    pub strict: bool,
+    pub strict_escape: StrictEscape,
+    /// Display of the listed session kinds, e.g. breaks counting up while
+    /// work counts down.
+    pub kind_display: HashMap<SessionKind, DisplayMode>,
+    /// Keep counting once a session reaches zero, until it's acknowledged.
+    pub overtime: bool,
+    pub overtime_style: OvertimeStyle,
+    /// Ring the terminal bell when a session completes.
+    pub bell: bool,
+    /// Never ring any bell, flashing the screen instead.
+    pub silent: bool,
+    /// Announces completed sessions outside the TUI when set.
+    pub notifications: Option<Notifications>,
+    /// Body of the notifications; `{kind}`, `{timer}`, `{label}` and
+    /// `{done_text}` are filled in.
+    pub notification_body: String,
+    /// Sound files played when a session of the listed kinds completes,
+    /// in place of the first ring of the alarm.
+    pub sounds: HashMap<SessionKind, PathBuf>,
+    /// Ring once this many seconds before a break ends.
+    pub break_end_warning_secs: Option<u64>,
+    /// Show something to do beneath the countdown during breaks.
+    pub break_suggestions: bool,
+    /// Breathing pacer shown under the countdown during breaks.
+    pub breathing: Breathing,
+    /// What breaks suggest, one picked at random per break.
+    pub suggestions: Vec<String>,
+    /// File of suggestions, one per line, replacing `suggestions` when set.
+    pub suggestions_file: Option<PathBuf>,
+    /// Steps the alarm escalates through while a finished session waits to
+    /// be acknowledged.
+    pub alarm: Vec<AlarmStep>,
+    /// Ring the alarm again this often until the session is acknowledged.
+    pub alarm_repeat_secs: Option<u64>,
+    /// Sessions stopped or reset within this many seconds are taken for
+    /// misfires and left out of the history and the stats.
+    pub misfire_secs: u64,
+    /// The `round_up` key brings the remaining time up to a multiple of
+    /// this many minutes; 0 turns it off.
+    pub round_minutes: u64,
+    /// The history log moves to the rotated log of its quarter once it
+    /// reaches this size, besides when the quarter is over; 0 for no limit.
+    pub history_max_bytes: u64,
+    /// Day the weeks of `report --week` start on.
+    pub week_start: WeekStart,
+    /// What running sessions do when the clock jumps forward.
+    pub clock_jump: ClockJump,
+    /// Pause the session when an interruption is recorded.
+    pub pause_on_interruption: bool,
+    /// Styles of the digits as time runs out; the lowest threshold that
+    /// applies wins.
+    pub warnings: Vec<Warning>,
+    /// Window during which sounds are suppressed.
+    pub quiet_hours: Option<QuietHours>,
+    /// Show wall-clock times in 24-hour format rather than 12-hour.
+    pub clock_24h: bool,
+    /// Title of the edit box, instead of the one of the locale.
+    pub input_title: Option<String>,
+    /// Dimmed hint shown while the edit box is empty, instead of the one of
+    /// the locale.
+    pub input_placeholder: Option<String>,
+    /// Language of the UI; `LANG` decides when unset.
+    pub lang: Option<String>,
+    /// Locale file used whatever the language, instead of
+    /// `locales/<lang>.toml` next to the config.
+    pub locale_file: Option<PathBuf>,
+    /// UI strings replacing those of the locale, by key.
+    pub strings: HashMap<String, String>,
+    /// Bare numbers typed as a duration are seconds below this and minutes
+    /// from it on; always minutes when 0.
+    pub bare_seconds_below: u64,
+    /// Duration the timers start out with, so a session can be started
+    /// without typing one; `00:00` leaves them empty.
+    #[serde(with = "duration")]
+    pub default_duration: Duration,
+    /// Quit this many seconds after a session completes unless a key is
+    /// pressed in the meantime.
+    pub exit_after_finish_secs: Option<u64>,
+    /// Printed by `pomidor status --format` when nothing is running.
+    pub status_idle: String,
+    /// Names of the timers opened at startup; a single one when empty.
+    pub timers: Vec<String>,
+    /// Named durations listed on the presets screen, e.g. `tea = "03:00"`.
+    pub presets: BTreeMap<String, String>,
+    /// Durations the listed keys start right away while no session runs.
+    #[serde(with = "duration::map")]
+    pub quick_start: HashMap<char, Duration>,
+    /// Org file completed work sessions are clocked into when set.
+    pub org_file: Option<PathBuf>,
+    /// Heading the clock lines go under, unless the session has a label.
+    pub org_heading: String,
+    /// Asks what was accomplished when a work session ends and keeps the
+    /// answer in the history log.
+    pub note_prompt: bool,
+    /// Cycles between work and breaks when set.
+    pub cycle: Option<Cycle>,
+    /// Named routines, started with `pomidor run <name>` or the `routine`
+    /// command.
+    #[serde(rename = "routine")]
+    pub routines: BTreeMap<String, Routine>,
+    /// Publishes state changes over MQTT when set.
+    pub mqtt: Option<Mqtt>,
+    /// Keys replacing the default bindings of the listed actions.
+    pub keys: HashMap<Action, Keys>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            celebration_text: String::from("DONE!"),
+            celebration_secs: 5,
+            done_text: None,
+            font: None,
+            digit_spacing: 0,
+            banner_scale: 1,
+            auto_fill: false,
+            show_percent: true,
+            show_elapsed: true,
+            show_total: true,
+            minute_progress: false,
+            banner_bg: None,
+            theme: String::from("gray"),
+            themes: BTreeMap::new(),
+            alignment: Align::Center,
+            border_style: BorderStyle::Plain,
+            border_color: None,
+            monochrome: false,
+            inline: false,
+            terminal_title: true,
+            focus_mode: false,
+            show_seconds: true,
+            round_up_minutes: true,
+            tenths: false,
+            display: DisplayMode::Remaining,
+            focus_lost: None,
+            focus_lock: false,
+            strict: false,
+            strict_escape: StrictEscape::Hold,
+            kind_display: HashMap::new(),
+            overtime: false,
+            overtime_style: OvertimeStyle::Plus,
+            bell: true,
+            silent: false,
+            notifications: None,
+            notification_body: String::from("{kind} finished"),
+            sounds: HashMap::new(),
+            break_end_warning_secs: None,
+            break_suggestions: true,
+            breathing: Breathing::default(),
+            suggestions: [
+                "stand up and stretch",
+                "refill water",
+                "look out the window for 20 seconds",
+                "walk around the room",
+                "roll your shoulders",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            suggestions_file: None,
+            alarm: vec![AlarmStep {
+                after_secs: 0,
+                bells: 1,
+                flash: false,
+                notify: false,
+            }],
+            alarm_repeat_secs: None,
+            misfire_secs: 30,
+            round_minutes: 5,
+            history_max_bytes: 1024 * 1024,
+            week_start: WeekStart::Monday,
+            clock_jump: ClockJump::Freeze,
+            pause_on_interruption: false,
+            warnings: vec![Warning {
+                threshold_secs: 60,
+                color: Some(Color::Red),
+                bold: false,
+                blink: false,
+            }],
+            quiet_hours: None,
+            clock_24h: true,
+            input_title: None,
+            input_placeholder: None,
+            lang: None,
+            locale_file: None,
+            strings: HashMap::new(),
+            bare_seconds_below: 0,
+            default_duration: Duration::from_secs(25 * 60),
+            exit_after_finish_secs: None,
+            status_idle: String::from("idle"),
+            timers: Vec::new(),
+            presets: BTreeMap::new(),
+            quick_start: [('1', 25), ('2', 5), ('3', 15), ('4', 50)]
+                .into_iter()
+                .map(|(key, minutes)| (key, Duration::from_secs(minutes * 60)))
+                .collect(),
+            org_file: None,
+            org_heading: String::from("Pomodoro"),
+            note_prompt: false,
+            cycle: None,
+            routines: BTreeMap::new(),
+            mqtt: None,
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// The theme file picked with `theme`, if it isn't a built-in one.
+    fn theme_file(&self) -> Option<&Theme> {
+        self.themes.get(&self.theme)
+    }
+
+    /// Reads the theme files, warning about the ones that don't read and
+    /// about a `theme` that names none.
+    pub fn load_themes(&mut self) -> Vec<String> {
+        let (themes, mut warnings) = theme::load_all();
+        self.themes = themes;
+        if !self.has_theme(&self.theme) {
+            warnings.push(format!("theme: unknown theme `{}`", self.theme));
+        }
+        warnings
+    }
+
+    pub fn has_theme(&self, name: &str) -> bool {
+        theme::BUILT_IN.contains(&name) || self.themes.contains_key(name)
+    }
+
+    /// Style of the text outside of warnings.
+    pub fn text_style(&self) -> Style {
+        match (
+            self.theme_file().and_then(|theme| theme.fg),
+            self.theme.as_str(),
+        ) {
+            (Some(color), _) => Style::default().fg(color),
+            (None, "adaptive") => Style::default(),
+            (None, _) => Style::default().fg(Color::Gray),
+        }
+    }
+
+    /// Style of the digits under `warning`, in the theme's warning color
+    /// unless the warning has its own.
+    pub fn warning_style(&self, warning: &Warning) -> Style {
+        let mut style = warning.style(self.monochrome);
+        let warn = self.theme_file().and_then(|theme| theme.warn);
+        if let (None, Some(color), false) = (warning.color, warn, self.monochrome) {
+            style = style.fg(color);
+        }
+        style
+    }
+
+    pub fn banner_background(&self) -> Option<Color> {
+        self.theme_file()
+            .and_then(|theme| theme.bg)
+            .or(self.banner_bg)
+    }
+
+    pub fn border_type(&self) -> BorderType {
+        self.theme_file()
+            .and_then(|theme| theme.border_style)
+            .unwrap_or(self.border_style)
+            .border_type()
+    }
+
+    pub fn border_fg(&self) -> Option<Color> {
+        self.theme_file()
+            .and_then(|theme| theme.border_color)
+            .or(self.border_color)
+    }
+
+    pub fn text_alignment(&self) -> Alignment {
+        self.theme_file()
+            .and_then(|theme| theme.alignment)
+            .unwrap_or(self.alignment)
+            .alignment()
+    }
+
+    /// Duration a session of `kind` starts out with.
+    pub fn initial_duration(&self, kind: SessionKind) -> Duration {
+        self.cycle
+            .map_or(self.default_duration, |cycle| cycle.duration(kind))
+    }
+
+    /// What the digits start out showing during a session of `kind`.
+    pub fn display_for(&self, kind: SessionKind) -> DisplayMode {
+        self.kind_display
+            .get(&kind)
+            .copied()
+            .unwrap_or(self.display)
+    }
+
+    /// Switches to the settings of the routine `name`, all of them or none
+    /// when one is invalid.
+    pub fn apply_routine(&mut self, name: &str) -> Result<&Routine, String> {
+        let routine = self
+            .routines
+            .get(name)
+            .ok_or_else(|| format!("no routine `{}`", name))?;
+        let cycle = routine.cycle(name, self.cycle.unwrap_or_default())?;
+
+        self.cycle = Some(cycle);
+        if let Some(bell) = routine.bell {
+            self.bell = bell;
+        }
+        self.sounds.extend(routine.sounds.clone());
+        Ok(routine)
+    }
+
+    /// Loads the config file, falling back to defaults when it doesn't exist.
+    /// Under a profile, the profile's own config file goes on top.
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        let mut table = toml::Table::new();
+        let mut paths = vec![shared_config_path(), config_path()];
+        paths.dedup();
+        for path in paths.into_iter().flatten() {
+            if path.exists() {
+                let content = fs::read_to_string(&path)?;
+                // Parsed as a config first for errors that point into the file.
+                toml::from_str::<Config>(&content)
+                    .map_err(|err| format!("invalid config {}: {}", path.display(), err))?;
+                merge(&mut table, toml::from_str(&content)?);
+            }
+        }
+        let mut config: Config = toml::Value::Table(table)
+            .try_into()
+            .map_err(|err| format!("invalid config: {}", err))?;
+
+        let base = config.cycle.unwrap_or_default();
+        for (name, routine) in &config.routines {
+            routine.cycle(name, base)?;
+        }
+
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            config.monochrome = true;
+        }
+        if config.focus_lock {
+            config.strict = true;
+            config.strict_escape = StrictEscape::Alt;
+        }
+
+        Ok(config)
+    }
+}
+
+impl Config {
+    /// Layers `POMIDOR_*` environment variables over the loaded config:
+    /// `POMIDOR_WORK`, `POMIDOR_SHORT_BREAK` and `POMIDOR_LONG_BREAK` as
+    /// `hh:mm:ss` or `mm:ss`, and `POMIDOR_KEY_<ACTION>` as space-separated
+    /// keys, e.g. `POMIDOR_KEY_QUIT=x`. Invalid values are left out and
+    /// returned as warnings.
+    pub fn apply_env(&mut self) -> Vec<String> {
+        self.apply_vars(env::vars())
+    }
+
+    /// Checks that every file of `sounds` and of the routines is a regular
+    /// file that can be read, dropping the ones that aren't, so their
+    /// sessions ring the bell, or the sound of `[sounds]` in a routine,
+    /// instead. Returns warnings about the dropped ones.
+    pub fn check_sounds(&mut self) -> Vec<String> {
+        let mut warnings = retain_sounds(&mut self.sounds, "");
+        for (name, routine) in &mut self.routines {
+            let place = format!(" in routine `{}`", name);
+            warnings.extend(retain_sounds(&mut routine.sounds, &place));
+        }
+        warnings
+    }
+
+    fn apply_vars(&mut self, vars: impl Iterator<Item = (String, String)>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut keys = self.keys.clone();
+
+        for (name, value) in vars {
+            if let Some(action) = name.strip_prefix(ENV_KEY_PREFIX) {
+                let action = match Action::from_name(&action.to_lowercase()) {
+                    Some(action) => action,
+                    None => {
+                        warnings.push(format!("{}: unknown action", name));
+                        continue;
+                    }
+                };
+                let names: Vec<String> = value.split_whitespace().map(String::from).collect();
+                match names.iter().find(|key| parse_key(key).is_none()) {
+                    Some(key) => warnings.push(format!("{}: unknown key `{}`", name, key)),
+                    None if names.is_empty() => warnings.push(format!("{}: no keys", name)),
+                    None => {
+                        keys.insert(action, Keys::Many(names));
+                    }
+                }
+                continue;
+            }
+
+            let field = match name.strip_prefix(ENV_PREFIX) {
+                Some(field @ ("WORK" | "SHORT_BREAK" | "LONG_BREAK")) => field,
+                _ => continue,
+            };
+            let duration = match parse_duration(&value) {
+                Some(duration) => duration,
+                None => {
+                    warnings.push(format!(
+                        "{}: invalid duration `{}`, expected hh:mm:ss or mm:ss",
+                        name, value
+                    ));
+                    continue;
+                }
+            };
+
+            // Break lengths only mean something when cycling, which only
+            // the config turns on.
+            match (field, self.cycle.as_mut()) {
+                ("WORK", cycle) => {
+                    self.default_duration = duration;
+                    if let Some(cycle) = cycle {
+                        cycle.work = duration;
+                    }
+                }
+                ("SHORT_BREAK", Some(cycle)) => cycle.short_break = duration,
+                (_, Some(cycle)) => cycle.long_break = duration,
+                (_, None) => warnings.push(format!("{}: ignored without a [cycle] table", name)),
+            }
+        }
+
+        // Keys that clash with each other keep the ones from the file.
+        match Keymap::new(&keys) {
+            Ok(_) => self.keys = keys,
+            Err(err) => warnings.push(format!("{}*: {}", ENV_KEY_PREFIX, err)),
+        }
+
+        warnings
+    }
+}
+
+/// Writes `presets` into the `[presets]` table of the config file, leaving
+/// the rest of the file, comments included, as it is.
+pub fn save_presets(presets: &BTreeMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let path = config_path().ok_or("no config directory")?;
+
+    let content = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let mut document: toml_edit::Document = content
+        .parse()
+        .map_err(|err| format!("invalid config {}: {}", path.display(), err))?;
+
+    if !document.contains_key("presets") {
+        let mut table = toml_edit::Table::new();
+        table.decor_mut().set_prefix("\n");
+        document.insert("presets", toml_edit::Item::Table(table));
+    }
+    let table = document["presets"]
+        .as_table_mut()
+        .ok_or("`presets` in the config is not a table")?;
+    table.clear();
+    for (name, duration) in presets {
+        table.insert(name, toml_edit::value(duration.as_str()));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, document.to_string())?;
+
+    Ok(())
+}
+
+/// Puts the keys of `layer` over the ones of `table`, table by table.
+fn merge(table: &mut toml::Table, layer: toml::Table) {
+    for (key, value) in layer {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(layer)) => merge(table, layer),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+/// `path` with a leading `~` standing for the home directory.
+/// Drops the files of `sounds` that can't be read, returning warnings
+/// about them that name the kind of session and `place`.
+fn retain_sounds(sounds: &mut HashMap<SessionKind, PathBuf>, place: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    sounds.retain(|kind, path| {
+        *path = expand_home(path);
+        match check_sound(path) {
+            Ok(()) => true,
+            Err(err) => {
+                warnings.push(format!(
+                    "sound for {}{}: cannot read {}: {}",
+                    kind.name(),
+                    place,
+                    path.display(),
+                    err
+                ));
+                false
+            }
+        }
+    });
+    warnings
+}
+
+/// Fails unless `path` is a regular file that can be opened.
+fn check_sound(path: &Path) -> io::Result<()> {
+    if !fs::metadata(path)?.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a regular file",
+        ));
+    }
+    fs::File::open(path).map(|_| ())
+}
+
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    // Tests never read or write the user's config.
+    if cfg!(test) {
+        return None;
+    }
+    dirs::config_dir().map(|dir| dir.join("pomidor"))
+}
+
+/// The config file of the current profile, which is the shared one under
+/// the default profile.
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| profile::dir(dir).join(CONFIG_FILE))
+}
+
+/// The config file every profile starts from.
+fn shared_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+pub mod color {
+    use ratatui::style::Color;
+
+    pub fn parse(value: &str) -> Option<Color> {
+        let value = value.trim().to_lowercase();
+
+        if let Some(hex) = value.strip_prefix('#') {
+            if hex.len() != 6 {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+
+        let color = match value.replace(['-', '_', ' '], "").as_str() {
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "darkgray" | "darkgrey" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            "white" => Color::White,
+            other => Color::Indexed(other.parse().ok()?),
+        };
+
+        Some(color)
+    }
+
+    pub fn name(color: Color) -> String {
+        match color {
+            Color::Reset => String::from("reset"),
+            Color::Black => String::from("black"),
+            Color::Red => String::from("red"),
+            Color::Green => String::from("green"),
+            Color::Yellow => String::from("yellow"),
+            Color::Blue => String::from("blue"),
+            Color::Magenta => String::from("magenta"),
+            Color::Cyan => String::from("cyan"),
+            Color::Gray => String::from("gray"),
+            Color::DarkGray => String::from("darkgray"),
+            Color::LightRed => String::from("lightred"),
+            Color::LightGreen => String::from("lightgreen"),
+            Color::LightYellow => String::from("lightyellow"),
+            Color::LightBlue => String::from("lightblue"),
+            Color::LightMagenta => String::from("lightmagenta"),
+            Color::LightCyan => String::from("lightcyan"),
+            Color::White => String::from("white"),
+            Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            Color::Indexed(i) => i.to_string(),
+        }
+    }
+
+    pub mod option {
+        use ratatui::style::Color;
+        use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(color: &Option<Color>, s: S) -> Result<S::Ok, S::Error> {
+            match color {
+                Some(color) => s.serialize_str(&super::name(*color)),
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Color>, D::Error> {
+            let value = String::deserialize(d)?;
+            super::parse(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("unknown color `{}`", value)))
+        }
+    }
+}
+
+/// `HH:MM` wall-clock times.
+pub mod clock {
+    use chrono::NaiveTime;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%H:%M";
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&time.format(FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<NaiveTime, D::Error> {
+        let value = String::deserialize(d)?;
+        NaiveTime::parse_from_str(&value, FORMAT)
+            .map_err(|_| D::Error::custom(format!("invalid time `{}`, expected HH:MM", value)))
+    }
+}
+
+/// Durations written as `hh:mm:ss` or `mm:ss`.
+pub mod duration {
+    use std::time::Duration;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::{parse_duration, remain_to_fmt};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&remain_to_fmt(duration.as_secs()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        parse(&String::deserialize(d)?)
+    }
+
+    fn parse<E: Error>(value: &str) -> Result<Duration, E> {
+        parse_duration(value).ok_or_else(|| {
+            E::custom(format!(
+                "invalid duration `{}`, expected hh:mm:ss or mm:ss",
+                value
+            ))
+        })
+    }
+
+    /// Tables of durations, e.g. `1 = "25:00"`.
+    pub mod map {
+        use std::{
+            collections::{BTreeMap, HashMap},
+            fmt::Display,
+            hash::Hash,
+            time::Duration,
+        };
+
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use crate::remain_to_fmt;
+
+        /// Keys are written as strings, which TOML wants even of a `char`,
+        /// in order.
+        pub fn serialize<K, S>(map: &HashMap<K, Duration>, s: S) -> Result<S::Ok, S::Error>
+        where
+            K: Display,
+            S: Serializer,
+        {
+            let sorted: BTreeMap<String, String> = map
+                .iter()
+                .map(|(key, duration)| (key.to_string(), remain_to_fmt(duration.as_secs())))
+                .collect();
+            s.collect_map(sorted)
+        }
+
+        pub fn deserialize<'de, K, D>(d: D) -> Result<HashMap<K, Duration>, D::Error>
+        where
+            K: Deserialize<'de> + Eq + Hash,
+            D: Deserializer<'de>,
+        {
+            HashMap::<K, String>::deserialize(d)?
+                .into_iter()
+                .map(|(key, value)| Ok((key, super::parse(&value)?)))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_by_name_hex_or_index() {
+        assert_eq!(color::parse("Dark-Gray"), Some(Color::DarkGray));
+        assert_eq!(color::parse("#1e1e2e"), Some(Color::Rgb(30, 30, 46)));
+        assert_eq!(color::parse("208"), Some(Color::Indexed(208)));
+        assert_eq!(color::parse("#1e1e2"), None);
+        assert_eq!(color::parse("256"), None);
+        assert_eq!(color::parse("teal"), None);
+    }
+
+    #[test]
+    fn colors_are_written_back_as_read() {
+        for value in ["darkgray", "#1e1e2e", "208"] {
+            assert_eq!(color::name(color::parse(value).unwrap()), value);
+        }
+    }
+
+    #[test]
+    fn banner_background_is_read_from_the_config() {
+        let config: Config = toml::from_str("banner_bg = \"blue\"").unwrap();
+        assert_eq!(config.banner_bg, Some(Color::Blue));
+        assert!(toml::from_str::<Config>("banner_bg = \"teal\"").is_err());
+    }
+
+    #[test]
+    fn quiet_hours_may_wrap_around_midnight() {
+        let at = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+        let config: Config =
+            toml::from_str("[quiet_hours]\nstart = \"22:00\"\nend = \"07:00\"").unwrap();
+        let night = config.quiet_hours.unwrap();
+        assert!(night.contains(at(23, 30)));
+        assert!(night.contains(at(6, 59)));
+        assert!(!night.contains(at(7, 0)));
+        assert!(!night.contains(at(12, 0)));
+
+        let lunch = QuietHours {
+            start: at(12, 0),
+            end: at(13, 0),
+        };
+        assert!(lunch.contains(at(12, 0)));
+        assert!(!lunch.contains(at(13, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_are_hours_and_minutes() {
+        let hours_only = "[quiet_hours]\nstart = \"22\"\nend = \"07:00\"";
+        assert!(toml::from_str::<Config>(hours_only).is_err());
+    }
+
+    #[test]
+    fn edit_box_texts_default_when_missing() {
+        let config: Config = toml::from_str("input_title = \"Minutes\"").unwrap();
+        assert_eq!(config.input_title.as_deref(), Some("Minutes"));
+        assert_eq!(config.input_placeholder, None);
+    }
+
+    #[test]
+    fn warnings_leave_out_colors_in_monochrome() {
+        let config: Config =
+            toml::from_str("[[warnings]]\nthreshold_secs = 10\ncolor = \"red\"\nbold = true")
+                .unwrap();
+        let warning = config.warnings[0];
+        assert_eq!(warning.style(false).fg, Some(Color::Red));
+        assert_eq!(warning.style(true).fg, None);
+        assert!(warning.style(true).add_modifier.contains(Modifier::BOLD));
+        assert!(!warning.blink);
+    }
+
+    #[test]
+    fn mqtt_broker_fills_in_the_defaults() {
+        assert!(Config::default().mqtt.is_none());
+        let config: Config = toml::from_str("[mqtt]\nhost = \"broker.lan\"").unwrap();
+        let mqtt = config.mqtt.unwrap();
+        assert_eq!(mqtt.host, "broker.lan");
+        assert_eq!(mqtt.port, 1883);
+        assert_eq!(mqtt.topic, "pomidor");
+    }
+
+    #[test]
+    fn cycle_durations_come_from_the_config() {
+        let config: Config =
+            toml::from_str("[cycle]\nwork = \"50:00\"\nlong_break_interval = 3").unwrap();
+        let cycle = config.cycle.unwrap();
+        assert_eq!(cycle.duration(SessionKind::Work), Duration::from_secs(3000));
+        assert_eq!(cycle.long_break, Duration::from_secs(900));
+        assert_eq!(cycle.long_break_interval, 3);
+        assert!(toml::from_str::<Config>("[cycle]\nwork = \"50\"").is_err());
+    }
+
+    #[test]
+    fn borders_default_to_the_plain_look() {
+        assert_eq!(Config::default().border_style, BorderStyle::Plain);
+        let config: Config = toml::from_str("border_style = \"double\"").unwrap();
+        assert_eq!(config.border_style.border_type(), BorderType::Double);
+        assert!(toml::from_str::<Config>("border_style = \"dotted\"").is_err());
+    }
+
+    #[test]
+    fn kinds_without_a_display_of_their_own_use_the_default() {
+        let text = "display = \"remaining\"\n[kind_display]\nshort_break = \"elapsed\"";
+        let config: Config = toml::from_str(text).unwrap();
+        let displays: Vec<DisplayMode> = [
+            SessionKind::Work,
+            SessionKind::ShortBreak,
+            SessionKind::LongBreak,
+        ]
+        .into_iter()
+        .map(|kind| config.display_for(kind))
+        .collect();
+        let (remaining, elapsed) = (DisplayMode::Remaining, DisplayMode::Elapsed);
+        assert_eq!(displays, [remaining, elapsed, remaining]);
+        assert!(toml::from_str::<Config>("[kind_display]\nlunch = \"elapsed\"").is_err());
+    }
+
+    #[test]
+    fn sessions_start_out_with_the_cycle_or_the_default_duration() {
+        let mut config = Config {
+            default_duration: Duration::from_secs(600),
+            ..Config::default()
+        };
+        let minutes = |config: &Config, kind| config.initial_duration(kind).as_secs() / 60;
+        assert_eq!(minutes(&config, SessionKind::ShortBreak), 10);
+        config.cycle = Some(Cycle::default());
+        assert_eq!(minutes(&config, SessionKind::Work), 25);
+        assert_eq!(minutes(&config, SessionKind::ShortBreak), 5);
+    }
+
+    #[test]
+    fn adaptive_theme_leaves_the_foreground_to_the_terminal() {
+        let gray = Style::default().fg(Color::Gray);
+        assert_eq!(Config::default().text_style(), gray);
+        let config: Config = toml::from_str("theme = \"adaptive\"").unwrap();
+        assert_eq!(config.text_style(), Style::default());
+    }
+
+    #[test]
+    fn quick_start_keys_take_durations() {
+        let config: Config = toml::from_str("[quick_start]\n7 = \"07:30\"").unwrap();
+        assert_eq!(config.quick_start[&'7'], Duration::from_secs(450));
+        assert_eq!(config.quick_start.len(), 1);
+        assert!(toml::from_str::<Config>("[quick_start]\n7 = \"soon\"").is_err());
+    }
+
+    #[test]
+    fn routines_fill_in_from_the_cycle_and_name_bad_keys() {
+        let mut config: Config = toml::from_str(
+            "[cycle]\nwork = \"25:00\"\ncycles = 4\n\n[routine.writing]\nwork = \"50m\"\nbreak = \"10m\"\nbell = false\n",
+        )
+        .unwrap();
+        let routine = config.apply_routine("writing").unwrap();
+        assert_eq!(routine.label, None);
+        let cycle = config.cycle.unwrap();
+        assert_eq!(cycle.work, Duration::from_secs(3000));
+        assert_eq!(cycle.short_break, Duration::from_secs(600));
+        assert_eq!(cycle.cycles, Some(4));
+        assert!(!config.bell);
+
+        let bad = Routine {
+            long_break: Some(String::from("soon")),
+            ..Routine::default()
+        };
+        let err = bad.cycle("writing", Cycle::default()).unwrap_err();
+        assert!(err.starts_with("routine `writing`: invalid `long_break` duration `soon`"));
+        assert!(config.apply_routine("reading").is_err());
+    }
+
+    #[test]
+    fn sounds_must_be_readable_regular_files() {
+        let dir = std::env::temp_dir().join(format!("pomidor-sounds-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let chime = dir.join("chime.wav");
+        fs::write(&chime, b"RIFF").unwrap();
+
+        let mut config = Config {
+            sounds: HashMap::from([
+                (SessionKind::Work, chime.clone()),
+                (SessionKind::ShortBreak, dir.clone()),
+                (SessionKind::LongBreak, dir.join("missing.wav")),
+            ]),
+            ..Config::default()
+        };
+        let mut warnings = config.check_sounds();
+        warnings.sort();
+
+        assert_eq!(config.sounds, HashMap::from([(SessionKind::Work, chime)]));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("sound for long break: cannot read"));
+        assert!(warnings[1].ends_with("not a regular file"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn break_variables_leave_cycling_off() {
+        let mut config = Config::default();
+        let warnings = config.apply_vars(vars(&[
+            ("POMIDOR_WORK", "50:00"),
+            ("POMIDOR_SHORT_BREAK", "10:00"),
+            ("POMIDOR_LONG_BREAK", "20:00"),
+        ]));
+
+        assert!(config.cycle.is_none());
+        assert_eq!(config.default_duration, Duration::from_secs(3000));
+        assert_eq!(
+            warnings,
+            [
+                "POMIDOR_SHORT_BREAK: ignored without a [cycle] table",
+                "POMIDOR_LONG_BREAK: ignored without a [cycle] table"
+            ]
+        );
+    }
+
+    #[test]
+    fn variables_override_the_cycle() {
+        let mut config = Config {
+            cycle: Some(Cycle::default()),
+            ..Config::default()
+        };
+        let warnings = config.apply_vars(vars(&[
+            ("POMIDOR_WORK", "50:00"),
+            ("POMIDOR_SHORT_BREAK", "10:00"),
+            ("POMIDOR_LONG_BREAK", "bad"),
+        ]));
+
+        let cycle = config.cycle.unwrap();
+        assert_eq!(cycle.work, Duration::from_secs(3000));
+        assert_eq!(cycle.short_break, Duration::from_secs(600));
+        assert_eq!(cycle.long_break, Cycle::default().long_break);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn key_variables_rebind_actions() {
+        let mut config = Config::default();
+        let warnings = config.apply_vars(vars(&[
+            ("POMIDOR_KEY_QUIT", "x esc"),
+            ("POMIDOR_KEY_NAP", "n"),
+            ("POMIDOR_KEY_STOP", "hyper"),
+        ]));
+
+        assert!(matches!(&config.keys[&Action::Quit], Keys::Many(keys) if keys == &["x", "esc"]));
+        assert_eq!(config.keys.len(), 1);
+        assert_eq!(
+            warnings,
+            [
+                "POMIDOR_KEY_NAP: unknown action",
+                "POMIDOR_KEY_STOP: unknown key `hyper`"
+            ]
+        );
+
+        // Keys taken by other actions leave the file's bindings alone.
+        let warnings = config.apply_vars(vars(&[("POMIDOR_KEY_RESET", "x")]));
+        assert_eq!(warnings.len(), 1);
+        assert!(!config.keys.contains_key(&Action::Reset));
+    }
+
+    #[test]
+    fn routine_sounds_are_checked_and_applied() {
+        let dir = std::env::temp_dir().join(format!("pomidor-routine-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (alert, chime) = (dir.join("alert.wav"), dir.join("chime.wav"));
+        fs::write(&alert, b"RIFF").unwrap();
+        fs::write(&chime, b"RIFF").unwrap();
+
+        let routine = Routine {
+            sounds: HashMap::from([
+                (SessionKind::Work, chime.clone()),
+                (SessionKind::ShortBreak, dir.join("missing.wav")),
+            ]),
+            ..Routine::default()
+        };
+        let mut config = Config {
+            sounds: HashMap::from([
+                (SessionKind::Work, alert.clone()),
+                (SessionKind::ShortBreak, alert.clone()),
+            ]),
+            routines: BTreeMap::from([(String::from("writing"), routine)]),
+            ..Config::default()
+        };
+        let warnings = config.check_sounds();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("sound for short break in routine `writing`: cannot read"));
+
+        config.apply_routine("writing").unwrap();
+        assert_eq!(
+            config.sounds,
+            HashMap::from([(SessionKind::Work, chime), (SessionKind::ShortBreak, alert)])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn routine_sounds_are_read_by_kind() {
+        let config: Config =
+            toml::from_str("[routine.writing.sounds]\nwork = \"~/sounds/alert.wav\"\n").unwrap();
+        assert_eq!(
+            config.routines["writing"].sounds,
+            HashMap::from([(SessionKind::Work, PathBuf::from("~/sounds/alert.wav"))])
+        );
+    }
+
+    #[test]
+    fn profile_config_goes_over_the_shared_one() {
+        let mut table: toml::Table = toml::from_str(
+            "celebration_secs = 5\nlang = \"en\"\n[cycle]\nwork = \"25:00\"\nlong_break = \"15:00\"",
+        )
+        .unwrap();
+        let layer = toml::from_str("celebration_secs = 50\n[cycle]\nwork = \"50:00\"").unwrap();
+        merge(&mut table, layer);
+
+        let merged: toml::Table = toml::from_str(
+            "celebration_secs = 50\nlang = \"en\"\n[cycle]\nwork = \"50:00\"\nlong_break = \"15:00\"",
+        )
+        .unwrap();
+        assert_eq!(table, merged);
+    }
+
+    #[test]
+    fn theme_files_go_over_the_config() {
+        let mut config = Config {
+            theme: String::from("nord"),
+            border_color: Some(Color::Red),
+            ..Config::default()
+        };
+        let nord = Theme {
+            fg: Some(Color::Cyan),
+            alignment: Some(Align::Left),
+            ..Theme::default()
+        };
+        config.themes.insert(String::from("nord"), nord);
+        assert_eq!(config.text_style(), Style::default().fg(Color::Cyan));
+        assert_eq!(config.text_alignment(), Alignment::Left);
+        assert_eq!(config.border_fg(), Some(Color::Red));
+        assert!(config.has_theme("adaptive"));
+        assert!(!config.has_theme("solarized"));
+    }
+
+    #[test]
+    fn dumped_config_reads_back() {
+        let config = Config {
+            quick_start: HashMap::from([
+                ('2', Duration::from_secs(300)),
+                ('1', Duration::from_secs(1500)),
+            ]),
+            ..Config::default()
+        };
+        let dumped = toml::to_string(&config).unwrap();
+        assert!(dumped.contains("[quick_start]\n1 = \"25:00\"\n2 = \"05:00\"\n"));
+
+        let read: Config = toml::from_str(&dumped).unwrap();
+        assert_eq!(read.quick_start, config.quick_start);
+        assert_eq!(toml::to_string(&read).unwrap(), dumped);
+    }
+}
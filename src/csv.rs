@@ -0,0 +1,383 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeZone};
+
+use crate::{
+    delay_arg,
+    history::{self, hashtags, split_tags, Entry, Outcome},
+    timer::SessionKind,
+};
+
+/// Sessions whose starts are this close are taken for the same session.
+const DUPLICATE_SECS: i64 = 60;
+
+const HEADER: &str = "start,end,duration_secs,kind,outcome,label";
+/// Columns of `export`, as `--map` takes them.
+pub const EXPORT_MAP: &str = "start=0,end=1,duration=2,kind=3,outcome=4,label=5";
+
+/// Which column holds which field of a session.
+#[derive(Debug, Clone, Default)]
+pub struct Columns {
+    start: usize,
+    duration: Option<usize>,
+    end: Option<usize>,
+    label: Option<usize>,
+    kind: Option<usize>,
+    outcome: Option<usize>,
+}
+
+impl Columns {
+    /// Parses `start=0,duration=1,label=2`: `start` and either `duration`
+    /// or `end` are needed; `label`, `kind` and `outcome` are optional.
+    pub fn parse(map: &str) -> Result<Columns, String> {
+        let mut start = None;
+        let mut columns = Columns::default();
+
+        for pair in map
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (field, column) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected field=column, got `{}`", pair))?;
+            let column: usize = column
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid column `{}` for `{}`", column, field))?;
+            match field.trim() {
+                "start" => start = Some(column),
+                "duration" => columns.duration = Some(column),
+                "end" => columns.end = Some(column),
+                "label" => columns.label = Some(column),
+                "kind" => columns.kind = Some(column),
+                "outcome" => columns.outcome = Some(column),
+                field => return Err(format!("unknown field `{}`", field)),
+            }
+        }
+
+        columns.start = start.ok_or("the map needs `start`")?;
+        if columns.duration.is_none() && columns.end.is_none() {
+            return Err(String::from("the map needs `duration` or `end`"));
+        }
+        Ok(columns)
+    }
+}
+
+/// Outcome of an import.
+#[derive(Debug, Default)]
+pub struct Import {
+    pub entries: Vec<Entry>,
+    /// Rows that couldn't be read, with their number and why.
+    pub malformed: Vec<(usize, String)>,
+    /// Rows of sessions already in the history.
+    pub duplicates: usize,
+}
+
+/// Reads the sessions of a CSV file laid out as `columns`. A first row
+/// whose start isn't a time is taken for a header. Rows starting within a
+/// minute of a session of `existing`, or of an earlier row, are left out.
+pub fn import(content: &str, columns: &Columns, existing: &[Entry]) -> Import {
+    let mut import = Import::default();
+    let mut starts: Vec<i64> = existing
+        .iter()
+        .map(|entry| entry.start.timestamp())
+        .collect();
+
+    for (index, row) in records(content).into_iter().enumerate() {
+        let number = index + 1;
+        if row.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let entry = match read_row(&row, columns) {
+            Ok(entry) => entry,
+            Err(_) if index == 0 => continue,
+            Err(err) => {
+                import.malformed.push((number, err));
+                continue;
+            }
+        };
+
+        let start = entry.start.timestamp();
+        if starts
+            .iter()
+            .any(|other| (other - start).abs() <= DUPLICATE_SECS)
+        {
+            import.duplicates += 1;
+            continue;
+        }
+        starts.push(start);
+        import.entries.push(entry);
+    }
+
+    import
+}
+
+fn read_row(row: &[String], columns: &Columns) -> Result<Entry, String> {
+    let field = |column: usize| -> Result<&str, String> {
+        row.get(column)
+            .map(|field| field.trim())
+            .ok_or_else(|| format!("no column {}", column))
+    };
+    let optional = |column: Option<usize>| -> Option<&str> {
+        column
+            .and_then(|column| row.get(column))
+            .map(|field| field.trim())
+            .filter(|field| !field.is_empty())
+    };
+
+    let start = field(columns.start)?;
+    let start = parse_time(start).ok_or_else(|| format!("invalid start `{}`", start))?;
+
+    let duration_secs = match (columns.duration, columns.end) {
+        (Some(column), _) => {
+            let duration = field(column)?;
+            delay_arg(duration)
+                .map_err(|_| format!("invalid duration `{}`", duration))?
+                .as_secs()
+        }
+        (None, Some(column)) => {
+            let end = field(column)?;
+            let end = parse_time(end).ok_or_else(|| format!("invalid end `{}`", end))?;
+            u64::try_from((end - start).num_seconds())
+                .map_err(|_| String::from("ends before it starts"))?
+        }
+        (None, None) => unreachable!("checked by Columns::parse"),
+    };
+    if duration_secs == 0 {
+        return Err(String::from("zero duration"));
+    }
+
+    let kind = match optional(columns.kind) {
+        Some(kind) => parse_kind(kind).ok_or_else(|| format!("invalid kind `{}`", kind))?,
+        None => SessionKind::Work,
+    };
+    let outcome = match optional(columns.outcome) {
+        Some(outcome) => {
+            parse_outcome(outcome).ok_or_else(|| format!("invalid outcome `{}`", outcome))?
+        }
+        None => Outcome::Completed,
+    };
+    let (label, tags) = optional(columns.label).map(split_tags).unwrap_or_default();
+
+    Ok(Entry {
+        start,
+        end: start + chrono::Duration::seconds(duration_secs as i64),
+        duration_secs,
+        kind,
+        label,
+        tags,
+        outcome,
+        note: None,
+        interruptions: Vec::new(),
+    })
+}
+
+/// RFC 3339, `2024-05-01 10:00[:00]` in local time or a Unix timestamp.
+fn parse_time(text: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(secs) = text.parse::<i64>() {
+        return Local.timestamp_opt(secs, 0).single().map(DateTime::from);
+    }
+    history::timestamp::parse(text).or_else(|| {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M")
+            .ok()
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+            .map(DateTime::from)
+    })
+}
+
+fn parse_kind(text: &str) -> Option<SessionKind> {
+    let text = text.to_lowercase().replace([' ', '-'], "_");
+    serde_json::from_value(serde_json::Value::String(text)).ok()
+}
+
+fn parse_outcome(text: &str) -> Option<Outcome> {
+    serde_json::from_value(serde_json::Value::String(text.to_lowercase())).ok()
+}
+
+/// The sessions as CSV with a header row, laid out as `EXPORT_MAP`; tags
+/// go back into the label.
+pub fn export(entries: &[Entry]) -> String {
+    let mut out = format!("{}\n", HEADER);
+    for entry in entries {
+        let mut label = entry.label.clone().unwrap_or_default();
+        if !entry.tags.is_empty() {
+            if !label.is_empty() {
+                label.push(' ');
+            }
+            label.push_str(&hashtags(&entry.tags));
+        }
+
+        let kind = serde_json::to_value(entry.kind).unwrap_or_default();
+        let fields = [
+            entry.start.to_rfc3339_opts(SecondsFormat::Secs, false),
+            entry.end.to_rfc3339_opts(SecondsFormat::Secs, false),
+            entry.duration_secs.to_string(),
+            kind.as_str().unwrap_or_default().to_string(),
+            entry.outcome.name().to_string(),
+            label,
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| quote(field)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// `field` quoted when it holds a comma, a quote or a line break.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits CSV into rows of fields. Quoted fields may hold commas, line
+/// breaks and doubled quotes.
+fn records(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(start: &str, minutes: i64, kind: SessionKind, outcome: Outcome) -> Entry {
+        let start = DateTime::parse_from_rfc3339(start).unwrap();
+        Entry {
+            start,
+            end: start + chrono::Duration::minutes(minutes),
+            duration_secs: minutes as u64 * 60,
+            kind,
+            label: None,
+            tags: Vec::new(),
+            outcome,
+            note: None,
+            interruptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn export_reads_back_as_the_same_sessions() {
+        let mut quoted = entry(
+            "2024-05-01T10:00:00+02:00",
+            25,
+            SessionKind::Work,
+            Outcome::Completed,
+        );
+        quoted.label = Some(String::from("write \"intro\", then edit"));
+        quoted.tags = vec![String::from("clientA"), String::from("writing")];
+        let mut tags_only = entry(
+            "2024-05-01T11:00:00-05:00",
+            10,
+            SessionKind::Work,
+            Outcome::Abandoned,
+        );
+        tags_only.tags = vec![String::from("admin")];
+        let entries = vec![
+            quoted,
+            tags_only,
+            entry(
+                "2024-05-01T12:00:00+00:00",
+                5,
+                SessionKind::ShortBreak,
+                Outcome::Completed,
+            ),
+            entry(
+                "2024-05-01T13:00:00+02:00",
+                15,
+                SessionKind::LongBreak,
+                Outcome::Restarted,
+            ),
+        ];
+
+        let columns = Columns::parse(EXPORT_MAP).unwrap();
+        let import = import(&export(&entries), &columns, &[]);
+        assert!(import.malformed.is_empty(), "{:?}", import.malformed);
+        assert_eq!(import.duplicates, 0);
+        assert_eq!(import.entries, entries);
+        // Equal instants aren't enough: the offsets come back too.
+        let starts: Vec<String> = import
+            .entries
+            .iter()
+            .map(|entry| entry.start.to_rfc3339())
+            .collect();
+        assert_eq!(starts[1], "2024-05-01T11:00:00-05:00");
+    }
+
+    #[test]
+    fn exporting_nothing_gives_the_header() {
+        assert_eq!(export(&[]), format!("{}\n", HEADER));
+        let columns = Columns::parse(EXPORT_MAP).unwrap();
+        assert!(import(&export(&[]), &columns, &[]).entries.is_empty());
+    }
+
+    #[test]
+    fn quoted_fields_hold_separators() {
+        assert_eq!(quote("a, \"b\""), "\"a, \"\"b\"\"\"");
+        assert_eq!(
+            records("x,\"a, \"\"b\"\"\nc\"\r\ny,\n"),
+            [
+                vec![String::from("x"), String::from("a, \"b\"\nc")],
+                vec![String::from("y"), String::new()],
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_are_mapped_and_bad_or_repeated_ones_left_out() {
+        let columns = Columns::parse("start=0,duration=1,label=2").unwrap();
+        let existing = [entry(
+            "2024-05-01T09:00:00+02:00",
+            25,
+            SessionKind::Work,
+            Outcome::Completed,
+        )];
+        let content = "when,how long,what\n\
+                       2024-05-01T10:00:00+02:00,25m,write #clientA\n\
+                       2024-05-01T10:00:40+02:00,25m,same again\n\
+                       2024-05-01T09:00:30+02:00,25:00,already logged\n\
+                       yesterday,25m,no time\n\
+                       2024-05-01T11:00:00+02:00,soon,no duration\n";
+        let import = import(content, &columns, &existing);
+        assert_eq!(import.entries.len(), 1);
+        assert_eq!(import.entries[0].label.as_deref(), Some("write"));
+        assert_eq!(import.entries[0].tags, ["clientA"]);
+        assert_eq!(import.entries[0].duration_secs, 1500);
+        assert_eq!(import.duplicates, 2);
+        let lines: Vec<usize> = import.malformed.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [5, 6]);
+
+        assert!(Columns::parse("duration=1").is_err());
+        assert!(Columns::parse("start=0,label=2").is_err());
+        assert!(Columns::parse("start=0,end=1,size=2").is_err());
+    }
+}
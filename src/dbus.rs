@@ -0,0 +1,214 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use zbus::{blocking::ConnectionBuilder, dbus_interface, fdo};
+
+use crate::{
+    command::Command,
+    keymap::Action,
+    parse_duration,
+    state::{State, Status},
+    timer::SessionKind,
+};
+
+const SERVICE_NAME: &str = "org.pomidor.Timer";
+const OBJECT_PATH: &str = "/org/pomidor/Timer";
+
+/// The `org.pomidor.Timer` interface. Method calls are forwarded to the main
+/// loop; properties mirror the last state it published.
+struct Service {
+    current: State,
+    commands: Sender<Command>,
+}
+
+impl Service {
+    fn send(&self, command: Command) -> fdo::Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| fdo::Error::Failed(String::from("pomidor is shutting down")))
+    }
+}
+
+#[dbus_interface(name = "org.pomidor.Timer")]
+impl Service {
+    /// Seconds left in the session.
+    #[dbus_interface(property)]
+    fn remaining(&self) -> u64 {
+        self.current.remaining_secs
+    }
+
+    /// `idle`, `running`, `paused` or `finished`.
+    #[dbus_interface(property)]
+    fn state(&self) -> String {
+        let name = match self.current.status {
+            Status::Idle => "idle",
+            Status::Running => "running",
+            Status::Paused => "paused",
+            Status::Finished => "finished",
+        };
+        String::from(name)
+    }
+
+    /// `work`, `short_break` or `long_break`.
+    #[dbus_interface(property)]
+    fn phase(&self) -> String {
+        String::from(phase_name(self.current.kind))
+    }
+
+    /// Name of the timer on screen.
+    #[dbus_interface(property)]
+    fn timer(&self) -> String {
+        self.current.timer.clone().unwrap_or_default()
+    }
+
+    /// Starts a session of the given `hh:mm:ss` or `mm:ss` duration.
+    fn start(&self, duration: &str) -> fdo::Result<()> {
+        let duration = parse_duration(duration)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("invalid duration `{}`", duration)))?;
+        self.send(Command::StartWith {
+            duration,
+            label: None,
+        })
+    }
+
+    fn pause(&self) -> fdo::Result<()> {
+        self.send(Command::Pause)
+    }
+
+    fn stop(&self) -> fdo::Result<()> {
+        self.send(Command::Action(Action::Stop))
+    }
+}
+
+/// Name of the `Phase` of a session of `kind`; empty before any session.
+fn phase_name(kind: Option<SessionKind>) -> &'static str {
+    match kind {
+        Some(SessionKind::Work) => "work",
+        Some(SessionKind::ShortBreak) => "short_break",
+        Some(SessionKind::LongBreak) => "long_break",
+        None => "",
+    }
+}
+
+/// Registers the service on the session bus from a thread of its own.
+/// Returns the sender the main loop publishes its state through; the
+/// service goes away quietly when there is no session bus.
+pub fn spawn(commands: Sender<Command>) -> Sender<State> {
+    let (updates, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _ = serve(commands, receiver);
+    });
+
+    updates
+}
+
+fn serve(commands: Sender<Command>, updates: Receiver<State>) -> zbus::Result<()> {
+    let service = Service {
+        current: State::idle(),
+        commands,
+    };
+    let connection = ConnectionBuilder::session()?
+        .name(SERVICE_NAME)?
+        .serve_at(OBJECT_PATH, service)?
+        .build()?;
+    let service = connection
+        .object_server()
+        .interface::<_, Service>(OBJECT_PATH)?;
+
+    for state in updates {
+        let mut iface = service.get_mut();
+        let previous = std::mem::replace(&mut iface.current, state);
+        let context = service.signal_context();
+
+        zbus::block_on(async {
+            if previous.remaining_secs != iface.current.remaining_secs {
+                iface.remaining_changed(context).await?;
+            }
+            if previous.status != iface.current.status {
+                iface.state_changed(context).await?;
+            }
+            if previous.kind != iface.current.kind {
+                iface.phase_changed(context).await?;
+            }
+            if previous.timer != iface.current.timer {
+                iface.timer_changed(context).await?;
+            }
+            Ok::<(), zbus::Error>(())
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use zbus::blocking::{fdo::DBusProxy, Connection, Proxy};
+
+    use super::*;
+
+    #[test]
+    fn phase_is_the_kind_of_session() {
+        let names: Vec<&str> = [
+            Some(SessionKind::Work),
+            Some(SessionKind::ShortBreak),
+            Some(SessionKind::LongBreak),
+            None,
+        ]
+        .into_iter()
+        .map(phase_name)
+        .collect();
+        assert_eq!(names, ["work", "short_break", "long_break", ""]);
+    }
+
+    /// Talks to the service over a real session bus, skipped without one,
+    /// e.g. run under `dbus-run-session -- cargo test --features dbus`.
+    #[test]
+    fn service_on_the_session_bus() {
+        let Ok(connection) = Connection::session() else {
+            eprintln!("no session bus, skipped");
+            return;
+        };
+        let (commands, received) = mpsc::channel();
+        let updates = spawn(commands);
+        let state = State::running(Duration::from_secs(300), Duration::from_secs(240))
+            .with_kind(SessionKind::ShortBreak)
+            .with_timer("desk");
+        updates.send(state).unwrap();
+
+        // The service registers and takes the state from a thread of its own.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let bus = DBusProxy::new(&connection).unwrap();
+        while !bus
+            .name_has_owner(SERVICE_NAME.try_into().unwrap())
+            .unwrap()
+        {
+            assert!(Instant::now() < deadline, "service never showed up");
+            thread::sleep(Duration::from_millis(20));
+        }
+        let proxy = Proxy::new(&connection, SERVICE_NAME, OBJECT_PATH, SERVICE_NAME).unwrap();
+        while proxy.get_property::<u64>("Remaining").unwrap() != 240 {
+            assert!(Instant::now() < deadline, "state never got through");
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(proxy.get_property::<String>("State").unwrap(), "running");
+        assert_eq!(
+            proxy.get_property::<String>("Phase").unwrap(),
+            "short_break"
+        );
+        assert_eq!(proxy.get_property::<String>("Timer").unwrap(), "desk");
+
+        proxy.call_method("Start", &("25:00",)).unwrap();
+        assert!(matches!(
+            received.recv_timeout(Duration::from_secs(5)),
+            Ok(Command::StartWith { duration, label: None }) if duration == Duration::from_secs(1500)
+        ));
+        proxy.call_method("Pause", &()).unwrap();
+        assert!(matches!(received.recv(), Ok(Command::Pause)));
+        assert!(proxy.call_method("Start", &("soon",)).is_err());
+    }
+}
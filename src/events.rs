@@ -0,0 +1,366 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    sync::mpsc::{self, Receiver, SyncSender, TrySendError},
+    thread,
+};
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::state::{State, Status};
+
+/// Events on their way to the writer thread.
+const CHANNEL_SIZE: usize = 64;
+/// Events kept back while the channel is full; beyond it the oldest go.
+const BACKLOG: usize = 64;
+
+/// What happened to a timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Started,
+    /// A running session crossed into another minute.
+    TickMinute,
+    Paused,
+    Resumed,
+    Finished,
+    Stopped,
+}
+
+/// A line of the event stream.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Event {
+    /// Counts up from 1 over the run; gaps are events dropped while the
+    /// reader fell behind.
+    pub seq: u64,
+    #[serde(with = "crate::history::timestamp")]
+    pub timestamp: DateTime<FixedOffset>,
+    pub event: Kind,
+    /// Name of the timer it happened to.
+    pub timer: Option<String>,
+    pub label: Option<String>,
+    pub duration_secs: u64,
+    pub remaining_secs: u64,
+}
+
+/// Turns the transitions of the timers into events and hands them to the
+/// writer thread without ever waiting on it.
+#[derive(Debug)]
+pub struct Emitter {
+    sender: SyncSender<Event>,
+    /// Events the channel had no room for yet, oldest first.
+    backlog: VecDeque<Event>,
+    seq: u64,
+    /// Minute each running timer was last seen in, by name.
+    minutes: HashMap<Option<String>, u64>,
+}
+
+impl Emitter {
+    pub fn new(sender: SyncSender<Event>) -> Emitter {
+        Emitter {
+            sender,
+            backlog: VecDeque::new(),
+            seq: 0,
+            minutes: HashMap::new(),
+        }
+    }
+
+    /// Emits the change of state `kind`, taken from the timer as it
+    /// happened.
+    pub fn emit(&mut self, kind: Kind, state: &State) {
+        match kind {
+            Kind::Started | Kind::Resumed => {
+                self.minutes
+                    .insert(state.timer.clone(), state.remaining_secs / 60);
+            }
+            Kind::Paused | Kind::Finished | Kind::Stopped => {
+                self.minutes.remove(&state.timer);
+            }
+            Kind::TickMinute => {}
+        }
+        self.push(kind, state);
+    }
+
+    /// Emits a tick when a running timer crossed into another minute
+    /// since it was last seen.
+    pub fn observe(&mut self, state: &State) {
+        self.flush();
+
+        if state.status != Status::Running {
+            return;
+        }
+        let minute = state.remaining_secs / 60;
+        match self.minutes.insert(state.timer.clone(), minute) {
+            Some(last) if last != minute => self.push(Kind::TickMinute, state),
+            _ => {}
+        }
+    }
+
+    fn push(&mut self, kind: Kind, state: &State) {
+        self.seq += 1;
+        self.backlog.push_back(Event {
+            seq: self.seq,
+            timestamp: Local::now().fixed_offset(),
+            event: kind,
+            timer: state.timer.clone(),
+            label: state.label.clone(),
+            duration_secs: state.duration_secs,
+            remaining_secs: state.remaining_secs,
+        });
+        self.flush();
+    }
+
+    /// Passes the backlog on as far as the channel has room. While it's
+    /// full, ticks are dropped first, then the oldest events.
+    fn flush(&mut self) {
+        while let Some(event) = self.backlog.pop_front() {
+            match self.sender.try_send(event) {
+                Ok(()) => {}
+                Err(TrySendError::Full(event)) => {
+                    self.backlog.push_front(event);
+                    break;
+                }
+                Err(TrySendError::Disconnected(_)) => self.backlog.clear(),
+            }
+        }
+
+        if !self.backlog.is_empty() {
+            self.backlog.retain(|event| event.event != Kind::TickMinute);
+        }
+        while self.backlog.len() > BACKLOG {
+            self.backlog.pop_front();
+        }
+    }
+}
+
+/// Writes the events to `out` from a background thread, a JSON object per
+/// line. Returns the emitter the main loop feeds the timers to.
+pub fn spawn(out: impl Write + Send + 'static) -> Emitter {
+    let (sender, receiver) = mpsc::sync_channel(CHANNEL_SIZE);
+    thread::spawn(move || write_all(receiver, out));
+    Emitter::new(sender)
+}
+
+fn write_all(receiver: Receiver<Event>, mut out: impl Write) {
+    for event in receiver {
+        let line = match serde_json::to_string(&event) {
+            Ok(line) => line,
+            Err(_) => continue,
+        };
+        // A reader gone away must not take the timer down with it.
+        if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::timer::Timer;
+
+    fn running(remaining_secs: u64) -> State {
+        State::running(
+            Duration::from_secs(1500),
+            Duration::from_secs(remaining_secs),
+        )
+        .with_timer("timer")
+    }
+
+    /// Feeds the transitions of `timer` to an emitter and returns the
+    /// kinds of the events it sent.
+    fn emitted(timer: &mut Timer) -> Vec<Kind> {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_SIZE);
+        let mut emitter = Emitter::new(sender);
+        for (kind, state) in timer.transitions.drain(..) {
+            emitter.emit(kind, &state);
+        }
+        emitter.observe(&timer.snapshot().with_timer(&timer.name));
+        receiver.try_iter().map(|event| event.event).collect()
+    }
+
+    fn finish(timer: &mut Timer) {
+        timer.skip(timer.time);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(timer.update());
+    }
+
+    #[test]
+    fn kinds_are_kebab_case() {
+        let names: Vec<String> = [
+            Kind::Started,
+            Kind::TickMinute,
+            Kind::Paused,
+            Kind::Resumed,
+            Kind::Finished,
+            Kind::Stopped,
+        ]
+        .iter()
+        .map(|kind| serde_json::to_string(kind).unwrap())
+        .collect();
+        assert_eq!(
+            names,
+            [
+                "\"started\"",
+                "\"tick-minute\"",
+                "\"paused\"",
+                "\"resumed\"",
+                "\"finished\"",
+                "\"stopped\""
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_of_a_line() {
+        let line = r#"{"seq":3,"timestamp":"2024-05-01T10:05:00+02:00","event":"tick-minute","timer":"timer","label":"write report","duration_secs":1500,"remaining_secs":1200}"#;
+        let event: Event = serde_json::from_str(line).unwrap();
+        assert_eq!(event.seq, 3);
+        assert_eq!(event.event, Kind::TickMinute);
+        assert_eq!(event.timer.as_deref(), Some("timer"));
+        assert_eq!(event.label.as_deref(), Some("write report"));
+        assert_eq!(event.timestamp.to_rfc3339(), "2024-05-01T10:05:00+02:00");
+        assert_eq!(serde_json::to_string(&event).unwrap(), line);
+    }
+
+    #[test]
+    fn missing_label_is_null() {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_SIZE);
+        let mut emitter = Emitter::new(sender);
+        emitter.emit(Kind::Started, &running(1500));
+
+        let event = receiver.try_recv().unwrap();
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["label"], serde_json::Value::Null);
+        assert_eq!(value["seq"], 1);
+        assert_eq!(value["duration_secs"], 1500);
+    }
+
+    #[test]
+    fn tick_on_each_new_minute() {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_SIZE);
+        let mut emitter = Emitter::new(sender);
+        emitter.emit(Kind::Started, &running(1500));
+        emitter.observe(&running(1500));
+        emitter.observe(&running(1499));
+        emitter.observe(&running(1440));
+        emitter.observe(&running(1439));
+
+        let events: Vec<(Kind, u64)> = receiver
+            .try_iter()
+            .map(|event| (event.event, event.remaining_secs))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (Kind::Started, 1500),
+                (Kind::TickMinute, 1499),
+                (Kind::TickMinute, 1439)
+            ]
+        );
+    }
+
+    #[test]
+    fn no_tick_while_paused() {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_SIZE);
+        let mut emitter = Emitter::new(sender);
+        emitter.emit(Kind::Started, &running(1500));
+        emitter.emit(Kind::Paused, &running(1200));
+        emitter.observe(&running(1100));
+
+        let kinds: Vec<Kind> = receiver.try_iter().map(|event| event.event).collect();
+        assert_eq!(kinds, [Kind::Started, Kind::Paused]);
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut timer = Timer::new("timer");
+        timer.time = Duration::from_secs(1500);
+        timer.start_countdown();
+        timer.pause();
+        timer.resume();
+        assert_eq!(
+            emitted(&mut timer),
+            [Kind::Started, Kind::Paused, Kind::Resumed]
+        );
+    }
+
+    #[test]
+    fn next_session_started_in_the_same_tick() {
+        let mut timer = Timer::new("timer");
+        timer.time = Duration::from_secs(1500);
+        timer.start_countdown();
+        finish(&mut timer);
+        // Auto-started break, the way a cycle or a queue goes on.
+        timer.time = Duration::from_secs(300);
+        timer.start_countdown();
+
+        assert_eq!(
+            emitted(&mut timer),
+            [Kind::Started, Kind::Finished, Kind::Started]
+        );
+    }
+
+    #[test]
+    fn restart_stops_the_session_first() {
+        let mut timer = Timer::new("timer");
+        timer.time = Duration::from_secs(1500);
+        timer.start_countdown();
+        timer.start_countdown();
+        assert_eq!(
+            emitted(&mut timer),
+            [Kind::Started, Kind::Stopped, Kind::Started]
+        );
+    }
+
+    #[test]
+    fn stop_carries_the_label() {
+        let mut timer = Timer::new("timer");
+        timer.time = Duration::from_secs(1500);
+        timer.label = Some(String::from("write report"));
+        timer.start_countdown();
+        timer.stop();
+        timer.label = None;
+        timer.time = Duration::new(0, 0);
+        timer.start_countdown();
+
+        let (kind, state) = timer.transitions.pop().unwrap();
+        assert_eq!(kind, Kind::Stopped);
+        assert_eq!(state.label.as_deref(), Some("write report"));
+        assert_eq!(timer.transitions.len(), 1);
+    }
+
+    #[test]
+    fn ticks_dropped_first_when_the_reader_lags() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let mut emitter = Emitter::new(sender);
+        emitter.emit(Kind::Started, &running(1500));
+        for remaining in (0..10).map(|minute| 1499 - minute * 60) {
+            emitter.observe(&running(remaining));
+        }
+        emitter.emit(Kind::Finished, &State::finished(Duration::from_secs(1500)));
+
+        let first = receiver.try_recv().unwrap();
+        assert_eq!(first.event, Kind::Started);
+        emitter.observe(&State::idle());
+        let rest: Vec<(u64, Kind)> = receiver
+            .try_iter()
+            .map(|event| (event.seq, event.event))
+            .collect();
+        assert_eq!(rest, [(12, Kind::Finished)]);
+    }
+
+    #[test]
+    fn backlog_is_bounded() {
+        let (sender, _receiver) = mpsc::sync_channel(1);
+        let mut emitter = Emitter::new(sender);
+        for _ in 0..BACKLOG * 2 {
+            emitter.emit(Kind::Paused, &running(1500));
+        }
+        assert_eq!(emitter.backlog.len(), BACKLOG);
+        assert_eq!(emitter.backlog.back().unwrap().seq, BACKLOG as u64 * 2);
+    }
+}
@@ -0,0 +1,1112 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::{DateTime, Datelike, FixedOffset, Local, Months, NaiveDate, SecondsFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::{state::data_dir, timer::SessionKind};
+
+const HISTORY_FILE: &str = "history.jsonl";
+const EVENTS_FILE: &str = "events.log";
+/// Rotated logs are `history-2024-Q1.jsonl`, a quarter each.
+const ARCHIVE_PREFIX: &str = "history-";
+const ARCHIVE_SUFFIX: &str = ".jsonl";
+/// Totals per month of the rotated logs, saved by `history compact`.
+const MONTHS_FILE: &str = "history-months.json";
+const LOCK_FILE: &str = "history.lock";
+/// How long a write waits for the lock of the logs before giving up.
+const LOCK_WAIT: Duration = Duration::from_secs(2);
+const LOCK_RETRY: Duration = Duration::from_millis(20);
+const LOCK_STALE: Duration = Duration::from_secs(60);
+
+/// How a session ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// Ran down to zero.
+    Completed,
+    /// Stopped before zero.
+    Abandoned,
+    /// Reset and started over before zero.
+    Restarted,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Completed => "completed",
+            Outcome::Abandoned => "abandoned",
+            Outcome::Restarted => "restarted",
+        }
+    }
+}
+
+/// A session, one per line of the history log.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Entry {
+    /// Start and end of the session in the local time of the moment, with
+    /// its UTC offset.
+    #[serde(with = "timestamp")]
+    pub start: DateTime<FixedOffset>,
+    #[serde(with = "timestamp")]
+    pub end: DateTime<FixedOffset>,
+    pub duration_secs: u64,
+    pub kind: SessionKind,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// `#tags` taken out of the label, without the `#`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub outcome: Outcome,
+    /// What was accomplished, as answered when the session ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// When the session was interrupted, with the offset of the moment.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "timestamp::list"
+    )]
+    pub interruptions: Vec<DateTime<FixedOffset>>,
+}
+
+impl Entry {
+    /// Appends the entry to the history log.
+    pub fn append(&self) -> io::Result<()> {
+        let path = history_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+
+        let _lock = match path.parent() {
+            Some(dir) => Some(Lock::take(dir)?),
+            None => None,
+        };
+
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())
+    }
+}
+
+/// Takes the `#tags` out of a label, e.g. `write report #clientA
+/// #writing`, returning what's left of the label and the tags without
+/// the `#`, each once. A tag is made of letters, digits, `-` and `_`.
+pub fn split_tags(text: &str) -> (Option<String>, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+
+    for word in text.split_whitespace() {
+        let tag = word.strip_prefix('#').filter(|tag| {
+            !tag.is_empty()
+                && tag
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        });
+        match tag {
+            Some(tag) if tags.iter().any(|known| known == tag) => {}
+            Some(tag) => tags.push(String::from(tag)),
+            None => words.push(word),
+        }
+    }
+
+    let label = words.join(" ");
+    (if label.is_empty() { None } else { Some(label) }, tags)
+}
+
+/// `tags` as typed, e.g. `#clientA #writing`.
+pub fn hashtags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Totals of the sessions of a month.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Month {
+    /// `2024-05`.
+    pub month: String,
+    pub work_sessions: u32,
+    pub abandoned: u32,
+    pub focused_secs: u64,
+    pub breaks: u32,
+    pub break_secs: u64,
+    pub interruptions: u32,
+}
+
+impl Month {
+    fn add(&mut self, entry: &Entry) {
+        if entry.kind.is_break() {
+            self.breaks += 1;
+            self.break_secs += entry.duration_secs;
+            return;
+        }
+
+        match entry.outcome {
+            Outcome::Completed => self.work_sessions += 1,
+            Outcome::Abandoned | Outcome::Restarted => self.abandoned += 1,
+        }
+        self.focused_secs += entry.duration_secs;
+        self.interruptions += entry.interruptions.len() as u32;
+    }
+
+    fn merge(&mut self, other: &Month) {
+        self.work_sessions += other.work_sessions;
+        self.abandoned += other.abandoned;
+        self.focused_secs += other.focused_secs;
+        self.breaks += other.breaks;
+        self.break_secs += other.break_secs;
+        self.interruptions += other.interruptions;
+    }
+}
+
+fn month_key(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+/// `2024-Q1`.
+fn quarter(date: NaiveDate) -> String {
+    format!("{}-Q{}", date.year(), date.month0() / 3 + 1)
+}
+
+/// Reads every entry of the history log and of the rotated logs, oldest
+/// first. Lines that don't parse are skipped.
+pub fn load() -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut paths = archives()?;
+    paths.extend(history_path());
+    read_all(&paths)
+}
+
+/// Reads the entries that may have started between `first` and `last`,
+/// only opening the rotated logs of the quarters in between.
+pub fn load_between(first: NaiveDate, last: NaiveDate) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut quarters = Vec::new();
+    let mut date = first.with_day(1).unwrap_or(first);
+    while date <= last {
+        quarters.push(quarter(date));
+        date = match date.checked_add_months(Months::new(1)) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    let mut paths: Vec<PathBuf> = archives()?
+        .into_iter()
+        .filter(|path| archive_quarter(path).is_some_and(|q| quarters.contains(&q)))
+        .collect();
+    paths.extend(history_path());
+    read_all(&paths)
+}
+
+/// Every logged session, read again only once a log has changed, going by
+/// the modification times of the logs.
+#[derive(Debug, Default)]
+pub struct Cache {
+    /// Each log with its modification time and size when last read.
+    stamps: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+    entries: Vec<Entry>,
+}
+
+impl Cache {
+    /// Every session of the logs, oldest first.
+    pub fn entries(&mut self) -> Result<&[Entry], Box<dyn Error>> {
+        let mut paths = archives()?;
+        paths.extend(history_path());
+        self.entries_in(paths)
+    }
+
+    /// Every session of `paths`, given oldest first.
+    pub fn entries_in(&mut self, paths: Vec<PathBuf>) -> Result<&[Entry], Box<dyn Error>> {
+        let stamps: Vec<(PathBuf, Option<(SystemTime, u64)>)> = paths
+            .into_iter()
+            .map(|path| {
+                let stamp = fs::metadata(&path)
+                    .and_then(|meta| Ok((meta.modified()?, meta.len())))
+                    .ok();
+                (path, stamp)
+            })
+            .collect();
+
+        if stamps != self.stamps {
+            let paths: Vec<PathBuf> = stamps.iter().map(|(path, _)| path.clone()).collect();
+            self.entries = read_all(&paths)?;
+            self.stamps = stamps;
+        }
+        Ok(&self.entries)
+    }
+}
+
+/// Search of the labels and tags of every logged session, newest first,
+/// ignoring case. The logs are read one at a time, as more matches are
+/// wanted.
+#[derive(Debug)]
+pub struct Search {
+    query: String,
+    /// Logs not read yet, oldest first.
+    pending: Vec<PathBuf>,
+}
+
+impl Search {
+    /// Searches the history log and the rotated logs.
+    pub fn new(query: &str) -> io::Result<Search> {
+        let mut paths = archives()?;
+        paths.extend(history_path());
+        Ok(Search::in_logs(query, paths))
+    }
+
+    /// Searches `paths`, given oldest first.
+    pub fn in_logs(query: &str, paths: Vec<PathBuf>) -> Search {
+        Search {
+            query: query.to_lowercase(),
+            pending: paths,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Whether every log has been read.
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Matches of the newest log not read yet, newest first; None once
+    /// every log has been read. Missing logs give no matches.
+    pub fn next_log(&mut self) -> Result<Option<Vec<Entry>>, Box<dyn Error>> {
+        let path = match self.pending.pop() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let mut matches: Vec<Entry> = read_all(&[path])?
+            .into_iter()
+            .filter(|entry| self.matches(entry))
+            .collect();
+        matches.reverse();
+        Ok(Some(matches))
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let label = entry
+            .label
+            .as_ref()
+            .is_some_and(|label| label.to_lowercase().contains(&self.query));
+        label
+            || entry
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&self.query))
+    }
+}
+
+fn read_all(paths: &[PathBuf]) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    for path in paths.iter().filter(|path| path.exists()) {
+        let content = fs::read_to_string(path)?;
+        entries.extend(
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Entry>(line).ok()),
+        );
+    }
+    Ok(entries)
+}
+
+/// The rotated logs, oldest first.
+fn archives() -> io::Result<Vec<PathBuf>> {
+    match data_dir() {
+        Some(dir) if dir.exists() => archives_in(&dir),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn archives_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| archive_quarter(path).is_some())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn archive_quarter(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let quarter = name
+        .strip_prefix(ARCHIVE_PREFIX)?
+        .strip_suffix(ARCHIVE_SUFFIX)?;
+    let (year, q) = quarter.split_once("-Q")?;
+    let valid = year.len() == 4
+        && year.bytes().all(|b| b.is_ascii_digit())
+        && matches!(q, "1" | "2" | "3" | "4");
+    valid.then(|| quarter.to_string())
+}
+
+/// Moves the history log to the rotated log of the quarter of its first
+/// session once that quarter is over, or once the log has grown to
+/// `max_bytes` (0 for no limit).
+pub fn rotate(max_bytes: u64) -> io::Result<()> {
+    let path = match history_path() {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let size = match fs::metadata(&path) {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    let mut first = String::new();
+    BufReader::new(fs::File::open(&path)?).read_line(&mut first)?;
+    let first = match serde_json::from_str::<Entry>(&first) {
+        Ok(entry) => quarter(entry.start.date_naive()),
+        Err(_) => return Ok(()),
+    };
+
+    let full = max_bytes > 0 && size >= max_bytes;
+    if !full && first == quarter(Local::now().date_naive()) {
+        return Ok(());
+    }
+    let _lock = match path.parent() {
+        Some(dir) => Some(Lock::take(dir)?),
+        None => None,
+    };
+
+    let archive = path.with_file_name(format!("{}{}{}", ARCHIVE_PREFIX, first, ARCHIVE_SUFFIX));
+    if archive.exists() {
+        let mut file = OpenOptions::new().append(true).open(&archive)?;
+        file.write_all(&fs::read(&path)?)?;
+        fs::remove_file(&path)
+    } else {
+        fs::rename(&path, &archive)
+    }
+}
+
+/// Adds sessions that happened earlier, e.g. imported ones, each to the
+/// log of its quarter: the history log for the current quarter, the
+/// rotated log for the ones before.
+pub fn insert(entries: &[Entry]) -> io::Result<()> {
+    let path = history_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    let _lock = match path.parent() {
+        Some(dir) => Some(Lock::take(dir)?),
+        None => None,
+    };
+
+    let current = quarter(Local::now().date_naive());
+    let mut logs: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for entry in entries {
+        let quarter = quarter(entry.start.date_naive());
+        let log = if quarter >= current {
+            path.clone()
+        } else {
+            path.with_file_name(format!("{}{}{}", ARCHIVE_PREFIX, quarter, ARCHIVE_SUFFIX))
+        };
+        let lines = logs.entry(log).or_default();
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+
+    for (log, lines) in logs {
+        let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+        file.write_all(lines.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Takes the session that started and ended when `entry` did out of the
+/// log holding it. Returns false when no log has it.
+pub fn remove(entry: &Entry) -> Result<bool, Box<dyn Error>> {
+    let mut paths = archives()?;
+    paths.extend(history_path());
+    let _lock = match data_dir() {
+        Some(dir) if dir.exists() => Some(Lock::take(&dir)?),
+        _ => None,
+    };
+
+    for path in paths.iter().filter(|path| path.exists()) {
+        let content = fs::read_to_string(path)?;
+        let mut found = false;
+        let mut kept = String::with_capacity(content.len());
+        for line in content.lines() {
+            let same = !found
+                && serde_json::from_str::<Entry>(line)
+                    .is_ok_and(|other| other.start == entry.start && other.end == entry.end);
+            if same {
+                found = true;
+            } else {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+        if found {
+            write_atomically(path, kept.as_bytes())?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Moves the sessions of the months before the current one out of the
+/// history log into the rotated logs of their quarters, and saves the
+/// totals per month of the rotated logs in `history-months.json`, so
+/// `pomidor history` doesn't have to read them again. Every session is
+/// kept. Returns how many sessions the totals cover and how many of this
+/// month stayed in the history log.
+pub fn compact() -> Result<(usize, usize), Box<dyn Error>> {
+    compact_in(&data_dir().ok_or("no data directory")?)
+}
+
+fn compact_in(dir: &Path) -> Result<(usize, usize), Box<dyn Error>> {
+    let _lock = Lock::take(dir)?;
+
+    let path = dir.join(HISTORY_FILE);
+    let this_month = month_key(Local::now().date_naive());
+    let (kept, past): (Vec<Entry>, Vec<Entry>) = read_all(std::slice::from_ref(&path))?
+        .into_iter()
+        .partition(|entry| month_key(entry.start.date_naive()) >= this_month);
+
+    let mut logs: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for entry in &past {
+        let quarter = quarter(entry.start.date_naive());
+        let lines = logs
+            .entry(dir.join(format!("{}{}{}", ARCHIVE_PREFIX, quarter, ARCHIVE_SUFFIX)))
+            .or_default();
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+    for (log, lines) in logs {
+        let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+        file.write_all(lines.as_bytes())?;
+    }
+
+    if path.exists() {
+        let mut log = String::new();
+        for entry in &kept {
+            log.push_str(&serde_json::to_string(entry)?);
+            log.push('\n');
+        }
+        write_atomically(&path, log.as_bytes())?;
+    }
+
+    let archives = archives_in(dir)?;
+    let entries = read_all(&archives)?;
+    let index = MonthIndex {
+        archives: sizes(&archives),
+        months: totals(&entries).into_values().collect(),
+    };
+    write_atomically(
+        &dir.join(MONTHS_FILE),
+        serde_json::to_string_pretty(&index)?.as_bytes(),
+    )?;
+
+    Ok((entries.len(), kept.len()))
+}
+
+/// Totals per month, oldest first, of every logged session. The rotated
+/// logs are only read when they changed since they were last compacted.
+pub fn months() -> Result<Vec<Month>, Box<dyn Error>> {
+    match data_dir() {
+        Some(dir) => months_in(&dir),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn months_in(dir: &Path) -> Result<Vec<Month>, Box<dyn Error>> {
+    let archives = archives_in(dir)?;
+    let index: MonthIndex = match fs::read_to_string(dir.join(MONTHS_FILE)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => MonthIndex::default(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut months = if index.archives == sizes(&archives) {
+        index
+            .months
+            .into_iter()
+            .map(|month| (month.month.clone(), month))
+            .collect()
+    } else {
+        totals(&read_all(&archives)?)
+    };
+    for (key, month) in totals(&read_all(&[dir.join(HISTORY_FILE)])?) {
+        months
+            .entry(key.clone())
+            .or_insert_with(|| Month {
+                month: key,
+                ..Month::default()
+            })
+            .merge(&month);
+    }
+
+    Ok(months.into_values().collect())
+}
+
+/// Totals of the sessions the rotated logs held when they were summed.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct MonthIndex {
+    /// File name and size of each rotated log summed.
+    archives: Vec<(String, u64)>,
+    months: Vec<Month>,
+}
+
+fn sizes(paths: &[PathBuf]) -> Vec<(String, u64)> {
+    paths
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+            (name, size)
+        })
+        .collect()
+}
+
+fn totals(entries: &[Entry]) -> BTreeMap<String, Month> {
+    let mut months: BTreeMap<String, Month> = BTreeMap::new();
+    for entry in entries {
+        let key = month_key(entry.start.date_naive());
+        months
+            .entry(key.clone())
+            .or_insert_with(|| Month {
+                month: key,
+                ..Month::default()
+            })
+            .add(entry);
+    }
+    months
+}
+
+/// Held while the logs are written, so a compaction rewriting them and a
+/// running timer appending to them take turns. A lock left behind by a
+/// crash is taken over once it's `LOCK_STALE` old.
+struct Lock(PathBuf);
+
+impl Lock {
+    fn take(dir: &Path) -> io::Result<Lock> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOCK_FILE);
+        let since = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Lock(path)),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err),
+            }
+
+            let stale = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > LOCK_STALE);
+            if stale {
+                let _ = fs::remove_file(&path);
+            } else if since.elapsed() > LOCK_WAIT {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("{} is held by another pomidor", path.display()),
+                ));
+            } else {
+                thread::sleep(LOCK_RETRY);
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Writes `content` to a temporary file first, so a crash never leaves
+/// `path` half written.
+fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)
+}
+
+/// Appends a timestamped line about something that happened to the timer
+/// to the events log, next to the history.
+pub fn log_event(message: &str) -> io::Result<()> {
+    let path = data_dir()
+        .map(|dir| dir.join(EVENTS_FILE))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let now = Local::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+    let line = format!("{} {}\n", now, message);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(HISTORY_FILE))
+}
+
+/// Timestamps of the history log as RFC 3339 with the UTC offset, e.g.
+/// `2024-05-01T10:00:00+02:00`, so they mean the same after travel or a
+/// DST change. Unix timestamps and naive local times from earlier logs are
+/// read too, in the current time zone.
+pub mod timestamp {
+    use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeZone};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const NAIVE_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"];
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Unix(i64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<FixedOffset>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(time))
+    }
+
+    fn format(time: &DateTime<FixedOffset>) -> String {
+        time.to_rfc3339_opts(SecondsFormat::Secs, false)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<FixedOffset>, D::Error> {
+        let time = match Raw::deserialize(deserializer)? {
+            Raw::Unix(secs) => Local.timestamp_opt(secs, 0).single().map(DateTime::from),
+            Raw::Text(text) => parse(&text),
+        };
+        time.ok_or_else(|| D::Error::custom("invalid timestamp"))
+    }
+
+    pub fn parse(text: &str) -> Option<DateTime<FixedOffset>> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+            return Some(time);
+        }
+        NAIVE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+            .map(DateTime::from)
+    }
+
+    /// A list of timestamps, each read like a single one.
+    pub mod list {
+        use chrono::{DateTime, FixedOffset};
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        #[derive(Deserialize)]
+        struct Time(#[serde(with = "super")] DateTime<FixedOffset>);
+
+        pub fn serialize<S: Serializer>(
+            times: &[DateTime<FixedOffset>],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(times.iter().map(super::format))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<DateTime<FixedOffset>>, D::Error> {
+            let times = Vec::<Time>::deserialize(deserializer)?;
+            Ok(times.into_iter().map(|Time(time)| time).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(list: &[&str]) -> Vec<String> {
+        list.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn tags_come_out_of_the_label() {
+        assert_eq!(
+            split_tags("write report #clientA #writing"),
+            (
+                Some(String::from("write report")),
+                tags(&["clientA", "writing"])
+            )
+        );
+        // Anywhere in the label, which closes up around them.
+        assert_eq!(
+            split_tags("#clientA write   #writing report"),
+            (
+                Some(String::from("write report")),
+                tags(&["clientA", "writing"])
+            )
+        );
+    }
+
+    #[test]
+    fn each_tag_once() {
+        assert_eq!(
+            split_tags("#a review #b #a #b"),
+            (Some(String::from("review")), tags(&["a", "b"]))
+        );
+        // Tags differing in case are different tags.
+        assert_eq!(split_tags("#Work #work"), (None, tags(&["Work", "work"])));
+    }
+
+    #[test]
+    fn hyphens_and_underscores_belong_to_the_tag() {
+        assert_eq!(
+            split_tags("#client-a #deep_work #q3-2024"),
+            (None, tags(&["client-a", "deep_work", "q3-2024"]))
+        );
+        // Anything else keeps the word in the label.
+        assert_eq!(
+            split_tags("fix #42! # #a.b C#"),
+            (Some(String::from("fix #42! # #a.b C#")), Vec::new())
+        );
+        assert_eq!(split_tags(""), (None, Vec::new()));
+    }
+
+    #[test]
+    fn hashtags_as_typed() {
+        assert_eq!(hashtags(&tags(&["a", "b-c"])), "#a #b-c");
+        assert_eq!(hashtags(&[]), "");
+    }
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pomidor-history-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(start: DateTime<FixedOffset>, kind: SessionKind, outcome: Outcome) -> Entry {
+        Entry {
+            start,
+            end: start + chrono::Duration::minutes(25),
+            duration_secs: 1500,
+            kind,
+            label: None,
+            tags: Vec::new(),
+            outcome,
+            note: None,
+            interruptions: Vec::new(),
+        }
+    }
+
+    fn at(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    fn write_log(path: &Path, entries: &[Entry]) {
+        let lines: String = entries
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+        fs::write(path, lines).unwrap();
+    }
+
+    #[test]
+    fn compact_keeps_every_session() {
+        let dir = temp_dir("compact");
+        let now = Local::now().fixed_offset();
+        write_log(
+            &dir.join(HISTORY_FILE),
+            &[
+                entry(
+                    at("2020-01-15T10:00:00+01:00"),
+                    SessionKind::Work,
+                    Outcome::Completed,
+                ),
+                entry(
+                    at("2020-02-03T10:00:00+01:00"),
+                    SessionKind::Work,
+                    Outcome::Abandoned,
+                ),
+                entry(
+                    at("2020-04-01T10:00:00+02:00"),
+                    SessionKind::ShortBreak,
+                    Outcome::Completed,
+                ),
+                entry(now, SessionKind::Work, Outcome::Completed),
+            ],
+        );
+
+        assert_eq!(compact_in(&dir).unwrap(), (3, 1));
+        assert_eq!(read_all(&[dir.join(HISTORY_FILE)]).unwrap().len(), 1);
+        let q1 = read_all(&[dir.join("history-2020-Q1.jsonl")]).unwrap();
+        assert_eq!(q1.len(), 2);
+        assert_eq!(q1[1].outcome, Outcome::Abandoned);
+        assert_eq!(
+            read_all(&[dir.join("history-2020-Q2.jsonl")])
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let months = months_in(&dir).unwrap();
+        let keys: Vec<&str> = months.iter().map(|month| month.month.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "2020-01",
+                "2020-02",
+                "2020-04",
+                &month_key(now.date_naive())
+            ]
+        );
+        assert_eq!(months[0].work_sessions, 1);
+        assert_eq!(months[1].abandoned, 1);
+        assert_eq!(months[2].breaks, 1);
+
+        // Again, with nothing left to move.
+        assert_eq!(compact_in(&dir).unwrap(), (3, 1));
+        assert_eq!(months_in(&dir).unwrap(), months);
+        assert!(!dir.join(LOCK_FILE).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn months_read_from_the_index_until_the_logs_change() {
+        let dir = temp_dir("index");
+        let archive = dir.join("history-2020-Q1.jsonl");
+        write_log(
+            &archive,
+            &[entry(
+                at("2020-01-15T10:00:00+01:00"),
+                SessionKind::Work,
+                Outcome::Completed,
+            )],
+        );
+        compact_in(&dir).unwrap();
+
+        // Totals that could only have come from the index.
+        let index = MonthIndex {
+            archives: sizes(std::slice::from_ref(&archive)),
+            months: vec![Month {
+                month: String::from("2020-01"),
+                work_sessions: 7,
+                ..Month::default()
+            }],
+        };
+        fs::write(
+            dir.join(MONTHS_FILE),
+            serde_json::to_string(&index).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(months_in(&dir).unwrap()[0].work_sessions, 7);
+
+        let mut file = OpenOptions::new().append(true).open(&archive).unwrap();
+        let more = entry(
+            at("2020-01-16T10:00:00+01:00"),
+            SessionKind::Work,
+            Outcome::Completed,
+        );
+        writeln!(file, "{}", serde_json::to_string(&more).unwrap()).unwrap();
+        assert_eq!(months_in(&dir).unwrap()[0].work_sessions, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cache_reads_the_logs_again_once_they_change() {
+        let dir = temp_dir("cache");
+        let log = dir.join(HISTORY_FILE);
+        let work = entry(
+            at("2024-05-06T10:00:00+02:00"),
+            SessionKind::Work,
+            Outcome::Completed,
+        );
+        write_log(&log, std::slice::from_ref(&work));
+        let mut cache = Cache::default();
+        assert_eq!(cache.entries_in(vec![log.clone()]).unwrap().len(), 1);
+
+        // As long as the log looks the same it isn't read again.
+        let stamp = fs::metadata(&log).unwrap().modified().unwrap();
+        let abandoned = Entry {
+            outcome: Outcome::Abandoned,
+            ..work.clone()
+        };
+        write_log(&log, &[abandoned]);
+        fs::File::options()
+            .write(true)
+            .open(&log)
+            .unwrap()
+            .set_modified(stamp)
+            .unwrap();
+        let entries = cache.entries_in(vec![log.clone()]).unwrap();
+        assert_eq!(entries[0].outcome, Outcome::Completed);
+
+        write_log(&log, &[work.clone(), work]);
+        assert_eq!(cache.entries_in(vec![log.clone()]).unwrap().len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lock_is_taken_in_turns() {
+        let dir = temp_dir("lock");
+        let lock = Lock::take(&dir).unwrap();
+        let err = Lock::take(&dir).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        drop(lock);
+        assert!(Lock::take(&dir).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stale_lock_is_taken_over() {
+        let dir = temp_dir("stale");
+        let file = fs::File::create(dir.join(LOCK_FILE)).unwrap();
+        file.set_modified(SystemTime::now() - LOCK_STALE * 2)
+            .unwrap();
+        assert!(Lock::take(&dir).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn labelled(start: &str, label: &str, names: &[&str]) -> Entry {
+        Entry {
+            label: Some(label.to_string()),
+            tags: tags(names),
+            ..entry(at(start), SessionKind::Work, Outcome::Completed)
+        }
+    }
+
+    fn labels(entries: &[Entry]) -> Vec<&str> {
+        entries
+            .iter()
+            .map(|entry| entry.label.as_deref().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn search_goes_through_the_logs_newest_first() {
+        let dir = temp_dir("search");
+        let (old, new) = (dir.join("history-2024-Q1.jsonl"), dir.join("history.jsonl"));
+        write_log(
+            &old,
+            &[
+                labelled("2024-02-01T10:00:00+01:00", "Write report", &[]),
+                labelled("2024-02-02T10:00:00+01:00", "email", &["Writing"]),
+                labelled("2024-02-03T10:00:00+01:00", "lunch", &[]),
+            ],
+        );
+        write_log(
+            &new,
+            &[
+                labelled("2024-05-01T10:00:00+02:00", "rewrite intro", &[]),
+                labelled("2024-05-02T10:00:00+02:00", "call", &[]),
+            ],
+        );
+
+        let mut search = Search::in_logs("WRIT", vec![old, dir.join("missing.jsonl"), new]);
+        assert_eq!(search.query(), "writ");
+        assert_eq!(
+            labels(&search.next_log().unwrap().unwrap()),
+            ["rewrite intro"]
+        );
+        assert!(search.next_log().unwrap().unwrap().is_empty());
+        assert!(!search.is_done());
+        assert_eq!(
+            labels(&search.next_log().unwrap().unwrap()),
+            ["email", "Write report"]
+        );
+        assert!(search.is_done());
+        assert!(search.next_log().unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn search_without_matches() {
+        let dir = temp_dir("search-none");
+        let log = dir.join("history.jsonl");
+        write_log(
+            &log,
+            &[labelled("2024-05-01T10:00:00+02:00", "call", &["phone"])],
+        );
+
+        let mut search = Search::in_logs("zzz", vec![log]);
+        assert!(search.next_log().unwrap().unwrap().is_empty());
+        assert!(search.next_log().unwrap().is_none());
+        assert!(Search::in_logs("call", Vec::new())
+            .next_log()
+            .unwrap()
+            .is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn interruptions_are_logged_with_their_offset() {
+        let mut entry = entry(
+            at("2024-05-01T10:00:00+02:00"),
+            SessionKind::Work,
+            Outcome::Completed,
+        );
+        entry.interruptions = vec![at("2024-05-01T10:07:30+02:00")];
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(line.contains(r#""interruptions":["2024-05-01T10:07:30+02:00"]"#));
+        assert_eq!(serde_json::from_str::<Entry>(&line).unwrap(), entry);
+    }
+
+    #[test]
+    fn unix_interruptions_of_older_logs_still_read() {
+        let line = r#"{"start":"2024-05-01T10:00:00+02:00","end":"2024-05-01T10:25:00+02:00","duration_secs":1500,"kind":"work","outcome":"completed","interruptions":[1714550850,"2024-05-01T10:10:00+02:00"]}"#;
+        let entry: Entry = serde_json::from_str(line).unwrap();
+        assert_eq!(entry.interruptions[0].timestamp(), 1714550850);
+        assert_eq!(entry.interruptions[1], at("2024-05-01T10:10:00+02:00"));
+        assert!(serde_json::from_str::<Entry>(&line.replace("1714550850", "\"soon\"")).is_err());
+    }
+
+    #[test]
+    fn unix_and_naive_times_of_older_logs_still_read() {
+        let line = r#"{"start":1714550400,"end":"2024-05-01 10:25:00","duration_secs":1500,"kind":"work","outcome":"completed"}"#;
+        let entry: Entry = serde_json::from_str(line).unwrap();
+        assert_eq!(entry.start.timestamp(), 1714550400);
+        assert_eq!(entry.end.naive_local().to_string(), "2024-05-01 10:25:00");
+        // Written back with their offset.
+        let line = serde_json::to_string(&entry).unwrap();
+        let end = format!(r#""end":"2024-05-01T10:25:00{}""#, entry.end.offset());
+        assert!(line.contains(&end));
+    }
+
+    #[test]
+    fn log_rotates_once_its_quarter_is_over_or_it_is_full() {
+        let dir = temp_dir("rotate");
+        crate::state::test_dir::set(dir.clone());
+        let log = dir.join(HISTORY_FILE);
+        let now = Local::now().fixed_offset();
+        write_log(&log, &[entry(now, SessionKind::Work, Outcome::Completed)]);
+        rotate(0).unwrap();
+        assert!(log.exists());
+        rotate(1).unwrap();
+        assert!(!log.exists());
+        let quarter = quarter(now.date_naive());
+        assert!(dir
+            .join(format!("{}{}{}", ARCHIVE_PREFIX, quarter, ARCHIVE_SUFFIX))
+            .exists());
+
+        let start = at("2020-02-03T10:00:00+01:00");
+        write_log(&log, &[entry(start, SessionKind::Work, Outcome::Completed)]);
+        rotate(0).unwrap();
+        assert!(!log.exists());
+        assert!(dir.join("history-2020-Q1.jsonl").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
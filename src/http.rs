@@ -0,0 +1,164 @@
+use std::{
+    error::Error,
+    net::SocketAddr,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{command::Command, keymap::Action, parse_duration, state::State};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7311";
+
+/// Serves `GET /status` and `POST /start|/pause|/resume|/stop` on `addr`
+/// from a thread of its own, forwarding the commands to the main loop.
+/// Returns the sender the main loop publishes its state through.
+pub fn spawn(
+    addr: SocketAddr,
+    commands: Sender<Command>,
+) -> Result<Sender<State>, Box<dyn Error + Send + Sync>> {
+    let server = Server::http(addr)?;
+    let current = Arc::new(Mutex::new(State::idle()));
+    let (updates, receiver) = mpsc::channel::<State>();
+
+    let latest = Arc::clone(&current);
+    thread::spawn(move || {
+        for state in receiver {
+            if let Ok(mut latest) = latest.lock() {
+                *latest = state;
+            }
+        }
+    });
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let _ = handle(request, &current, &commands);
+        }
+    });
+
+    Ok(updates)
+}
+
+fn handle(
+    mut request: Request,
+    current: &Mutex<State>,
+    commands: &Sender<Command>,
+) -> std::io::Result<()> {
+    let expected = match request.url() {
+        "/status" => Method::Get,
+        "/start" | "/pause" | "/resume" | "/stop" => Method::Post,
+        _ => return request.respond(Response::empty(404)),
+    };
+    if *request.method() != expected {
+        return request.respond(Response::empty(405));
+    }
+
+    let command = match request.url() {
+        "/status" => {
+            let state = match current.lock() {
+                Ok(state) => state.clone(),
+                Err(_) => return request.respond(Response::empty(500)),
+            };
+            let body = serde_json::to_string(&state)?;
+            let mut response = Response::from_string(body);
+            if let Ok(header) = "Content-Type: application/json".parse::<Header>() {
+                response = response.with_header(header);
+            }
+            return request.respond(response);
+        }
+        "/start" => {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body)?;
+            let body = body.trim();
+
+            if body.is_empty() {
+                Command::Action(Action::Start)
+            } else {
+                match parse_duration(body) {
+                    Some(duration) => Command::StartWith {
+                        duration,
+                        label: None,
+                    },
+                    None => {
+                        let message = format!("invalid duration `{}`\n", body);
+                        return request
+                            .respond(Response::from_string(message).with_status_code(400));
+                    }
+                }
+            }
+        }
+        "/pause" => Command::Pause,
+        "/resume" => Command::Resume,
+        _ => Command::Action(Action::Stop),
+    };
+
+    match commands.send(command) {
+        Ok(()) => request.respond(Response::empty(204)),
+        Err(_) => request.respond(Response::empty(503)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        time::Duration,
+    };
+
+    use super::*;
+
+    /// Sends a bare HTTP/1.0 request and returns the whole response.
+    fn send(addr: SocketAddr, method: &str, path: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn status_and_commands_over_http() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (commands, received) = mpsc::channel();
+        let updates = spawn(addr, commands).unwrap();
+        let state = State::running(Duration::from_secs(300), Duration::from_secs(240));
+        updates.send(state).unwrap();
+
+        let mut status = send(addr, "GET", "/status", "");
+        while !status.contains("\"running\"") {
+            thread::sleep(Duration::from_millis(20));
+            status = send(addr, "GET", "/status", "");
+        }
+        assert!(status.starts_with("HTTP/1.0 200"), "{}", status);
+        assert!(status.contains("application/json"));
+
+        assert!(send(addr, "POST", "/start", "05:00").starts_with("HTTP/1.0 204"));
+        assert!(matches!(
+            received.recv_timeout(Duration::from_secs(5)),
+            Ok(Command::StartWith { duration, label: None }) if duration == Duration::from_secs(300)
+        ));
+        assert!(send(addr, "POST", "/pause", "").starts_with("HTTP/1.0 204"));
+        assert!(matches!(received.recv(), Ok(Command::Pause)));
+
+        assert!(send(addr, "POST", "/start", "soon").starts_with("HTTP/1.0 400"));
+        assert!(send(addr, "DELETE", "/status", "").starts_with("HTTP/1.0 405"));
+        assert!(send(addr, "GET", "/stop", "").starts_with("HTTP/1.0 405"));
+        assert!(send(addr, "GET", "/", "").starts_with("HTTP/1.0 404"));
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+/// Everything that can be triggered from the keyboard outside the edit mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Toggle,
+    Start,
+    Edit,
+    Reset,
+    Stop,
+    ToggleDisplay,
+    Copy,
+    Quit,
+    AddMinute,
+    SubtractMinute,
+    AddFiveMinutes,
+    SubtractFiveMinutes,
+    ClearDuration,
+    Palette,
+    NextTimer,
+    PreviousTimer,
+    #[serde(rename = "timer_1")]
+    Timer1,
+    #[serde(rename = "timer_2")]
+    Timer2,
+    #[serde(rename = "timer_3")]
+    Timer3,
+    #[serde(rename = "timer_4")]
+    Timer4,
+    #[serde(rename = "timer_5")]
+    Timer5,
+    #[serde(rename = "timer_6")]
+    Timer6,
+    #[serde(rename = "timer_7")]
+    Timer7,
+    #[serde(rename = "timer_8")]
+    Timer8,
+    #[serde(rename = "timer_9")]
+    Timer9,
+    Presets,
+    Picker,
+    History,
+    SearchHistory,
+    SkipBreak,
+    RoundUp,
+    Stats,
+    Interrupt,
+    ReloadConfig,
+    ToggleFocus,
+    ToggleSeconds,
+    ToggleFill,
+    PauseAll,
+}
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Toggle, &["space"]),
+    (Action::Start, &["enter"]),
+    (Action::Edit, &["e"]),
+    (Action::Reset, &["r"]),
+    (Action::Stop, &["s"]),
+    (Action::ToggleDisplay, &["v"]),
+    (Action::Copy, &["y"]),
+    (Action::Quit, &["q"]),
+    (Action::AddMinute, &["k", "+"]),
+    (Action::SubtractMinute, &["j", "-"]),
+    (Action::AddFiveMinutes, &["K"]),
+    (Action::SubtractFiveMinutes, &["J"]),
+    (Action::ClearDuration, &["0"]),
+    (Action::Palette, &[":"]),
+    (Action::NextTimer, &["tab"]),
+    (Action::PreviousTimer, &["backtab"]),
+    (Action::Timer1, &["alt+1"]),
+    (Action::Timer2, &["alt+2"]),
+    (Action::Timer3, &["alt+3"]),
+    (Action::Timer4, &["alt+4"]),
+    (Action::Timer5, &["alt+5"]),
+    (Action::Timer6, &["alt+6"]),
+    (Action::Timer7, &["alt+7"]),
+    (Action::Timer8, &["alt+8"]),
+    (Action::Timer9, &["alt+9"]),
+    (Action::Presets, &["P"]),
+    (Action::Picker, &["/", "ctrl+p"]),
+    (Action::History, &["h"]),
+    (Action::SearchHistory, &["?"]),
+    (Action::SkipBreak, &["n"]),
+    (Action::RoundUp, &["u"]),
+    (Action::Stats, &["S"]),
+    (Action::Interrupt, &["i"]),
+    (Action::ReloadConfig, &["R"]),
+    (Action::ToggleFocus, &["f"]),
+    (Action::ToggleSeconds, &["m"]),
+    (Action::ToggleFill, &["F"]),
+    (Action::PauseAll, &["p"]),
+];
+
+/// Keys bound to an action in the config, either `"x"` or `["x", "y"]`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn names(&self) -> Vec<&str> {
+        match self {
+            Keys::One(key) => vec![key.as_str()],
+            Keys::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// A key with the modifiers that have to be held along with it.
+type Key = (KeyCode, KeyModifiers);
+
+pub struct Keymap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the defaults, replacing the keys of every
+    /// action listed in `overrides`. A key taken over by an override no
+    /// longer triggers its default action.
+    pub fn new(overrides: &HashMap<Action, Keys>) -> Result<Keymap, String> {
+        let mut bindings = HashMap::new();
+
+        for (action, keys) in DEFAULT_BINDINGS {
+            if overrides.contains_key(action) {
+                continue;
+            }
+            for name in keys.iter() {
+                if let Some(key) = parse_key(name) {
+                    bindings.insert(key, *action);
+                }
+            }
+        }
+
+        let mut claimed: HashMap<Key, Action> = HashMap::new();
+        for (action, keys) in overrides {
+            for name in keys.names() {
+                let key = parse_key(name)
+                    .ok_or_else(|| format!("unknown key `{}` for `{}`", name, action.name()))?;
+
+                if let Some(other) = claimed.insert(key, *action) {
+                    return Err(format!(
+                        "key `{}` is bound to both `{}` and `{}`",
+                        name,
+                        other.name(),
+                        action.name()
+                    ));
+                }
+                bindings.insert(key, *action);
+            }
+        }
+
+        Ok(Keymap { bindings })
+    }
+
+    /// Action bound to `code` pressed with `modifiers`. Shift is part of
+    /// the character already, so only ctrl and alt count.
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        self.bindings.get(&(code, modifiers)).copied()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::new(&HashMap::new()).expect("default bindings are valid")
+    }
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Toggle,
+        Action::Start,
+        Action::Edit,
+        Action::Reset,
+        Action::Stop,
+        Action::ToggleDisplay,
+        Action::Copy,
+        Action::Quit,
+        Action::AddMinute,
+        Action::SubtractMinute,
+        Action::AddFiveMinutes,
+        Action::SubtractFiveMinutes,
+        Action::ClearDuration,
+        Action::Palette,
+        Action::NextTimer,
+        Action::PreviousTimer,
+        Action::Timer1,
+        Action::Timer2,
+        Action::Timer3,
+        Action::Timer4,
+        Action::Timer5,
+        Action::Timer6,
+        Action::Timer7,
+        Action::Timer8,
+        Action::Timer9,
+        Action::Presets,
+        Action::Picker,
+        Action::History,
+        Action::SearchHistory,
+        Action::SkipBreak,
+        Action::RoundUp,
+        Action::Stats,
+        Action::Interrupt,
+        Action::ReloadConfig,
+        Action::ToggleFocus,
+        Action::ToggleSeconds,
+        Action::ToggleFill,
+        Action::PauseAll,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Toggle => "toggle",
+            Action::Start => "start",
+            Action::Edit => "edit",
+            Action::Reset => "reset",
+            Action::Stop => "stop",
+            Action::ToggleDisplay => "toggle_display",
+            Action::Copy => "copy",
+            Action::Quit => "quit",
+            Action::AddMinute => "add_minute",
+            Action::SubtractMinute => "subtract_minute",
+            Action::AddFiveMinutes => "add_five_minutes",
+            Action::SubtractFiveMinutes => "subtract_five_minutes",
+            Action::ClearDuration => "clear_duration",
+            Action::Palette => "palette",
+            Action::NextTimer => "next_timer",
+            Action::PreviousTimer => "previous_timer",
+            Action::Timer1 => "timer_1",
+            Action::Timer2 => "timer_2",
+            Action::Timer3 => "timer_3",
+            Action::Timer4 => "timer_4",
+            Action::Timer5 => "timer_5",
+            Action::Timer6 => "timer_6",
+            Action::Timer7 => "timer_7",
+            Action::Timer8 => "timer_8",
+            Action::Timer9 => "timer_9",
+            Action::Presets => "presets",
+            Action::Picker => "picker",
+            Action::History => "history",
+            Action::SearchHistory => "search_history",
+            Action::SkipBreak => "skip_break",
+            Action::RoundUp => "round_up",
+            Action::Stats => "stats",
+            Action::Interrupt => "interrupt",
+            Action::ReloadConfig => "reload_config",
+            Action::ToggleFocus => "toggle_focus",
+            Action::ToggleSeconds => "toggle_seconds",
+            Action::ToggleFill => "toggle_fill",
+            Action::PauseAll => "pause_all",
+        }
+    }
+}
+
+/// Parses key names such as `q`, `K`, `+`, `space`, `enter` or `tab`,
+/// each of them optionally after `ctrl+` or `alt+`.
+pub fn parse_key(name: &str) -> Option<Key> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = name;
+    loop {
+        let (prefix, rest) = match name.split_once('+') {
+            Some((prefix, rest)) if !rest.is_empty() => (prefix, rest),
+            _ => break,
+        };
+        modifiers |= match prefix.to_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            _ => return None,
+        };
+        name = rest;
+    }
+
+    parse_code(name).map(|code| (code, modifiers))
+}
+
+fn parse_code(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_are_parsed() {
+        assert_eq!(
+            parse_key("ctrl+p"),
+            Some((KeyCode::Char('p'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            parse_key("Alt+Ctrl+enter"),
+            Some((KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::ALT))
+        );
+        assert_eq!(
+            parse_key("+"),
+            Some((KeyCode::Char('+'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            parse_key("ctrl++"),
+            Some((KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(parse_key("super+p"), None);
+        assert_eq!(parse_key("ctrl+"), None);
+    }
+
+    #[test]
+    fn picker_is_also_on_ctrl_p() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(KeyCode::Char('p'), KeyModifiers::CONTROL),
+            Some(Action::Picker)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('p'), KeyModifiers::NONE),
+            Some(Action::PauseAll)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('K'), KeyModifiers::SHIFT),
+            Some(Action::AddFiveMinutes)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('k'), KeyModifiers::CONTROL),
+            None
+        );
+    }
+
+    #[test]
+    fn override_takes_the_key_over() {
+        let overrides = HashMap::from([(Action::Quit, Keys::One(String::from("ctrl+p")))]);
+        let keymap = Keymap::new(&overrides).unwrap();
+        assert_eq!(
+            keymap.action(KeyCode::Char('p'), KeyModifiers::CONTROL),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(KeyCode::Char('q'), KeyModifiers::NONE), None);
+        assert_eq!(
+            keymap.action(KeyCode::Char('/'), KeyModifiers::NONE),
+            Some(Action::Picker)
+        );
+    }
+
+    #[test]
+    fn one_key_for_two_actions_is_an_error() {
+        let overrides = HashMap::from([
+            (Action::Quit, Keys::One(String::from("ctrl+x"))),
+            (Action::Stop, Keys::Many(vec![String::from("CTRL+x")])),
+        ]);
+        assert!(Keymap::new(&overrides)
+            .err()
+            .unwrap()
+            .contains("is bound to both"));
+    }
+
+    #[test]
+    fn first_nine_timers_are_on_alt_digits() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(KeyCode::Char('1'), KeyModifiers::ALT),
+            Some(Action::Timer1)
+        );
+        assert_eq!(
+            keymap.action(KeyCode::Char('9'), KeyModifiers::ALT),
+            Some(Action::Timer9)
+        );
+        assert_eq!(keymap.action(KeyCode::Char('1'), KeyModifiers::NONE), None);
+        assert_eq!(Action::from_name("timer_3"), Some(Action::Timer3));
+        assert_eq!(
+            serde_json::from_str::<Action>("\"timer_3\"").unwrap(),
+            Action::Timer3
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::{collections::HashMap, env, fs, path::Path};
+
+use crate::config::{config_dir, expand_home, Config};
+
+/// The strings of the UI in English, which every locale falls back to.
+/// `{name}` marks a value filled in when the string is shown.
+const EN: &[(&str, &str)] = &[
+    ("input.duration", "Session timer (hh:mm:ss or mm:ss)"),
+    ("input.duration_placeholder", "25:00"),
+    (
+        "input.duration_hint",
+        ":ss for seconds · numbers are {bare}",
+    ),
+    ("input.minutes", "minutes"),
+    (
+        "input.seconds_below",
+        "seconds below {below}, minutes from {below}",
+    ),
+    ("input.command", "Command"),
+    (
+        "input.command_placeholder",
+        "start 25:00 label, pause, stop, quit...",
+    ),
+    ("input.preset", "New preset (name hh:mm:ss or mm:ss)"),
+    ("input.preset_placeholder", "tea 03:00"),
+    ("input.note", "What did you accomplish?"),
+    ("input.no_command", "no matching command"),
+    ("input.name_and_duration", "expected a name and a duration"),
+    ("input.invalid_duration", "invalid duration `{duration}`"),
+    ("input.no_preset", "no matching preset"),
+    ("footer.space", "space: {action}"),
+    ("action.start", "start"),
+    ("action.pause", "pause"),
+    ("action.resume", "resume"),
+    ("action.acknowledge", "acknowledge"),
+    ("kind.work", "work"),
+    ("kind.short_break", "short break"),
+    ("kind.long_break", "long break"),
+    ("status.done", "{count} done"),
+    ("status.long_break", "{session}/{interval} until long break"),
+    ("status.over", "over"),
+    ("status.overtime", "{sign} overtime"),
+    ("status.paused", "paused"),
+    ("status.ends", "ends {time}"),
+    ("status.left", "{time} left"),
+    ("status.silent", "silent"),
+    ("status.profile", "profile {name}"),
+    ("status.step", "step {step}/{steps}"),
+    ("status.quiet_hours", "quiet hours"),
+    ("status.nag", "you're supposed to be working"),
+    ("status.strict_mode", "🔒"),
+    ("status.paused_all", "⏸ all paused"),
+    (
+        "status.strict",
+        "strict mode · hold {secs}s to {action} or :abandon",
+    ),
+    (
+        "status.strict_alt",
+        "strict mode · press it with alt to {action} or :abandon",
+    ),
+    ("tab.done", "{name} done"),
+    ("banner.running", "▶ running"),
+    ("banner.paused", " ⏸ PAUSED "),
+    ("banner.scheduled", "⏰ starts at {time} · in {left}"),
+    ("breathing.inhale", "inhale"),
+    ("breathing.hold", "hold"),
+    ("breathing.exhale", "exhale"),
+    ("summary.title", "All cycles done"),
+    ("summary.footer", "Y: copy · esc: close"),
+    ("summary.work_sessions", "Work sessions"),
+    ("summary.focused", "Focused"),
+    ("summary.interruptions", "Interruptions"),
+    ("summary.per_session", "{count} per session"),
+    ("summary.completed", "Completed"),
+    ("summary.of_started", "{percent}% of started"),
+    ("summary.breaks", "Breaks"),
+    ("summary.break_time", "Break time"),
+    ("summary.breaks_skipped", "Breaks skipped"),
+    ("stats.title", "Stats"),
+    ("stats.footer", "t: by label · Y: copy today · esc: close"),
+    (
+        "stats.breakdown_footer",
+        "p: today / week / month · t: overview · esc: close",
+    ),
+    ("stats.today", "Today"),
+    ("stats.week", "This week"),
+    ("stats.month", "This month"),
+    ("stats.unlabeled", "(none)"),
+    ("stats.no_sessions", "No work sessions in this period"),
+    ("stats.recent", "Last 30 days"),
+    ("stats.lifetime", "All time"),
+    ("stats.sessions", "Work sessions"),
+    ("stats.focused", "Focused"),
+    ("stats.average", "Average session"),
+    ("stats.per_day", "Sessions per day"),
+    ("stats.best_day", "Best day"),
+    ("stats.completed", "Completed"),
+    ("stats.interruptions", "Interruptions"),
+    ("stats.empty", "No sessions in the history yet"),
+    (
+        "stats.too_few",
+        "Averages show up after {count} work sessions",
+    ),
+    ("presets.title", "Presets"),
+    (
+        "presets.footer",
+        "enter: start · a: add · d: delete · esc: close",
+    ),
+    ("presets.empty", "No presets yet, press a to add one"),
+    ("history.title", "Today"),
+    (
+        "history.footer",
+        "↑/↓/j/k: scroll · ?: search · d: delete · esc: close",
+    ),
+    ("history.empty", "No sessions yet today"),
+    ("history.search", "Search \"{query}\" · {count} found"),
+    (
+        "history.search_footer",
+        "↑/↓/j/k: scroll · ?: search · d: delete · esc: back",
+    ),
+    ("history.no_match", "No session matches"),
+    ("history.confirm", "Delete the session of {time}? y/n"),
+    ("picker.title", "Start"),
+    ("picker.empty", "Nothing matches"),
+    ("picker.preset", "preset"),
+    ("picker.routine", "routine"),
+    ("picker.recent", "recent"),
+    ("message.remaining", "{arrow} remaining time"),
+    ("message.elapsed", "{arrow} elapsed time"),
+    ("message.end", "{arrow} end time"),
+    ("message.finished", "{name} finished"),
+    ("message.break_ends", "{kind} ends in {time}"),
+    ("message.suggestions", "suggestions: {error}"),
+    ("message.history", "history: {error}"),
+    ("message.org_log", "org log: {error}"),
+    ("message.on_finish", "on_finish: {error}"),
+    ("message.no_timer", "no timer {number}"),
+    ("message.no_work_session", "no work session running"),
+    ("message.config", "config: {path}"),
+    ("message.no_config", "no config directory"),
+    ("message.reloaded", "config reloaded"),
+    (
+        "message.reloaded_deferred",
+        "config reloaded; the new duration applies from the next session",
+    ),
+    (
+        "message.reloaded_ignoring",
+        "config reloaded; ignoring {warning}",
+    ),
+    ("message.clock_back", "clock went back {time}"),
+    ("message.slept", "system slept {time}"),
+    ("message.slept_frozen", "system slept {time} — timer frozen"),
+    (
+        "message.slept_adjusted",
+        "system slept {time} — timer adjusted",
+    ),
+    ("message.no_routines", "no routines in the config"),
+    ("message.routines", "routines: {names}"),
+    ("message.presets_unsaved", "could not save presets: {error}"),
+    ("message.editor", "editor: {error}"),
+    ("message.sound", "sound: {error}"),
+    ("message.notification", "notification: {error}"),
+    ("message.copied", "copied {text}"),
+    ("message.copied_summary", "copied the summary"),
+    ("message.copied_today", "copied today's summary"),
+    ("message.too_long", "too long to copy"),
+    ("message.themes", "themes: {names}"),
+    ("message.unknown_theme", "unknown theme `{name}`"),
+    (
+        "message.history_unread",
+        "could not read the history: {error}",
+    ),
+    ("message.deleted", "session deleted"),
+    ("message.not_found", "session not found in the history"),
+    ("message.no_break", "no break to skip"),
+    (
+        "message.skip_nudge",
+        "{count} breaks skipped today, take the next one",
+    ),
+    ("message.fill", "banner fills the screen"),
+    ("message.scale", "banner at scale {scale}"),
+    (
+        "message.no_font",
+        "banner font unavailable, showing plain digits",
+    ),
+    ("message.resumed_all", "resumed all timers"),
+    ("message.none_running", "no timer running"),
+    ("brief.today", "Today: {pomodoros}, {focused} focused."),
+    ("brief.week", "Week: {pomodoros}."),
+    ("brief.streak", "Streak: {days}."),
+    ("brief.pomodoro", "{count} pomodoro"),
+    ("brief.pomodoros", "{count} pomodoros"),
+    ("brief.day", "{count} day"),
+    ("brief.days", "{count} days"),
+    ("brief.empty", "no history yet"),
+];
+
+/// User-visible strings looked up by key, e.g. `status.paused`.
+#[derive(Debug, Default)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+}
+
+impl Locale {
+    /// Picks the locale from `lang` in the config or, failing that, the
+    /// `LANG` variable. Languages other than English are read from
+    /// `locale_file` or `locales/<lang>.toml` in the config directory, flat
+    /// tables of `"key" = "string"`; a language without a file is English.
+    /// The `[strings]` table of the config goes on top.
+    pub fn load(config: &Config) -> Result<Locale, String> {
+        let lang = config.lang.clone().or_else(|| {
+            env::var("LANG")
+                .ok()
+                .and_then(|lang| lang.split(['_', '.']).next().map(String::from))
+        });
+
+        let file = match (&config.locale_file, lang.as_deref()) {
+            (Some(file), _) => Some(expand_home(file)),
+            (None, None | Some("en" | "C" | "POSIX" | "")) => None,
+            (None, Some(lang)) => config_dir()
+                .map(|dir| dir.join("locales").join(format!("{}.toml", lang)))
+                .filter(|path| path.exists()),
+        };
+
+        let mut strings = match file {
+            Some(file) => read(&file)?,
+            None => HashMap::new(),
+        };
+        strings.extend(config.strings.clone());
+
+        Ok(Locale { strings })
+    }
+
+    /// The string for `key`, in English when the locale lacks it.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.strings.get(key) {
+            Some(string) => string,
+            None => EN
+                .iter()
+                .find(|(en, _)| *en == key)
+                .map_or(key, |(_, string)| string),
+        }
+    }
+
+    /// The string for `key` with each `{name}` replaced by its value.
+    pub fn format(&self, key: &str, values: &[(&str, &str)]) -> String {
+        values
+            .iter()
+            .fold(self.get(key).to_string(), |string, (name, value)| {
+                string.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
+fn read(path: &Path) -> Result<HashMap<String, String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read locale {}: {}", path.display(), err))?;
+    toml::from_str(&content).map_err(|err| format!("invalid locale {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use regex::Regex;
+
+    use super::*;
+
+    /// Every key the code looks up, going by the string literals shaped
+    /// like one, has an English string.
+    #[test]
+    fn every_key_used_is_in_english() {
+        let sources = [
+            include_str!("main.rs"),
+            include_str!("aggregate.rs"),
+            include_str!("breathing.rs"),
+            include_str!("picker.rs"),
+        ];
+        let sections: HashSet<&str> = EN
+            .iter()
+            .filter_map(|(key, _)| key.split('.').next())
+            .collect();
+        let literal = Regex::new(r#""(([a-z]+)\.[a-z_]+)""#).unwrap();
+
+        let mut missing: Vec<&str> = sources
+            .iter()
+            .flat_map(|source| literal.captures_iter(source))
+            .filter(|captures| sections.contains(&captures[2]))
+            .map(|captures| captures.get(1).unwrap().as_str())
+            .filter(|key| EN.iter().all(|(en, _)| en != key))
+            .collect();
+        missing.dedup();
+        assert_eq!(missing, Vec::<&str>::new());
+    }
+
+    /// Messages of the status line and errors of the edit box are looked up
+    /// too: whatever text is handed to them is a key, not English.
+    #[test]
+    fn messages_are_looked_up() {
+        let source = include_str!("main.rs");
+        let source = &source[..source.find("\nmod tests").unwrap_or(source.len())];
+        let call = Regex::new(r"(show_message|show_error|\.copy|input_error = Some)\(").unwrap();
+        let literal = Regex::new(r#""((?:[^"\\]|\\.)*)""#).unwrap();
+        let key = Regex::new(r"^[a-z]+\.[a-z_]+$").unwrap();
+        let words = Regex::new(r"[A-Za-z]{2,}").unwrap();
+
+        let mut english = Vec::new();
+        for found in call.find_iter(source) {
+            let rest = &source[found.end()..];
+            let mut depth = 1;
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| {
+                    depth += match c {
+                        '(' => 1,
+                        ')' => -1,
+                        _ => 0,
+                    };
+                    depth == 0
+                })
+                .map_or(rest.len(), |(end, _)| end);
+            english.extend(
+                literal
+                    .captures_iter(&rest[..end])
+                    .map(|captures| captures.get(1).unwrap().as_str())
+                    .filter(|text| !key.is_match(text) && words.is_match(text)),
+            );
+        }
+        assert_eq!(english, Vec::<&str>::new());
+    }
+
+    #[test]
+    fn keys_are_unique() {
+        let keys: HashSet<&str> = EN.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys.len(), EN.len());
+    }
+
+    #[test]
+    fn strict_messages() {
+        let locale = Locale::default();
+        assert_eq!(locale.get("status.locked"), "status.locked");
+        assert_eq!(
+            locale.format("status.strict_alt", &[("action", "quit")]),
+            "strict mode · press it with alt to quit or :abandon"
+        );
+    }
+}
@@ -1,392 +1,7024 @@
+mod aggregate;
+mod alarm;
+mod breathing;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod command;
+mod config;
+mod csv;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod events;
+mod history;
+#[cfg(feature = "http")]
+mod http;
+mod keymap;
+mod locale;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod notify;
+mod org;
+mod osc52;
+mod picker;
+mod profile;
+mod queue;
+mod report;
+mod sound;
+mod state;
+mod stats;
+mod template;
+mod theme;
+mod timer;
+
 use std::{
+    collections::BTreeMap,
     error::Error,
-    io,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
     rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        OnceLock,
+    },
+    thread,
     time::{Duration, Instant},
 };
 
+#[cfg(feature = "http")]
+use std::net::SocketAddr;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use clap::{Parser, Subcommand, ValueEnum};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    style::Print,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
 };
 
-use ratatui::{prelude::*, widgets::*};
+use ratatui::{
+    prelude::*,
+    widgets::{block::*, *},
+};
 use regex::Regex;
 
 use figlet_rs::FIGfont;
 
+use aggregate::{Breakdown, ChartPeriod, Overview};
+use alarm::Alarm;
+use command::Command;
+use config::{ClockJump, Config, DisplayMode, FocusLost, OvertimeStyle, StrictEscape, Warning};
+use history::Outcome;
+use keymap::{Action, Keymap};
+use locale::Locale;
+use picker::Picker;
+use queue::Queue;
+use report::Period;
+use sound::Ring;
+use state::{State, Status};
+use stats::Stats;
+use timer::{next_session, SessionKind, Timer, TimerState};
+
 const MARGIN_LINES: usize = 2;
 const INPUT_HEIGHT: usize = 3;
 const SECS_IN_HOUR: u16 = 3600;
 const SECS_IN_MIN: u16 = 60;
+const MESSAGE_SECS: u64 = 3;
+const DEFAULT_TIMER: &str = "timer";
+const CLOCK_JUMP_SECS: i64 = 5;
+const TENTHS_BELOW_SECS: u64 = 10;
+const TENTHS_TICK: Duration = Duration::from_millis(100);
+/// Rows the history pane scrolls by on page up and page down.
+const HISTORY_PAGE: isize = 10;
+/// Narrowest terminal the history pane goes beside the timer in; below
+/// it, the pane goes under the timer.
+const HISTORY_SIDE_WIDTH: u16 = 100;
+/// Cells of the bar of the current minute.
+const MINUTE_BAR_WIDTH: usize = 15;
+/// How long a key refused by strict mode has to be held down to get
+/// through, and the longest gap between its repeats that still counts.
+/// A key pressed longer ago than the gap no longer gets anything through.
+const STRICT_HOLD: Duration = Duration::from_secs(3);
+const HOLD_GAP: Duration = Duration::from_millis(700);
+/// Saves the terminal title onto the title stack, and restores it (xterm).
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+#[cfg(feature = "dbus")]
+const DBUS_HELP: &str = "While running, the timer is available on the session bus as \
+org.pomidor.Timer, e.g.:
+  busctl --user call org.pomidor.Timer /org/pomidor/Timer org.pomidor.Timer Start s 25:00
+  busctl --user get-property org.pomidor.Timer /org/pomidor/Timer org.pomidor.Timer Remaining";
+
+#[derive(Parser)]
+#[command(version, about)]
+#[cfg_attr(feature = "dbus", command(after_help = DBUS_HELP))]
+struct Args {
+    /// Print the current session as JSON and exit
+    #[arg(long)]
+    status: bool,
+    /// Print the config in effect, defaults and environment variables
+    /// included, as TOML and exit
+    #[arg(long)]
+    dump_config: bool,
+    /// Print today's and the week's work sessions and the streak, and exit
+    #[arg(long)]
+    stats: bool,
+    /// Serve the status and accept commands over HTTP on ADDR
+    #[cfg(feature = "http")]
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        default_missing_value = http::DEFAULT_ADDR
+    )]
+    listen: Option<SocketAddr>,
+    /// Duration the timers start out with (hh:mm:ss or mm:ss), instead of
+    /// `default_duration` from the config
+    #[arg(long, value_name = "DURATION", value_parser = duration_arg)]
+    duration: Option<Duration>,
+    /// Quit this long after a session completes unless a key is pressed,
+    /// e.g. 30s, 2m or 01:30, instead of `exit_after_finish_secs` from the config
+    #[arg(long, value_name = "DELAY", value_parser = delay_arg)]
+    exit_after: Option<Duration>,
+    /// Text shown in place of the digits until a finished session is
+    /// acknowledged, e.g. 'TEA IS READY', instead of `done_text` from the config
+    #[arg(long, value_name = "TEXT")]
+    done_text: Option<String>,
+    /// Draw in the normal screen buffer, leaving the last frame in the
+    /// scrollback after quitting
+    #[arg(long)]
+    inline: bool,
+    /// Language of the UI, e.g. pl for locales/pl.toml in the config
+    /// directory, instead of `lang` from the config or LANG
+    #[arg(long, value_name = "LANG")]
+    lang: Option<String>,
+    /// Keep config overrides, history and state under profiles/NAME in the
+    /// config and data directories; `default` is the usual place
+    #[arg(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+    /// Run the sessions listed in FILE one after the other, a line like
+    /// `25:00 Math` each; with --exit-after, quit after the last one
+    #[arg(long, value_name = "FILE")]
+    queue: Option<PathBuf>,
+    /// Theme to use, built-in or from the themes directory, instead of
+    /// `theme` from the config
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
+    /// Append a JSON line to FILE for every session started, paused,
+    /// resumed, finished or stopped, and every minute of a running one;
+    /// with `-`, write them to stdout and draw the timer on stderr
+    #[arg(long, value_name = "FILE")]
+    events: Option<PathBuf>,
+    /// Wait until HH:MM, today or tomorrow, then start the pending duration
+    #[arg(long, value_name = "HH:MM", value_parser = time_arg)]
+    at: Option<NaiveTime>,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Print the current session and exit
+    Status {
+        /// Print one line from a template instead of JSON, e.g. '{remaining} {icon}'.
+        /// Placeholders: {remaining}, {elapsed}, {percent}, {phase}, {label}, {icon}, {color}
+        #[arg(long)]
+        format: Option<String>,
+        /// Output shape; plain when a format is given, json otherwise
+        #[arg(long, value_enum)]
+        output: Option<Output>,
+        /// Keep printing a line every second
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Start the routine NAME from the config
+    Run {
+        /// Name of a `[routine.NAME]` table
+        name: String,
+    },
+    /// Print the totals per month of the history log
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+    },
+    /// Print the sessions of a day from the history log
+    Report {
+        /// Print a Markdown table instead of plain text
+        #[arg(long)]
+        markdown: bool,
+        /// Print the work sessions of the week DATE falls in, or of N weeks
+        /// before it, day by day
+        #[arg(
+            long,
+            value_name = "N",
+            num_args = 0..=1,
+            default_missing_value = "0"
+        )]
+        week: Option<u64>,
+        /// Print the focused time per tag instead of the sessions
+        #[arg(long)]
+        by_tag: bool,
+        /// Print the sessions completed, focused minutes and abandonment
+        /// rate per day as JSON
+        #[arg(long, conflicts_with_all = ["markdown", "by_tag"])]
+        json: bool,
+        /// Day to report on, as YYYY-MM-DD; today by default
+        date: Option<NaiveDate>,
+    },
+    /// Add sessions from a CSV file to the history log
+    Import {
+        /// CSV file with a session per row
+        #[arg(long, value_name = "FILE")]
+        csv: PathBuf,
+        /// Column of each field, from 0, e.g. 'start=0,duration=1,label=2';
+        /// `duration` may be replaced by `end`, and `kind` and `outcome`
+        /// are optional. The layout of `export --csv` by default
+        #[arg(long, default_value = csv::EXPORT_MAP)]
+        map: String,
+    },
+    /// Print the history log
+    Export {
+        /// Print it as CSV
+        #[arg(long, required = true)]
+        csv: bool,
+    },
+    /// Print the figures of the stats screen
+    Stats {
+        /// Print them as JSON, with a `version` field for the layout
+        #[arg(long, required = true)]
+        json: bool,
+        /// Days covered, up to today: the week (see `week_start`), the month
+        /// or the whole history
+        #[arg(long, value_enum, default_value_t = StatsPeriod::All)]
+        period: StatsPeriod,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StatsPeriod {
+    Week,
+    Month,
+    All,
+}
+
+impl StatsPeriod {
+    fn name(self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "week",
+            StatsPeriod::Month => "month",
+            StatsPeriod::All => "all",
+        }
+    }
+
+    /// First day covered on `today`, None for the whole history.
+    fn first_day(self, today: NaiveDate, week_start: Weekday) -> Option<NaiveDate> {
+        let period = match self {
+            StatsPeriod::Week => ChartPeriod::Week,
+            StatsPeriod::Month => ChartPeriod::Month,
+            StatsPeriod::All => return None,
+        };
+        Some(period.first_day(today, week_start))
+    }
+}
+
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// Move the sessions of past months into the rotated logs and save
+    /// the totals per month of those
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Output {
+    /// The state document
+    Json,
+    /// One line from the template, e.g. for polybar or tmux
+    Plain,
+    /// Full text, short text and color lines for i3blocks
+    I3blocks,
+}
+
+/// What the input box at the bottom is being used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    Duration,
+    Command,
+    Preset,
+    Note,
+}
+
+/// What the space bar does in the current state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpaceAction {
+    Start,
+    Pause,
+    Resume,
+    Acknowledge,
+}
+
+impl SpaceAction {
+    /// Locale key of the label.
+    fn label(&self) -> &'static str {
+        match self {
+            SpaceAction::Start => "action.start",
+            SpaceAction::Pause => "action.pause",
+            SpaceAction::Resume => "action.resume",
+            SpaceAction::Acknowledge => "action.acknowledge",
+        }
+    }
+}
+
+/// Locale key of the name of `kind`.
+fn kind_label(kind: SessionKind) -> &'static str {
+    match kind {
+        SessionKind::Work => "kind.work",
+        SessionKind::ShortBreak => "kind.short_break",
+        SessionKind::LongBreak => "kind.long_break",
+    }
+}
+
+/// A key held down: its presses keep repeating.
+#[derive(Debug, Clone, Copy)]
+struct Hold {
+    code: KeyCode,
+    /// Whether alt was down on the last press.
+    alt: bool,
+    since: Instant,
+    last: Instant,
+    /// Set once the hold got through strict mode; its repeats are then
+    /// ignored until the key is let go.
+    spent: bool,
+}
+
+/// Today's sessions, or the ones matching a search, listed next to the
+/// timer.
+#[derive(Debug, Default)]
+struct HistoryPane {
+    /// Newest first.
+    entries: Vec<history::Entry>,
+    list: ListState,
+    /// Set by `d` until the deletion of the highlighted session is
+    /// confirmed or called off.
+    confirm: bool,
+    /// Set by the search action while the search is typed into
+    /// `input_str`.
+    typing: bool,
+    /// Search whose matches are listed in place of today's sessions.
+    search: Option<history::Search>,
+}
 
 struct App {
     time_str: String,
     edit_mode: bool,
-    reset: bool,
-    time: Duration,
+    input_kind: InputKind,
+    input_error: Option<String>,
+    timers: Vec<Timer>,
+    /// Timers the pause-all action paused, by index, until it resumes them.
+    paused_all: Vec<usize>,
+    current: usize,
+    presets: Option<ListState>,
+    /// Fuzzy picker over the timer; its filter is typed into `input_str`.
+    picker: Option<Picker>,
+    history: Option<HistoryPane>,
+    /// Figures of the stats screen, while it's open.
+    overview: Option<Overview>,
+    /// Focused time per label, shown on the stats screen in place of the
+    /// overview.
+    breakdown: Option<Breakdown>,
+    /// The parsed history, read again once a log changes.
+    history_cache: history::Cache,
     input_str: String,
     cursor_position: usize,
+    /// What was typed before Tab completed a preset name, to cycle through
+    /// the other names on the next Tab.
+    completing: Option<String>,
+    celebration_until: Option<Instant>,
+    exit_at: Option<Instant>,
+    display: DisplayMode,
+    /// Dims everything but the digits.
+    focus_mode: bool,
+    /// Shows seconds in the digits; minutes only otherwise.
+    show_seconds: bool,
+    /// Magnifies the banner to fill the terminal; `banner_scale` otherwise.
+    auto_fill: bool,
+    message: Option<(String, Instant)>,
+    #[cfg(feature = "clipboard")]
+    clipboard: clipboard::SystemClipboard,
+    observers: Vec<Sender<State>>,
+    /// Feeds the `--events` stream when given.
+    events: Option<events::Emitter>,
+    published: Option<(Status, u64, Option<String>, Option<String>)>,
+    stats: Stats,
+    summary: Option<ListState>,
+    /// Work sessions completed since the last long break, in cycle mode.
+    cycle_count: u32,
+    /// Breaks skipped and the day they were skipped on, kept in the saved
+    /// state across the runs of a day.
+    breaks_skipped: (NaiveDate, u32),
+    /// Last suggestion picked for a break, not to be picked twice in a row.
+    last_suggestion: Option<String>,
+    /// Lines of `suggestions_file`, read at the first break after the
+    /// config was loaded.
+    suggestions_file: Option<Vec<String>>,
+    /// Routine the cycle settings come from, if one was started.
+    routine: Option<String>,
+    /// Whether the terminal lost focus during a work session, to be told
+    /// off once it's back.
+    wandered: bool,
+    /// Shows the reminder to get back to work until the next key press.
+    nag: bool,
+    /// Last key pressed, to tell when it's held down.
+    held: Option<Hold>,
+    /// When the pending duration starts by itself, as set with `--at`.
+    scheduled: Option<DateTime<Local>>,
+    /// Alarm of the last finished session and when it started ringing.
+    alarm: Option<(Instant, Alarm)>,
+    /// Bells and flashes waiting to be written to the terminal, each with
+    /// when it's due.
+    rings: Vec<(Instant, Ring)>,
+    /// Set when the config file is to be opened in `$EDITOR`, which needs
+    /// the terminal.
+    edit_config: bool,
+    /// Titles and bodies of the notifications waiting to be sent, and
+    /// whether each is urgent.
+    notifications: Vec<(String, String, bool)>,
+    /// Text waiting to be put on the clipboard through the terminal.
+    osc52: Option<String>,
+    /// Work session waiting for its note before going into the history log.
+    unnoted: Option<history::Entry>,
+    keymap: Keymap,
+    locale: Locale,
+    config: Config,
 }
 
 impl App {
-    fn new() -> App {
-        App {
+    fn new(config: Config, keymap: Keymap) -> App {
+        let mut timers: Vec<Timer> = config.timers.iter().map(Timer::new).collect();
+        if timers.is_empty() {
+            timers.push(Timer::new(DEFAULT_TIMER));
+        }
+        let first = config.initial_duration(SessionKind::Work);
+        sound::set_silent(config.silent);
+        for timer in timers.iter_mut() {
+            timer.time = first;
+            timer.remain = first;
+        }
+
+        let mut app = App {
             input_str: String::from(""),
             edit_mode: false,
-            reset: false,
-            time: Duration::new(0, 0),
+            input_kind: InputKind::Duration,
+            input_error: None,
+            timers,
+            paused_all: Vec::new(),
+            current: 0,
+            presets: None,
+            picker: None,
+            history: None,
+            overview: None,
+            breakdown: None,
+            history_cache: history::Cache::default(),
             time_str: String::from("00:00"),
             cursor_position: 0,
+            completing: None,
+            celebration_until: None,
+            exit_at: None,
+            display: config.display_for(SessionKind::Work),
+            focus_mode: config.focus_mode,
+            show_seconds: config.show_seconds,
+            auto_fill: config.auto_fill,
+            message: None,
+            #[cfg(feature = "clipboard")]
+            clipboard: clipboard::SystemClipboard::default(),
+            observers: Vec::new(),
+            events: None,
+            published: None,
+            stats: Stats::default(),
+            summary: None,
+            cycle_count: 0,
+            breaks_skipped: (Local::now().date_naive(), 0),
+            last_suggestion: None,
+            suggestions_file: None,
+            routine: None,
+            wandered: false,
+            nag: false,
+            held: None,
+            scheduled: None,
+            alarm: None,
+            rings: Vec::new(),
+            edit_config: false,
+            unnoted: None,
+            notifications: Vec::new(),
+            osc52: None,
+            keymap,
+            locale: Locale::default(),
+            config,
+        };
+        match Locale::load(&app.config) {
+            Ok(locale) => app.locale = locale,
+            Err(err) => app.show_message(err),
         }
+        app.refresh_time_str();
+        app
     }
 
-    fn on_tick(&mut self, remain: String) {
-        self.time_str = remain;
+    /// The timer on screen.
+    fn timer(&self) -> &Timer {
+        &self.timers[self.current]
     }
 
-    fn enter_char(&mut self, new_char: char) {
-        self.input_str.push(new_char);
-
-        self.move_cursor_right();
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timers[self.current]
     }
 
-    fn submit_time(&mut self) {
-        let duration = self.parse_duration(self.input_str.as_str());
-        match duration {
-            Some(value) => {
-                self.time = value;
-                self.input_str.clear();
-                self.reset_cursor();
-                self.reset = true;
-                self.edit_mode = false;
-            }
-            None => {}
+    fn refresh_time_str(&mut self) {
+        let timer = self.timer();
+        if let Some(over) = self.overtime() {
+            self.time_str = overtime_fmt(over.as_secs(), self.config.overtime_style);
+            return;
         }
+
+        let shown = match self.display {
+            DisplayMode::Remaining => timer.remain,
+            DisplayMode::Elapsed => timer.time.saturating_sub(timer.remain),
+            DisplayMode::End if timer.is_paused() => {
+                self.time_str = String::from("--:--");
+                return;
+            }
+            DisplayMode::End => match self.end_time() {
+                Some(end) => {
+                    self.time_str = self.format_clock(end);
+                    return;
+                }
+                // Idle timers have no end yet and show their duration.
+                None => timer.remain,
+            },
+        };
+        // Sessions under a minute would be mostly zeros.
+        let short = timer.time.as_secs() > 0 && timer.time.as_secs() < SECS_IN_MIN as u64;
+        self.time_str = match (short, self.shows_tenths()) {
+            (false, false) if !self.show_seconds => {
+                minutes_fmt(shown.as_secs(), self.config.round_up_minutes)
+            }
+            (true, false) => format!("0:{:02}", shown.as_secs()),
+            (true, true) => format!("0:{:02}.{}", shown.as_secs(), shown.subsec_millis() / 100),
+            (false, false) => remain_to_fmt(shown.as_secs()),
+            (false, true) => remain_to_fmt_tenths(shown),
+        };
     }
 
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.cursor_position != 0;
-        if is_not_cursor_leftmost {
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - 1;
-            let before_char_to_delete = self.input_str.chars().take(from_left_to_current_index);
-            let after_char_to_delete = self.input_str.chars().skip(current_index);
-            self.input_str = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
-        }
+    /// Whether the digits show tenths of a second right now.
+    fn shows_tenths(&self) -> bool {
+        let timer = self.timer();
+        self.config.tenths
+            && self.display != DisplayMode::End
+            && timer.is_active()
+            && (timer.remain.as_secs() < TENTHS_BELOW_SECS
+                || timer.time.as_secs() < SECS_IN_MIN as u64)
     }
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input_str.len())
+    fn toggle_display(&mut self) {
+        self.display = match self.display {
+            DisplayMode::Remaining => DisplayMode::Elapsed,
+            DisplayMode::Elapsed => DisplayMode::End,
+            DisplayMode::End => DisplayMode::Remaining,
+        };
+        self.refresh_time_str();
+
+        // The arrow in the status line only shows during a session.
+        let shown = match self.display {
+            DisplayMode::Remaining => "message.remaining",
+            DisplayMode::Elapsed => "message.elapsed",
+            DisplayMode::End => "message.end",
+        };
+        let message = self
+            .locale
+            .format(shown, &[("arrow", self.display_indicator())]);
+        self.show_message(message);
     }
 
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+    fn toggle_focus(&mut self) {
+        self.focus_mode = !self.focus_mode;
     }
 
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.cursor_position.saturating_add(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_right);
+    fn toggle_seconds(&mut self) {
+        self.show_seconds = !self.show_seconds;
+        self.refresh_time_str();
     }
 
-    fn reset_cursor(&mut self) {
-        self.cursor_position = 0;
+    fn toggle_fill(&mut self) {
+        self.auto_fill = !self.auto_fill;
+        let message = if self.auto_fill {
+            self.tr("message.fill").to_string()
+        } else {
+            let scale = self.config.banner_scale.max(1).to_string();
+            self.locale.format("message.scale", &[("scale", &scale)])
+        };
+        self.show_message(message);
     }
 
-    fn enter_edit(&mut self) {
-        self.edit_mode = true;
+    /// How many times the banner can be magnified: as far as `width` and
+    /// `height` allow with auto-fill, up to `banner_scale` otherwise.
+    fn banner_scale(&self, content: &[String], width: u16, height: u16) -> u16 {
+        let fit = fill_scale(content, width, height);
+        if self.auto_fill {
+            fit
+        } else {
+            self.config.banner_scale.clamp(1, fit)
+        }
     }
 
-    fn exit_edit(&mut self) {
-        self.edit_mode = false;
-        self.input_str.clear();
-        self.reset_cursor();
+    /// Whether strict mode guards `action` now: pausing the running work
+    /// session; restarting, shortening or stopping it or starting another
+    /// in its place while it runs or is paused; quitting while one does.
+    fn strict_guards(&self, action: Action) -> bool {
+        let working = |timer: &Timer| timer.is_active() && timer.kind == SessionKind::Work;
+        let running =
+            |timer: &Timer| timer.state == TimerState::Running && timer.kind == SessionKind::Work;
+        self.config.strict
+            && match action {
+                Action::Quit => self.timers.iter().any(working),
+                Action::PauseAll => self.timers.iter().any(running),
+                Action::Toggle => running(self.timer()),
+                Action::Start
+                | Action::Reset
+                | Action::Stop
+                | Action::SubtractMinute
+                | Action::SubtractFiveMinutes => working(self.timer()),
+                _ => false,
+            }
     }
 
-    fn parse_duration(&self, duration: &str) -> Option<Duration> {
-        if duration.len() != 5 && duration.len() != 8 {
-            return None;
+    /// Whether strict mode refuses `action`, flashing the screen and
+    /// telling how to get through when it does: with `strict_escape`, a
+    /// key held down for `STRICT_HOLD` or pressed with alt.
+    fn strict_refuses(&mut self, action: Action) -> bool {
+        if !self.strict_guards(action) {
+            return false;
         }
 
-        let re = Regex::new(r"(:?([01][0-9]|2[0-3]):)?([0-5][0-9]):([0-5][0-9])").unwrap();
-        let caps = re.captures(duration);
+        let now = Instant::now();
+        let escape = self.config.strict_escape;
+        let held = match self
+            .held
+            .as_mut()
+            .filter(|hold| now - hold.last <= HOLD_GAP)
+        {
+            Some(hold) if escape == StrictEscape::Alt && hold.alt => return false,
+            Some(hold) if escape == StrictEscape::Hold && hold.last - hold.since >= STRICT_HOLD => {
+                hold.spent = true;
+                return false;
+            }
+            Some(hold) => hold.last - hold.since,
+            None => Duration::ZERO,
+        };
 
-        match caps {
-            Some(c) => {
-                let h: u64 = c.get(2).map_or(0, |m| m.as_str().parse().unwrap());
-                let m: u64 = c.get(3).map_or(0, |m| m.as_str().parse().unwrap());
-                let s: u64 = c.get(4).map_or(0, |m| m.as_str().parse().unwrap());
+        let name = match action {
+            Action::Toggle => "pause",
+            action => action.name(),
+        };
+        let message = match escape {
+            StrictEscape::Hold => {
+                let secs = (STRICT_HOLD - held).as_secs_f32().ceil().to_string();
+                self.locale
+                    .format("status.strict", &[("action", name), ("secs", &secs)])
+            }
+            StrictEscape::Alt => self.locale.format("status.strict_alt", &[("action", name)]),
+        };
+        self.show_message(message);
+        if held.is_zero() {
+            self.ring(sound::alarm(0, true));
+        }
+        true
+    }
 
-                return Some(Duration::new(3600 * h + 60 * m + s, 0));
+    /// Records a key press, telling the repeats of a key held down from
+    /// separate presses. Returns whether it repeats a spent hold.
+    fn press(&mut self, key: KeyEvent) -> bool {
+        let now = Instant::now();
+        let (code, alt) = (key.code, key.modifiers.contains(KeyModifiers::ALT));
+        match &mut self.held {
+            Some(hold) if hold.code == code && now - hold.last <= HOLD_GAP => {
+                hold.last = now;
+                hold.alt = alt;
+                hold.spent
             }
-            None => {
-                return None;
+            _ => {
+                self.held = Some(Hold {
+                    code,
+                    alt,
+                    since: now,
+                    last: now,
+                    spent: false,
+                });
+                false
             }
-        };
+        }
     }
 
-    fn reset(&mut self) {
-        self.reset = true;
+    /// Style of what's around the digits: the status line, the tabs and
+    /// the footer.
+    fn chrome_style(&self) -> Style {
+        if self.focus_mode {
+            Style::default().add_modifier(Modifier::DIM)
+        } else {
+            Style::default()
+        }
     }
 
-    fn stop(&mut self) {
-        self.time = Duration::new(0, 0);
-        self.time_str = String::from("00:00");
-        self.reset = true;
+    /// Arrow telling which way the digits are counting.
+    fn display_indicator(&self) -> &'static str {
+        match self.display {
+            DisplayMode::Remaining => "▼",
+            DisplayMode::Elapsed => "▲",
+            DisplayMode::End => "@",
+        }
     }
-}
 
-fn remain_to_fmt(remain: u64) -> String {
-    let (hours, minutes, seconds) = (
-        remain / SECS_IN_HOUR as u64,
-        (remain % SECS_IN_HOUR as u64) / SECS_IN_MIN as u64,
-        remain % SECS_IN_MIN as u64,
-    );
+    fn is_active(&self) -> bool {
+        self.timer().is_active()
+    }
 
-    if hours == 0 {
-        format!("{:02}:{:02}", minutes, seconds)
-    } else {
-        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    fn is_paused(&self) -> bool {
+        self.timer().is_paused()
     }
-}
 
-fn generate_content(text: &str) -> Vec<String> {
-    let mut content: Vec<String> = Vec::new();
+    fn remaining(&self) -> Duration {
+        self.timer().remaining()
+    }
 
-    let standard_font = FIGfont::standard().unwrap();
+    fn start_countdown(&mut self) {
+        self.end_celebration();
+        self.timer_mut().start_countdown();
+        self.refresh_time_str();
+        self.save_state();
+    }
 
-    let figlet = standard_font.convert(text).unwrap();
-    let letter_count = figlet.characters.len();
-    let mut text_height = 0;
+    /// Advances every timer, so the ones in the background keep time too.
+    /// Returns `true` when any session has just completed.
+    fn update_countdown(&mut self) -> bool {
+        let mut finished = false;
 
-    if figlet.characters.len() > 0 {
-        text_height = figlet.characters.get(0).unwrap().height;
+        for index in 0..self.timers.len() {
+            if !self.timers[index].update() {
+                self.warn_break_end(index);
+                continue;
+            }
+
+            finished = true;
+            let sound = self.config.sounds.get(&self.timers[index].kind).cloned();
+            self.alarm = Some((Instant::now(), Alarm::new(sound)));
+            if index == self.current {
+                self.finish();
+            }
+            if self.timers.len() > 1 {
+                let message = self
+                    .locale
+                    .format("message.finished", &[("name", &self.timers[index].name)]);
+                self.show_message(message);
+            }
+            self.queue_notification(index);
+            self.complete(index);
+        }
+
+        self.suggest_break_activity();
+        self.refresh_time_str();
+        finished
     }
 
-    for line_no in 0..text_height {
-        let mut line = String::from("");
-        for letter_no in 0..letter_count {
-            line.push_str(
-                format!(
-                    "{}",
-                    figlet
-                        .characters
-                        .get(letter_no)
-                        .unwrap()
-                        .characters
-                        .get(line_no as usize)
-                        .unwrap()
-                )
-                .as_str(),
-            );
+    /// Lines up the notification of the session timer `index` just
+    /// completed, when notifications are on.
+    fn queue_notification(&mut self, index: usize) {
+        self.notify(index, false);
+    }
+
+    fn notify(&mut self, index: usize, urgent: bool) {
+        if self.config.notifications.is_none() {
+            return;
         }
-        content.push(line);
+
+        let timer = &self.timers[index];
+        let body = self
+            .config
+            .notification_body
+            .replace("{kind}", self.locale.get(kind_label(timer.kind)))
+            .replace("{timer}", &timer.name)
+            .replace("{label}", timer.label.as_deref().unwrap_or_default())
+            .replace(
+                "{done_text}",
+                self.config.done_text.as_deref().unwrap_or_default(),
+            );
+        self.notifications
+            .push((String::from("pomidor"), body, urgent));
     }
-    content
-}
 
-fn create_chunks(size: Rect, top_h: u16, text_h: u16, bot_h: u16, input_h: u16) -> Rc<[Rect]> {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(top_h),
-                Constraint::Length(text_h),
-                Constraint::Length(bot_h),
-                Constraint::Max(input_h),
-            ]
-            .as_ref(),
-        )
-        .split(size);
+    /// Picks something to do for the break on screen, once per break.
+    fn suggest_break_activity(&mut self) {
+        let timer = self.timer();
+        if !self.config.break_suggestions
+            || !timer.kind.is_break()
+            || !timer.is_active()
+            || timer.suggestion.is_some()
+        {
+            return;
+        }
 
-    chunks
-}
+        // Once per break, found or not.
+        self.timer_mut().suggestion = Some(None);
+        if self.suggestions_file.is_none() {
+            if let Some(path) = &self.config.suggestions_file {
+                match std::fs::read_to_string(config::expand_home(path)) {
+                    Ok(content) => {
+                        self.suggestions_file = Some(
+                            content
+                                .lines()
+                                .map(str::trim)
+                                .filter(|line| !line.is_empty())
+                                .map(String::from)
+                                .collect(),
+                        )
+                    }
+                    Err(err) => self.show_error("message.suggestions", err),
+                }
+            }
+        }
+        let suggestions = self
+            .suggestions_file
+            .as_ref()
+            .unwrap_or(&self.config.suggestions);
+        if suggestions.is_empty() {
+            return;
+        }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
-    let size = f.size();
-    let mut text: Vec<Line> = Vec::new();
+        // The clock is random enough to pick a suggestion with.
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |now| now.subsec_nanos() as usize);
+        let mut index = nanos % suggestions.len();
+        if suggestions.len() > 1 && self.last_suggestion.as_ref() == Some(&suggestions[index]) {
+            index = (index + 1) % suggestions.len();
+        }
 
-    let content = generate_content(app.time_str.as_str());
+        let suggestion = suggestions[index].clone();
+        self.last_suggestion = Some(suggestion.clone());
+        self.timer_mut().suggestion = Some(Some(suggestion));
+    }
 
-    let text_height = content.len() + MARGIN_LINES + INPUT_HEIGHT;
+    /// Line of the breathing pacer, while a break runs.
+    fn breathing(&self) -> Option<Line<'_>> {
+        let timer = self.timer();
+        let breathing = &self.config.breathing;
+        if !breathing.enabled || !timer.kind.is_break() || timer.state != TimerState::Running {
+            return None;
+        }
 
-    if text_height as u16 > size.height {
-        return;
+        let elapsed = timer.time.saturating_sub(timer.remaining());
+        let (phase, lit) = breathing::pace(breathing, elapsed)?;
+        Some(Line::from(vec![
+            Span::raw(breathing::bar(lit)),
+            Span::styled(
+                format!(" {:<6}", self.tr(phase.label())),
+                self.chrome_style(),
+            ),
+        ]))
     }
 
-    let blank_height: u16 = size.height - (text_height as u16);
+    /// Thin bar of how far through the current minute the running session
+    /// is, when `minute_progress` is on.
+    fn minute_progress(&self) -> Option<Line<'_>> {
+        let timer = self.timer();
+        if !self.config.minute_progress || timer.state != TimerState::Running {
+            return None;
+        }
 
-    let top_height: u16 = blank_height / 2;
-    let mut bot_height: i16 = (blank_height / 2) as i16;
-    let mut input_height: u16 = 0;
+        let elapsed = timer.time.saturating_sub(timer.remaining());
+        let fraction = (elapsed.as_millis() % 60_000) as f32 / 60_000.0;
+        Some(Line::from(Span::styled(
+            minute_bar(fraction, MINUTE_BAR_WIDTH),
+            self.chrome_style(),
+        )))
+    }
 
-    if app.edit_mode {
-        bot_height = bot_height - INPUT_HEIGHT as i16;
-        if bot_height < 0 {
-            bot_height = 0;
+    /// The suggestion to show beneath the countdown, if any.
+    fn suggestion(&self) -> Option<&str> {
+        let timer = self.timer();
+        if !self.config.break_suggestions || !timer.kind.is_break() || !timer.is_active() {
+            return None;
         }
-        input_height = INPUT_HEIGHT as u16;
+        timer.suggestion.as_ref()?.as_deref()
     }
 
-    for line in content {
-        text.push(Line::from(line));
+    /// Gives a heads-up once when the break on timer `index` is about to
+    /// end.
+    fn warn_break_end(&mut self, index: usize) {
+        let secs = match self.config.break_end_warning_secs {
+            Some(secs) => secs,
+            None => return,
+        };
+        let timer = &mut self.timers[index];
+        if timer.warned
+            || !timer.kind.is_break()
+            || timer.state != TimerState::Running
+            || timer.remain.as_secs() >= secs
+        {
+            return;
+        }
+
+        timer.warned = true;
+        let kind = self.locale.get(kind_label(timer.kind));
+        let message = self.locale.format(
+            "message.break_ends",
+            &[("kind", kind), ("time", &spelled_out(secs))],
+        );
+        self.show_message(message);
+        if self.config.bell && !self.quiet_hours_active() {
+            self.ring(sound::bell());
+        }
     }
 
-    let chunks = create_chunks(
-        size,
-        top_height,
-        text_height as u16,
-        bot_height as u16,
-        input_height,
-    );
+    /// Counts the session timer `index` just completed and, in cycle mode,
+    /// lines up the next one, or the summary once the last cycle is done.
+    fn complete(&mut self, index: usize) {
+        let timer = &self.timers[index];
+        let (kind, time) = (timer.kind, timer.time);
+        self.stats
+            .record(kind, time, timer.interruptions.len() as u32);
 
-    let create_block = |title: String| {
-        Block::default()
-            .borders(Borders::NONE)
-            .style(Style::default().fg(Color::Gray))
-            .title(Span::styled(
-                title,
-                Style::default().add_modifier(Modifier::BOLD),
-            ))
-    };
+        self.log_history(index);
+        if kind == SessionKind::Work {
+            self.clock_in_org(index);
+        }
 
-    let paragraph = Paragraph::new(text.clone())
-        .style(Style::default().fg(Color::Gray))
-        .block(create_block(String::from("")))
-        .alignment(Alignment::Center);
-    f.render_widget(paragraph, chunks[1]);
+        if self.timers[index].queue.is_some() {
+            self.next_in_queue(index);
+            return;
+        }
 
-    if app.edit_mode {
-        let input = Paragraph::new(app.input_str.as_str())
-            .style(Style::default())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Session timer (format hh:mm:ss)"),
-            );
-        f.render_widget(input, chunks[3]);
-        f.set_cursor(
-            chunks[3].x + app.cursor_position as u16 + 1,
-            chunks[3].y + 1,
-        );
-    }
-}
+        let cycle = match self.config.cycle {
+            Some(cycle) => cycle,
+            None => return,
+        };
 
-fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    mut app: App,
-    tick_rate: Duration,
-) -> io::Result<()> {
-    let mut last_tick = Instant::now();
-    let mut start = Instant::now();
-    let mut deadline = Duration::new(0, 0);
+        let work_sessions = self.stats.work_sessions;
+        let last_cycle = cycle
+            .cycles
+            .is_some_and(|cycles| kind == SessionKind::Work && work_sessions >= cycles);
 
-    loop {
-        terminal.draw(|f| ui(f, &app))?;
+        let next = if last_cycle {
+            self.open_summary();
+            self.run_on_finish();
+            SessionKind::Work
+        } else {
+            let (next, count) = next_session(kind, self.cycle_count, cycle.long_break_interval);
+            self.cycle_count = count;
+            next
+        };
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        let timer = &mut self.timers[index];
+        timer.kind = next;
+        timer.time = cycle.duration(next);
+        if cycle.auto_start && !last_cycle {
+            timer.start_countdown();
+        } else {
+            timer.remain = timer.time;
+        }
 
-        if app.reset {
-            app.reset = false;
-            deadline = app.time;
-            start = Instant::now();
-        }
-
-        if crossterm::event::poll(timeout)? {
-            if app.edit_mode {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Enter => {
-                                app.submit_time();
-                            }
-                            KeyCode::Char(to_insert) => {
-                                app.enter_char(to_insert);
-                            }
-                            KeyCode::Backspace => {
-                                app.delete_char();
-                            }
-                            KeyCode::Left => {
-                                app.move_cursor_left();
-                            }
-                            KeyCode::Right => {
-                                app.move_cursor_right();
-                            }
-                            KeyCode::Esc => {
-                                app.exit_edit();
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            } else {
-                if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('e') => {
-                            app.enter_edit();
-                        }
-                        KeyCode::Char('r') => {
-                            app.reset();
-                        }
-                        KeyCode::Char('s') => {
-                            app.stop();
-                        }
-                        KeyCode::Char('q') => {
-                            return Ok(());
-                        }
-                        _ => {}
-                    }
-                }
-            }
+        if index == self.current {
+            self.display = self.config.display_for(next);
+            self.refresh_time_str();
+            self.save_state();
         }
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
+    }
 
-            if deadline.as_secs() == 0 {
-                continue;
+    /// Starts running `queue` on the current timer, in place of whatever
+    /// it was doing.
+    fn start_queue(&mut self, queue: Queue) {
+        self.end_early(Outcome::Abandoned);
+        self.timer_mut().queue = Some(queue);
+        self.next_in_queue(self.current);
+    }
+
+    /// Starts the next session of the queue of timer `index`. Once the
+    /// queue is done, the timer stays finished and the app quits as after
+    /// any other session, if it's meant to.
+    fn next_in_queue(&mut self, index: usize) {
+        let step = match self.timers[index].queue.as_mut().and_then(Queue::next) {
+            Some(step) => step,
+            None => {
+                self.timers[index].queue = None;
+                return;
             }
+        };
 
-            let mut elapsed = start.elapsed();
+        let (label, tags) = step
+            .label
+            .as_deref()
+            .map(history::split_tags)
+            .unwrap_or_default();
+        let timer = &mut self.timers[index];
+        timer.kind = step.kind;
+        timer.time = step.duration;
+        timer.label = label;
+        timer.tags = tags;
+        timer.start_countdown();
 
-            if deadline < elapsed {
-                start = Instant::now();
-                elapsed = start.elapsed();
-            }
-            let remain = deadline - elapsed;
-            let time_str = remain_to_fmt(remain.as_secs());
+        if index == self.current {
+            self.display = self.config.display_for(step.kind);
+            self.exit_at = None;
+            self.refresh_time_str();
+            self.save_state();
+        }
+    }
 
-            app.on_tick(time_str);
+    /// Appends the session timer `index` just completed to the history log,
+    /// asking for a note first when that's configured for work sessions.
+    fn log_history(&mut self, index: usize) {
+        let entry = self.history_entry(index, self.timers[index].time, Outcome::Completed);
+
+        let wants_note = entry.kind == SessionKind::Work && self.config.note_prompt;
+        if wants_note && !self.edit_mode {
+            self.unnoted = Some(entry);
+            self.prompt_note();
+        } else {
+            self.append_history(entry);
         }
     }
-}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    /// History entry of the session of timer `index`, `spent` being how
+    /// long it ran until now.
+    fn history_entry(&self, index: usize, spent: Duration, outcome: Outcome) -> history::Entry {
+        let timer = &self.timers[index];
+        let end: DateTime<FixedOffset> = Local::now().into();
 
-    let tick_rate = Duration::from_millis(250);
-    let app = App::new();
-    let res = run_app(&mut terminal, app, tick_rate);
+        history::Entry {
+            start: end - chrono::Duration::seconds(spent.as_secs() as i64),
+            end,
+            duration_secs: spent.as_secs(),
+            kind: timer.kind,
+            label: timer.label.clone(),
+            tags: timer.tags.clone(),
+            outcome,
+            note: None,
+            interruptions: timer.interruptions.clone(),
+        }
+    }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    /// Logs the session in progress on the current timer as ended early
+    /// with `outcome`. Sessions shorter than `misfire_secs` are ignored.
+    fn end_early(&mut self, outcome: Outcome) {
+        let timer = self.timer();
+        if !timer.is_active() {
+            return;
+        }
 
-    if let Err(err) = res {
-        println!("{err:?}");
+        let spent = timer.time.saturating_sub(timer.remaining());
+        if spent.as_secs() < self.config.misfire_secs {
+            return;
+        }
+
+        self.stats.record_abandoned(timer.kind);
+        let entry = self.history_entry(self.current, spent, outcome);
+        self.append_history(entry);
     }
 
-    Ok(())
+    fn append_history(&mut self, entry: history::Entry) {
+        if let Err(err) = history::rotate(self.config.history_max_bytes) {
+            self.show_error("message.history", err);
+        }
+        if let Err(err) = entry.append() {
+            self.show_error("message.history", err);
+        }
+        self.refresh_history();
+    }
+
+    fn toggle_history(&mut self) {
+        if self.history.take().is_none() {
+            self.history = Some(HistoryPane::default());
+            self.refresh_history();
+        }
+    }
+
+    /// Reloads today's sessions into the history pane, if it's open and
+    /// not showing a search.
+    fn refresh_history(&mut self) {
+        match &self.history {
+            Some(pane) if pane.search.is_none() => {}
+            _ => return,
+        }
+        let today = Local::now().date_naive();
+        let entries = match history::load_between(today, today) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.show_error("message.history", err);
+                Vec::new()
+            }
+        };
+
+        if let Some(pane) = self.history.as_mut() {
+            pane.entries = entries
+                .into_iter()
+                .rev()
+                .filter(|entry| entry.start.date_naive() == today)
+                .collect();
+            let selected = match pane.entries.len() {
+                0 => None,
+                count => Some(pane.list.selected().unwrap_or(0).min(count - 1)),
+            };
+            pane.list.select(selected);
+        }
+    }
+
+    /// Starts typing a search into the history pane, opening it first.
+    fn prompt_history_search(&mut self) {
+        if self.history.is_none() {
+            self.toggle_history();
+        }
+        if let Some(pane) = self.history.as_mut() {
+            pane.typing = true;
+            self.input_str.clear();
+            self.reset_cursor();
+        }
+    }
+
+    /// Lists the sessions matching what was typed, across every day; an
+    /// empty search goes back to today's sessions.
+    fn submit_history_search(&mut self) {
+        let query = self.input_str.trim().to_string();
+        self.input_str.clear();
+        self.reset_cursor();
+        let pane = match self.history.as_mut() {
+            Some(pane) => pane,
+            None => return,
+        };
+        pane.typing = false;
+        pane.entries.clear();
+        pane.list.select(None);
+
+        if query.is_empty() {
+            pane.search = None;
+            self.refresh_history();
+            return;
+        }
+        match history::Search::new(&query) {
+            Ok(search) => pane.search = Some(search),
+            Err(err) => return self.show_error("message.history", err),
+        }
+        self.fill_search(HISTORY_PAGE as usize);
+    }
+
+    /// Goes back to today's sessions, or closes the pane when it already
+    /// shows them.
+    fn close_history_search(&mut self) {
+        match self.history.as_mut() {
+            Some(pane) if pane.search.is_some() => {
+                pane.search = None;
+                pane.list.select(None);
+                self.refresh_history();
+            }
+            _ => self.history = None,
+        }
+    }
+
+    /// Reads the logs of the search until it has `wanted` matches or runs
+    /// out of logs.
+    fn fill_search(&mut self, wanted: usize) {
+        let pane = match self.history.as_mut() {
+            Some(pane) => pane,
+            None => return,
+        };
+        let search = match pane.search.as_mut() {
+            Some(search) => search,
+            None => return,
+        };
+
+        while pane.entries.len() < wanted {
+            match search.next_log() {
+                Ok(Some(matches)) => pane.entries.extend(matches),
+                Ok(None) => break,
+                Err(err) => {
+                    self.show_error("message.history", err);
+                    return;
+                }
+            }
+        }
+        if pane.list.selected().is_none() && !pane.entries.is_empty() {
+            pane.list.select(Some(0));
+        }
+    }
+
+    /// Scrolls the history pane by `offset` rows, stopping at either end.
+    /// A search reads further logs as the end of its matches comes near.
+    fn move_history(&mut self, offset: isize) {
+        if let Some(pane) = &self.history {
+            let index = pane.list.selected().unwrap_or(0) as isize;
+            let wanted = (index + offset).max(0) as usize + HISTORY_PAGE as usize;
+            self.fill_search(wanted);
+        }
+        if let Some(pane) = self.history.as_mut() {
+            let count = pane.entries.len() as isize;
+            if count > 0 {
+                let index = pane.list.selected().unwrap_or(0) as isize;
+                pane.list
+                    .select(Some((index + offset).clamp(0, count - 1) as usize));
+            }
+        }
+    }
+
+    /// Deletes the highlighted session from the log once `confirm` comes
+    /// after `d`; anything else calls it off.
+    fn delete_history(&mut self, confirm: bool) {
+        let pane = match self.history.as_mut() {
+            Some(pane) if pane.confirm => pane,
+            _ => return,
+        };
+        pane.confirm = false;
+        let selected = pane
+            .list
+            .selected()
+            .and_then(|index| pane.entries.get(index));
+        let entry = match (selected, confirm) {
+            (Some(entry), true) => entry.clone(),
+            _ => return,
+        };
+
+        match history::remove(&entry) {
+            Ok(true) => self.show_message(self.tr("message.deleted").to_string()),
+            Ok(false) => self.show_message(self.tr("message.not_found").to_string()),
+            Err(err) => return self.show_error("message.history", err),
+        }
+
+        // A search keeps its place, without the deleted session.
+        if let Some(pane) = self.history.as_mut().filter(|pane| pane.search.is_some()) {
+            if let Some(index) = pane.list.selected() {
+                pane.entries.remove(index);
+                let count = pane.entries.len();
+                pane.list.select((count > 0).then(|| index.min(count - 1)));
+            }
+        }
+        self.refresh_history();
+    }
+
+    fn prompt_note(&mut self) {
+        self.edit_mode = true;
+        self.input_kind = InputKind::Note;
+        self.input_error = None;
+        self.input_str.clear();
+        self.reset_cursor();
+    }
+
+    /// Logs the session waiting for its note, with the typed note unless
+    /// `skip` is set or nothing was typed.
+    fn submit_note(&mut self, skip: bool) {
+        let note = self.input_str.trim();
+        let note = (!skip && !note.is_empty()).then(|| note.to_string());
+
+        if let Some(mut entry) = self.unnoted.take() {
+            entry.note = note;
+            self.append_history(entry);
+        }
+        self.exit_edit();
+    }
+
+    /// Writes what must outlive the app before it quits.
+    fn quit(&mut self) {
+        if let Some(entry) = self.unnoted.take() {
+            self.append_history(entry);
+        }
+        self.persist(State::idle());
+    }
+
+    /// Clocks the work session timer `index` just completed into the org
+    /// file, if there's one.
+    fn clock_in_org(&mut self, index: usize) {
+        let file = match &self.config.org_file {
+            Some(file) => file,
+            None => return,
+        };
+        let timer = &self.timers[index];
+        let heading = timer.label.as_ref().unwrap_or(&self.config.org_heading);
+
+        // Pauses stay inside the clock, as they did on the wall.
+        let end = Local::now();
+        let start = timer.started_at.unwrap_or_else(|| {
+            chrono::Duration::from_std(timer.time).map_or(end, |time| end - time)
+        });
+
+        if let Err(err) = org::log(file, heading, start, end) {
+            self.show_error("message.org_log", err);
+        }
+    }
+
+    /// Runs the `on_finish` command of the current routine, if any.
+    fn run_on_finish(&mut self) {
+        let command = self
+            .routine
+            .as_ref()
+            .and_then(|name| self.config.routines.get(name))
+            .and_then(|routine| routine.on_finish.clone());
+
+        if let Some(command) = command {
+            let spawned = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn();
+            if let Err(err) = spawned {
+                self.show_error("message.on_finish", err);
+            }
+        }
+    }
+
+    fn open_summary(&mut self) {
+        let mut list = ListState::default();
+        list.select(Some(0));
+        self.summary = Some(list);
+    }
+
+    /// Closes the summary, starting a fresh run.
+    fn close_summary(&mut self) {
+        self.summary = None;
+        self.stats = Stats::default();
+        self.cycle_count = 0;
+    }
+
+    fn move_summary(&mut self, offset: isize) {
+        let count = summary_lines(&self.stats, self.skipped_today(), &self.locale).len() as isize;
+        if let Some(list) = self.summary.as_mut() {
+            let index = list.selected().unwrap_or(0) as isize;
+            list.select(Some((index + offset).clamp(0, count - 1) as usize));
+        }
+    }
+
+    fn pause(&mut self) {
+        if self.strict_refuses(Action::Toggle) {
+            return;
+        }
+        self.timer_mut().pause();
+        self.refresh_time_str();
+        self.save_state();
+    }
+
+    fn resume(&mut self) {
+        self.timer_mut().resume();
+        self.save_state();
+    }
+
+    /// Whether timers paused by `pause_all` are still waiting on it; ones
+    /// resumed on their own since don't count.
+    fn all_paused(&self) -> bool {
+        self.paused_all.iter().any(|&index| {
+            self.timers
+                .get(index)
+                .is_some_and(|timer| timer.state == TimerState::Paused)
+        })
+    }
+
+    /// Pauses every running timer at once, or resumes the ones it paused.
+    fn pause_all(&mut self) {
+        if self.all_paused() {
+            for index in std::mem::take(&mut self.paused_all) {
+                if let Some(timer) = self.timers.get_mut(index) {
+                    timer.resume();
+                }
+            }
+            self.show_message(self.tr("message.resumed_all").to_string());
+        } else {
+            if self.strict_refuses(Action::PauseAll) {
+                return;
+            }
+            self.paused_all = (0..self.timers.len())
+                .filter(|&index| self.timers[index].state == TimerState::Running)
+                .collect();
+            if self.paused_all.is_empty() {
+                self.show_message(self.tr("message.none_running").to_string());
+                return;
+            }
+            for &index in &self.paused_all {
+                self.timers[index].pause();
+            }
+        }
+        self.refresh_time_str();
+        self.save_state();
+    }
+
+    fn acknowledge(&mut self) {
+        self.timer_mut().acknowledge();
+        self.end_celebration();
+    }
+
+    /// Adds `delta_secs` (or removes, when negative) to the current timer,
+    /// for `action`.
+    fn adjust(&mut self, action: Action, delta_secs: i64) {
+        if self.strict_refuses(action) {
+            return;
+        }
+        let active = self.is_active();
+        if !active {
+            self.end_celebration();
+        } else if self.timer().time.as_secs() as i64 + delta_secs <= 0 {
+            // Shortened to nothing, the session is stopped.
+            self.end_early(Outcome::Abandoned);
+        }
+
+        self.timer_mut().adjust(delta_secs);
+        self.refresh_time_str();
+
+        if active {
+            self.save_state();
+        }
+    }
+
+    /// Brings the remaining time of the session up to the next multiple
+    /// of `round_minutes`; a session already on one is left alone.
+    fn round_up(&mut self) {
+        if !self.is_active() || self.config.round_minutes == 0 {
+            return;
+        }
+
+        let remaining = self.remaining().as_secs();
+        let rounded = round_up(remaining, self.config.round_minutes * SECS_IN_MIN as u64);
+        if rounded > remaining {
+            self.adjust(Action::RoundUp, (rounded - remaining) as i64);
+        }
+    }
+
+    /// Clears the pending duration of an idle timer.
+    fn clear_duration(&mut self) {
+        if !self.is_active() {
+            self.acknowledge();
+            let timer = self.timer_mut();
+            timer.time = Duration::new(0, 0);
+            timer.remain = timer.time;
+            self.refresh_time_str();
+        }
+    }
+
+    /// Starts the pending duration unless a session is already under way.
+    fn start(&mut self) {
+        if !self.is_active() {
+            self.reset();
+        }
+    }
+
+    fn space_action(&self) -> Option<SpaceAction> {
+        let timer = self.timer();
+        match timer.state {
+            TimerState::Finished => Some(SpaceAction::Acknowledge),
+            TimerState::Paused => Some(SpaceAction::Resume),
+            TimerState::Running => Some(SpaceAction::Pause),
+            TimerState::Idle
+                if timer.time.as_secs() > 0 || timer.last_duration.as_secs() > 0 =>
+            {
+                Some(SpaceAction::Start)
+            }
+            TimerState::Idle => None,
+        }
+    }
+
+    fn toggle(&mut self) {
+        match self.space_action() {
+            Some(SpaceAction::Start) => self.reset(),
+            Some(SpaceAction::Pause) => self.pause(),
+            Some(SpaceAction::Resume) => self.resume(),
+            Some(SpaceAction::Acknowledge) => self.acknowledge(),
+            None => {}
+        }
+    }
+
+    /// Switches to the timer `offset` tabs away, wrapping around.
+    fn cycle_timer(&mut self, offset: isize) {
+        let count = self.timers.len() as isize;
+        self.select_timer((self.current as isize + offset).rem_euclid(count) as usize);
+    }
+
+    /// Switches to the timer called `name`, adding it if there's none yet.
+    fn switch_to(&mut self, name: String) {
+        let index = match self.timers.iter().position(|timer| timer.name == name) {
+            Some(index) => index,
+            None => {
+                let mut timer = Timer::new(name);
+                timer.time = self.config.default_duration;
+                timer.remain = timer.time;
+                self.timers.push(timer);
+                self.timers.len() - 1
+            }
+        };
+        self.select_timer(index);
+    }
+
+    /// Switches to the `number`th timer, counted from 1, if there's one.
+    fn select_numbered(&mut self, number: usize) {
+        if number > self.timers.len() {
+            let message = self
+                .locale
+                .format("message.no_timer", &[("number", &number.to_string())]);
+            self.show_message(message);
+            return;
+        }
+        self.select_timer(number - 1);
+    }
+
+    fn select_timer(&mut self, index: usize) {
+        if index == self.current {
+            return;
+        }
+
+        self.current = index;
+        self.display = self.config.display_for(self.timer().kind);
+        self.end_celebration();
+        self.refresh_time_str();
+        self.save_state();
+    }
+
+    /// Builds the `62% · 15:30 / 25:00` line shown under the digits while a
+    /// session is running.
+    fn progress_text(&self) -> Option<String> {
+        if !self.is_active() {
+            return None;
+        }
+
+        let timer = self.timer();
+        let elapsed = timer.time.saturating_sub(timer.remain);
+        let mut parts: Vec<String> = Vec::new();
+
+        if self.config.show_percent && !timer.time.is_zero() {
+            let percent = elapsed.as_millis() * 100 / timer.time.as_millis();
+            parts.push(format!("{}%", percent));
+        }
+
+        let times = match (self.config.show_elapsed, self.config.show_total) {
+            (true, true) => Some(format!(
+                "{} / {}",
+                remain_to_fmt(elapsed.as_secs()),
+                remain_to_fmt(timer.time.as_secs())
+            )),
+            (true, false) => Some(remain_to_fmt(elapsed.as_secs())),
+            (false, true) => Some(remain_to_fmt(timer.time.as_secs())),
+            (false, false) => None,
+        };
+        parts.extend(times);
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" · "))
+        }
+    }
+
+    fn quiet_hours_active(&self) -> bool {
+        self.config
+            .quiet_hours
+            .is_some_and(|quiet_hours| quiet_hours.contains(Local::now().time()))
+    }
+
+    fn format_clock(&self, time: DateTime<Local>) -> String {
+        if self.config.clock_24h {
+            time.format("%H:%M").to_string()
+        } else {
+            time.format("%-I:%M %p").to_string()
+        }
+    }
+
+    /// Wall-clock time at which the running session reaches zero.
+    fn end_time(&self) -> Option<DateTime<Local>> {
+        if self.timer().state != TimerState::Running {
+            return None;
+        }
+
+        chrono::Duration::from_std(self.timer().remain)
+            .ok()
+            .map(|remain| Local::now() + remain)
+    }
+
+    /// Builds the line shown under the digits, if there is anything to show.
+    fn status_text(&self) -> Option<String> {
+        let mut parts: Vec<String> = Vec::new();
+
+        if self.config.strict {
+            parts.push(self.tr("status.strict_mode").to_string());
+        }
+        if self.all_paused() {
+            parts.push(self.tr("status.paused_all").to_string());
+        }
+        if !profile::is_default() {
+            parts.push(
+                self.locale
+                    .format("status.profile", &[("name", profile::name())]),
+            );
+        }
+        if let Some(routine) = &self.routine {
+            parts.push(routine.clone());
+        }
+        if let Some(cycle) = &self.config.cycle {
+            parts.push(self.tr(kind_label(self.timer().kind)).to_string());
+            if self.stats.work_sessions > 0 {
+                let count = self.stats.work_sessions.to_string();
+                parts.push(self.locale.format("status.done", &[("count", &count)]));
+            }
+            // Where this work session falls among those before the long break.
+            if self.timer().kind == SessionKind::Work && cycle.long_break_interval > 0 {
+                let session = (self.cycle_count + 1).to_string();
+                let interval = cycle.long_break_interval.to_string();
+                parts.push(self.locale.format(
+                    "status.long_break",
+                    &[("session", &session), ("interval", &interval)],
+                ));
+            }
+        }
+
+        // A sign the font can't draw goes into the status line instead.
+        if self.overtime().is_some() {
+            match self.config.overtime_style {
+                OvertimeStyle::Over => parts.push(self.tr("status.over").to_string()),
+                OvertimeStyle::Plus if !has_glyph(banner_font(), '+') => {
+                    parts.push(self.locale.format("status.overtime", &[("sign", "+")]))
+                }
+                OvertimeStyle::Minus if !has_glyph(banner_font(), '-') => {
+                    parts.push(self.locale.format("status.overtime", &[("sign", "-")]))
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(queue) = &self.timer().queue {
+            let (step, steps) = queue.position();
+            let (step, steps) = (step.to_string(), steps.to_string());
+            parts.push(
+                self.locale
+                    .format("status.step", &[("step", &step), ("steps", &steps)]),
+            );
+        }
+        // The label of a routine goes with its work sessions only.
+        let routine_break = self.routine.is_some() && self.timer().kind != SessionKind::Work;
+        if let (Some(label), true) = (&self.timer().label, self.is_active() && !routine_break) {
+            parts.push(label.clone());
+        }
+        if !self.timer().tags.is_empty() && self.is_active() {
+            parts.push(history::hashtags(&self.timer().tags));
+        }
+
+        let interruptions = self.timer().interruptions.len();
+        if interruptions > 0 && self.is_active() {
+            parts.push("'".repeat(interruptions));
+        }
+
+        if self.is_active() {
+            parts.push(match self.progress_text() {
+                Some(progress) => format!("{} {}", self.display_indicator(), progress),
+                None => String::from(self.display_indicator()),
+            });
+        }
+
+        if self.is_paused() {
+            parts.push(self.tr("status.paused").to_string());
+            parts.push(self.locale.format("status.ends", &[("time", "--:--")]));
+        } else if self.display == DisplayMode::End && self.is_active() {
+            // The digits show the end already; the countdown moves here.
+            let left = remain_to_fmt(self.timer().remain.as_secs());
+            parts.push(self.locale.format("status.left", &[("time", &left)]));
+        } else if let Some(end) = self.end_time() {
+            let end = self.format_clock(end);
+            parts.push(self.locale.format("status.ends", &[("time", &end)]));
+        }
+
+        if self.config.silent {
+            parts.push(self.tr("status.silent").to_string());
+        } else if self.quiet_hours_active() {
+            parts.push(self.tr("status.quiet_hours").to_string());
+        }
+
+        if let Some((message, _)) = &self.message {
+            parts.push(message.clone());
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" · "))
+        }
+    }
+
+    /// The UI string for `key` in the current locale.
+    fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        self.locale.get(key)
+    }
+
+    /// Records an interruption of the running work session, pausing it if
+    /// that's configured.
+    fn interrupt(&mut self) {
+        let timer = self.timer();
+        if timer.state != TimerState::Running || timer.kind != SessionKind::Work {
+            self.show_message(self.tr("message.no_work_session").to_string());
+            return;
+        }
+
+        let now = Local::now().fixed_offset();
+        self.timer_mut().interruptions.push(now);
+        if self.config.pause_on_interruption {
+            self.pause();
+        }
+    }
+
+    fn focus_lost(&mut self) {
+        let timer = self.timer();
+        if timer.state != TimerState::Running || timer.kind != SessionKind::Work {
+            return;
+        }
+
+        match self.config.focus_lost {
+            Some(FocusLost::Warn) => self.wandered = true,
+            Some(FocusLost::Pause) if !self.strict_guards(Action::Toggle) => self.pause(),
+            Some(FocusLost::Pause) => {}
+            None => {}
+        }
+    }
+
+    fn focus_gained(&mut self) {
+        if self.wandered && self.is_active() {
+            self.nag = true;
+        }
+        self.wandered = false;
+    }
+
+    fn show_config_path(&mut self) {
+        match config::config_path() {
+            Some(path) => {
+                let path = path.display().to_string();
+                let message = self.locale.format("message.config", &[("path", &path)]);
+                self.show_message(message);
+            }
+            None => self.show_message(self.tr("message.no_config").to_string()),
+        }
+    }
+
+    /// Loads the config file again, keeping the current one when the new
+    /// one is invalid. Idle timers still holding the old initial duration
+    /// take the new one; running sessions keep theirs.
+    fn reload_config(&mut self) {
+        let mut warnings = Vec::new();
+        let loaded = Config::load()
+            .map_err(|err| err.to_string())
+            .and_then(|mut config| {
+                warnings = config.apply_env();
+                warnings.extend(config.check_sounds());
+                warnings.extend(config.load_themes());
+                let keymap =
+                    Keymap::new(&config.keys).map_err(|err| format!("invalid [keys]: {}", err))?;
+                Ok((config, keymap))
+            });
+
+        match loaded {
+            Ok((mut config, keymap)) => {
+                // The routine keeps overriding the file, as long as it's
+                // still in there.
+                if let Some(name) = &self.routine {
+                    if let Err(err) = config.apply_routine(name) {
+                        self.show_message(err);
+                        self.routine = None;
+                    }
+                }
+
+                let mut deferred = false;
+                for timer in self.timers.iter_mut() {
+                    let old = self.config.initial_duration(timer.kind);
+                    let new = config.initial_duration(timer.kind);
+                    if old == new {
+                        continue;
+                    }
+
+                    if timer.is_active() {
+                        deferred = true;
+                    } else if timer.time == old {
+                        timer.time = new;
+                        timer.remain = new;
+                    }
+                }
+
+                self.config = config;
+                self.keymap = keymap;
+                self.suggestions_file = None;
+                match Locale::load(&self.config) {
+                    Ok(locale) => self.locale = locale,
+                    Err(err) => warnings.push(err),
+                }
+                sound::set_silent(self.config.silent);
+                if !self.is_active() {
+                    self.display = self.config.display_for(self.timer().kind);
+                }
+                self.refresh_time_str();
+
+                let message = if deferred {
+                    self.tr("message.reloaded_deferred").to_string()
+                } else if let Some(warning) = warnings.first() {
+                    self.locale
+                        .format("message.reloaded_ignoring", &[("warning", warning)])
+                } else {
+                    self.tr("message.reloaded").to_string()
+                };
+                self.show_message(message);
+            }
+            Err(err) => self.show_message(err),
+        }
+    }
+
+    /// Compares the wall-clock and the monotonic time that passed since the
+    /// last tick, and tells about any jump of the wall clock, applying the
+    /// `clock_jump` policy to the running sessions.
+    fn check_clock(&mut self, wall: chrono::Duration, monotonic: Duration) {
+        let jump = wall.num_seconds() - monotonic.as_secs() as i64;
+        if jump.abs() < CLOCK_JUMP_SECS {
+            return;
+        }
+
+        let amount = spelled_out(jump.unsigned_abs());
+        let running = self
+            .timers
+            .iter()
+            .any(|timer| timer.state == TimerState::Running);
+
+        let key = match (jump > 0, running, self.config.clock_jump) {
+            (false, _, _) => "message.clock_back",
+            (true, false, _) => "message.slept",
+            (true, true, ClockJump::Freeze) => "message.slept_frozen",
+            (true, true, ClockJump::Adjust) => {
+                for timer in self.timers.iter_mut() {
+                    timer.skip(Duration::from_secs(jump as u64));
+                }
+                "message.slept_adjusted"
+            }
+        };
+        let message = self.locale.format(key, &[("time", &amount)]);
+
+        let _ = history::log_event(&message);
+        self.show_message(message);
+    }
+
+    /// Rings the alarm of a finished session when its next step is due. It
+    /// only repeats while the current session waits to be acknowledged.
+    fn ring_alarm(&mut self) {
+        let (since, alarm) = match &mut self.alarm {
+            Some(alarm) => alarm,
+            None => return,
+        };
+        if alarm.has_rung() && self.timers[self.current].state != TimerState::Finished {
+            self.alarm = None;
+            return;
+        }
+
+        let step = alarm
+            .due(
+                &self.config.alarm,
+                self.config.alarm_repeat_secs,
+                since.elapsed(),
+            )
+            .copied();
+        let step = match step {
+            Some(step) => step,
+            None => return,
+        };
+        let sound = alarm.take_sound();
+        if step.notify {
+            self.notify(self.current, true);
+        }
+        if self.quiet_hours_active() {
+            return;
+        }
+
+        // A sound that can't be played rings the bells after all.
+        let played = match sound {
+            Some(path) => match sound::play(&path) {
+                Ok(()) => true,
+                Err(err) => {
+                    self.show_error("message.sound", err);
+                    false
+                }
+            },
+            None => false,
+        };
+        let bells = if played || !self.config.bell {
+            0
+        } else {
+            step.bells
+        };
+        self.ring(sound::alarm(bells, step.flash));
+    }
+
+    /// Lines up the bells and flashes of `schedule`, due that long from now.
+    fn ring(&mut self, schedule: Vec<(Duration, Ring)>) {
+        let now = Instant::now();
+        self.rings.extend(
+            schedule
+                .into_iter()
+                .map(|(after, ring)| (now + after, ring)),
+        );
+    }
+
+    /// Takes the bells and flashes due by `now`, in the order they're due.
+    fn due_rings(&mut self, now: Instant) -> Vec<Ring> {
+        self.rings.sort_by_key(|(at, _)| *at);
+        let due = self.rings.partition_point(|(at, _)| *at <= now);
+        self.rings.drain(..due).map(|(_, ring)| ring).collect()
+    }
+
+    /// Silences the alarm, though a flash under way still ends.
+    fn silence(&mut self) {
+        self.alarm = None;
+        self.rings.retain(|(_, ring)| *ring == Ring::FlashOff);
+    }
+
+    /// Time the current session ran past zero, when overtime is counted.
+    fn overtime(&self) -> Option<Duration> {
+        if self.config.overtime {
+            self.timer().overtime()
+        } else {
+            None
+        }
+    }
+
+    /// The warning that applies to the time left, if any.
+    fn warning(&self) -> Option<&Warning> {
+        if !self.is_active() {
+            return None;
+        }
+
+        let remain = self.timer().remain;
+        self.config
+            .warnings
+            .iter()
+            .filter(|warning| remain < Duration::from_secs(warning.threshold_secs))
+            .min_by_key(|warning| warning.threshold_secs)
+    }
+
+    /// Terminal title: the time left and the kind of the session while one
+    /// is counting.
+    fn window_title(&self) -> String {
+        let timer = self.timer();
+        let icon = match timer.state {
+            TimerState::Running => "⏳",
+            TimerState::Paused => "⏸",
+            TimerState::Finished => "✔",
+            TimerState::Idle => return String::from("pomidor"),
+        };
+        format!(
+            "{} {} · {}",
+            icon,
+            remain_to_fmt(timer.remain.as_secs()),
+            self.tr(kind_label(timer.kind))
+        )
+    }
+
+    /// Line above the digits telling a paused session from a running one
+    /// at a glance, in modifiers rather than colors.
+    fn state_marker(&self) -> Option<Line<'_>> {
+        let (key, modifier) = match (self.timer().state, self.scheduled) {
+            (TimerState::Running, _) => ("banner.running", Modifier::DIM),
+            (TimerState::Paused, _) => ("banner.paused", Modifier::BOLD | Modifier::REVERSED),
+            (_, Some(at)) => {
+                let left = (at - Local::now()).num_seconds().max(0) as u64;
+                let marker = self.locale.format(
+                    "banner.scheduled",
+                    &[
+                        ("time", &at.format("%H:%M").to_string()),
+                        ("left", &remain_to_fmt(left)),
+                    ],
+                );
+                return Some(Line::from(Span::styled(
+                    marker,
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+            }
+            (TimerState::Idle | TimerState::Finished, None) => return None,
+        };
+        Some(Line::from(Span::styled(
+            self.tr(key),
+            Style::default().add_modifier(modifier),
+        )))
+    }
+
+    /// Tab titles, with the time left on the timers that are counting.
+    fn tab_titles(&self) -> Vec<String> {
+        self.timers
+            .iter()
+            .map(|timer| match timer.state {
+                TimerState::Running | TimerState::Paused => {
+                    format!("{} {}", timer.name, remain_to_fmt(timer.remain.as_secs()))
+                }
+                TimerState::Finished => self.locale.format("tab.done", &[("name", &timer.name)]),
+                TimerState::Idle => timer.name.clone(),
+            })
+            .collect()
+    }
+
+    fn finish(&mut self) {
+        self.refresh_time_str();
+        self.save_state();
+
+        let celebrate = !self.config.celebration_text.is_empty()
+            && self.config.celebration_secs > 0
+            && self.timer().kind == SessionKind::Work;
+        if celebrate {
+            self.celebration_until =
+                Some(Instant::now() + Duration::from_secs(self.config.celebration_secs));
+        }
+
+        if let Some(secs) = self.config.exit_after_finish_secs {
+            self.exit_at = Some(Instant::now() + Duration::from_secs(secs));
+        }
+    }
+
+    /// Whether the finished session has been left alone long enough for the
+    /// app to close itself.
+    fn should_auto_exit(&self) -> bool {
+        self.exit_at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    fn is_celebrating(&self) -> bool {
+        self.celebration_until.is_some()
+    }
+
+    /// The label of the session being celebrated, shown under the banner.
+    fn celebrated_label(&self) -> Option<&str> {
+        self.timer()
+            .label
+            .as_deref()
+            .filter(|label| self.is_celebrating() && !label.is_empty())
+    }
+
+    /// The text standing in for the digits of a finished session, unless
+    /// it's counting overtime.
+    fn done_text(&self) -> Option<&str> {
+        if self.timer().state != TimerState::Finished || self.overtime().is_some() {
+            return None;
+        }
+        self.config
+            .done_text
+            .as_deref()
+            .filter(|text| !text.is_empty())
+    }
+
+    fn end_celebration(&mut self) {
+        self.celebration_until = None;
+    }
+
+    /// Starts the pending duration at the next `time` of day, today or
+    /// tomorrow.
+    fn schedule(&mut self, time: NaiveTime) {
+        let now = Local::now();
+        let mut date = now.date_naive();
+        if time <= now.time() {
+            date = date.succ_opt().unwrap_or(date);
+        }
+        self.scheduled = Local.from_local_datetime(&date.and_time(time)).earliest();
+    }
+
+    /// Starts the scheduled session once its time has come. Starting a
+    /// session by hand before then drops the schedule.
+    fn check_schedule(&mut self) {
+        let at = match self.scheduled {
+            Some(at) => at,
+            None => return,
+        };
+        if self.is_active() {
+            self.scheduled = None;
+        } else if Local::now() >= at {
+            self.scheduled = None;
+            self.start();
+        }
+    }
+
+    fn update_celebration(&mut self) {
+        if let Some(until) = self.celebration_until {
+            if Instant::now() >= until {
+                self.end_celebration();
+            }
+        }
+    }
+
+    /// Shows a short-lived message in the status line.
+    fn show_message(&mut self, message: impl Into<String>) {
+        let until = Instant::now() + Duration::from_secs(MESSAGE_SECS);
+        self.message = Some((message.into(), until));
+    }
+
+    /// Shows `err` in the message `key`, e.g. `message.history`.
+    fn show_error(&mut self, key: &str, err: impl std::fmt::Display) {
+        let message = self.locale.format(key, &[("error", &err.to_string())]);
+        self.show_message(message);
+    }
+
+    fn update_message(&mut self) {
+        if let Some((_, until)) = self.message {
+            if Instant::now() >= until {
+                self.message = None;
+            }
+        }
+    }
+
+    fn copy_time(&mut self) {
+        let time = self.time_str.clone();
+        let done = self.locale.format("message.copied", &[("text", &time)]);
+        self.copy(&time, done);
+    }
+
+    /// Copies the summary of the run, as shown.
+    fn copy_summary(&mut self) {
+        let summary = summary_lines(&self.stats, self.skipped_today(), &self.locale).join("\n");
+        self.copy(&summary, self.tr("message.copied_summary").to_string());
+    }
+
+    /// Copies today's totals from the stats screen, as `--stats` prints
+    /// them.
+    fn copy_today(&mut self) {
+        let today = Local::now().date_naive();
+        let first = report::week_start(today, self.config.week_start.weekday());
+        let brief = match self.history_cache.entries() {
+            Ok(entries) => aggregate::brief(entries, first, today),
+            Err(err) => return self.show_error("message.history", err),
+        };
+        let lines = brief_lines(&brief, &self.locale).join("\n");
+        self.copy(&lines, self.tr("message.copied_today").to_string());
+    }
+
+    /// Puts `text` on the clipboard, through the terminal's OSC 52 over SSH
+    /// and in tmux, or where the system clipboard can't be reached.
+    fn copy(&mut self, text: &str, done: impl Into<String>) {
+        let copied = !osc52::preferred() && self.copy_to_system(text);
+        if !copied {
+            if osc52::sequence(text).is_none() {
+                self.show_message(self.tr("message.too_long").to_string());
+                return;
+            }
+            self.osc52 = Some(text.to_string());
+        }
+        self.show_message(done);
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn copy_to_system(&mut self, text: &str) -> bool {
+        self.clipboard.copy(text).is_ok()
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn copy_to_system(&mut self, _text: &str) -> bool {
+        false
+    }
+
+    fn enter_char(&mut self, new_char: char) {
+        self.input_str.push(new_char);
+        self.completing = None;
+
+        self.move_cursor_right();
+    }
+
+    /// Inserts pasted text at the cursor in one go. Line breaks and other
+    /// control characters are dropped.
+    fn paste(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let index = self
+            .input_str
+            .char_indices()
+            .nth(self.cursor_position)
+            .map_or(self.input_str.len(), |(index, _)| index);
+
+        self.input_str.insert_str(index, &text);
+        self.cursor_position += text.chars().count();
+        self.completing = None;
+    }
+
+    /// Starts what's in the edit box: a duration and a label, e.g. `25:00
+    /// write report #clientA`, or a routine expression run as a queue. An
+    /// invalid expression leaves the cursor where it goes wrong.
+    fn submit_time(&mut self) {
+        if self.strict_refuses(Action::Start) {
+            return;
+        }
+        if let Some((name, _)) = self.typed_preset() {
+            if let Err(err) = self.launch_preset(name, App::exit_edit) {
+                self.input_error = Some(err);
+            }
+            return;
+        }
+
+        if queue::is_expression(&self.input_str) {
+            match Queue::from_expression(&self.input_str, self.config.bare_seconds_below) {
+                Ok(queue) => {
+                    self.exit_edit();
+                    self.start_queue(queue);
+                }
+                Err(err) => {
+                    self.input_error =
+                        Some(format!("{} at column {}", err.message, err.column + 1));
+                    self.cursor_position = err.column;
+                }
+            }
+            return;
+        }
+
+        let input = self.input_str.trim();
+        let (duration, label) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        if let Some(value) = parse_input(duration, self.config.bare_seconds_below) {
+            let label = Some(label.to_string());
+            self.timer_mut().last_duration = value;
+            self.input_str.clear();
+            self.reset_cursor();
+            self.edit_mode = false;
+            self.start_with(value, label);
+        }
+    }
+
+    /// The preset named by the whole of the input box, ignoring case.
+    fn typed_preset(&self) -> Option<(String, String)> {
+        let typed = self.input_str.trim();
+        self.config
+            .presets
+            .iter()
+            .find(|(name, _)| !typed.is_empty() && name.eq_ignore_ascii_case(typed))
+            .map(|(name, duration)| (name.clone(), duration.clone()))
+    }
+
+    /// Completes the preset name being typed in the duration box; each
+    /// further Tab moves on to the next name that matches. Numbers are left
+    /// alone.
+    fn complete_preset(&mut self) {
+        if self.input_str.is_empty() || self.input_str.starts_with(|c: char| c.is_ascii_digit()) {
+            return;
+        }
+
+        let prefix = self
+            .completing
+            .clone()
+            .unwrap_or_else(|| self.input_str.clone());
+        let matches = complete_preset(&self.config.presets, &prefix);
+        if matches.is_empty() {
+            self.input_error = Some(self.tr("input.no_preset").to_string());
+            return;
+        }
+
+        let next = matches
+            .iter()
+            .position(|name| *name == self.input_str)
+            .map_or(0, |index| (index + 1) % matches.len());
+        self.input_str = matches[next].to_string();
+        self.cursor_position = self.input_str.len();
+        self.input_error = None;
+        self.completing = Some(prefix);
+    }
+
+    /// Title of the duration box, telling how bare numbers are taken, or
+    /// the duration of the preset typed in.
+    fn duration_title(&self) -> String {
+        if let Some((name, duration)) = self.typed_preset() {
+            return format!("{} → {}", name, duration);
+        }
+        let bare = match self.config.bare_seconds_below {
+            0 => self.tr("input.minutes").to_string(),
+            below => self
+                .locale
+                .format("input.seconds_below", &[("below", &below.to_string())]),
+        };
+        let title = match &self.config.input_title {
+            Some(title) => title.as_str(),
+            None => self.tr("input.duration"),
+        };
+        let hint = self
+            .locale
+            .format("input.duration_hint", &[("bare", &bare)]);
+        format!("{} · {}", title, hint)
+    }
+
+    fn delete_char(&mut self) {
+        let is_not_cursor_leftmost = self.cursor_position != 0;
+        if is_not_cursor_leftmost {
+            let current_index = self.cursor_position;
+            let from_left_to_current_index = current_index - 1;
+            let before_char_to_delete = self.input_str.chars().take(from_left_to_current_index);
+            let after_char_to_delete = self.input_str.chars().skip(current_index);
+            self.input_str = before_char_to_delete.chain(after_char_to_delete).collect();
+            self.completing = None;
+            self.move_cursor_left();
+        }
+    }
+
+    /// Moves the field of the duration under the cursor up or down.
+    fn step_field(&mut self, delta: i64) {
+        let stepped = step_field(
+            &self.input_str,
+            self.cursor_position,
+            delta,
+            self.config.bare_seconds_below,
+        );
+        if let Some((input, cursor)) = stepped {
+            self.input_str = input;
+            self.cursor_position = cursor;
+            self.input_error = None;
+        }
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.input_str.len())
+    }
+
+    fn move_cursor_left(&mut self) {
+        let cursor_moved_left = self.cursor_position.saturating_sub(1);
+        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+    }
+
+    fn move_cursor_right(&mut self) {
+        let cursor_moved_right = self.cursor_position.saturating_add(1);
+        self.cursor_position = self.clamp_cursor(cursor_moved_right);
+    }
+
+    fn reset_cursor(&mut self) {
+        self.cursor_position = 0;
+    }
+
+    fn enter_edit(&mut self) {
+        self.edit_mode = true;
+        self.input_kind = InputKind::Duration;
+        self.input_error = None;
+
+        let last_duration = self.timer().last_duration;
+        if self.input_str.is_empty() && last_duration.as_secs() > 0 {
+            self.input_str = remain_to_fmt(last_duration.as_secs());
+            self.cursor_position = self.input_str.len();
+        }
+    }
+
+    /// Opens the edit box with `c` typed in, rather than the last duration,
+    /// so the key that opened it isn't lost.
+    fn start_typing(&mut self, c: char) {
+        self.enter_edit();
+        self.input_str.clear();
+        self.reset_cursor();
+        self.enter_char(c);
+    }
+
+    fn open_palette(&mut self) {
+        self.edit_mode = true;
+        self.input_kind = InputKind::Command;
+        self.input_error = None;
+        self.input_str.clear();
+        self.reset_cursor();
+    }
+
+    /// Completes the command name being typed in the palette, listing the
+    /// candidates when there's more than one.
+    fn complete_command(&mut self) {
+        let matches = command::complete(self.input_str.as_str());
+
+        match matches.as_slice() {
+            [] => self.input_error = Some(self.tr("input.no_command").to_string()),
+            [name] => {
+                self.input_str = format!("{} ", name);
+                self.input_error = None;
+            }
+            _ => {
+                self.input_str = String::from(command::common_prefix(&matches));
+                self.input_error = Some(matches.join(" "));
+            }
+        }
+        self.cursor_position = self.input_str.len();
+    }
+
+    /// Takes the palette line out of the input box, leaving the box open with
+    /// the error when it doesn't parse.
+    fn take_command(&mut self) -> Option<Command> {
+        match command::parse(self.input_str.as_str()) {
+            Ok(command) => {
+                self.exit_edit();
+                Some(command)
+            }
+            Err(err) => {
+                self.input_error = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Starts `duration` right away, taking the `#tags` out of `label`. A
+    /// session under way is logged as abandoned first.
+    fn start_with(&mut self, duration: Duration, label: Option<String>) {
+        self.end_early(Outcome::Abandoned);
+        let (label, tags) = label
+            .as_deref()
+            .map(history::split_tags)
+            .unwrap_or_default();
+        let timer = self.timer_mut();
+        timer.time = duration;
+        timer.label = label;
+        timer.tags = tags;
+        self.start_countdown();
+    }
+
+    /// Starts the routine `name` from its first work session, in place of
+    /// whatever the current timer was doing.
+    fn start_routine(&mut self, name: &str) {
+        if self.strict_refuses(Action::Start) {
+            return;
+        }
+        let mut config = self.config.clone();
+        let label = match config.apply_routine(name) {
+            Ok(routine) => routine.label.clone(),
+            Err(err) => {
+                self.show_message(err);
+                return;
+            }
+        };
+
+        // Logged before the routine changes the kind of the session.
+        self.end_early(Outcome::Abandoned);
+        self.timer_mut().stop();
+        self.config = config;
+        self.routine = Some(name.to_string());
+        self.close_summary();
+
+        let work = self.config.initial_duration(SessionKind::Work);
+        self.timer_mut().kind = SessionKind::Work;
+        self.display = self.config.display_for(SessionKind::Work);
+        self.start_with(work, label);
+    }
+
+    /// Lists the routines in the config on the status line.
+    fn list_routines(&mut self) {
+        if self.config.routines.is_empty() {
+            self.show_message(self.tr("message.no_routines").to_string());
+        } else {
+            let names: Vec<&str> = self.config.routines.keys().map(String::as_str).collect();
+            let message = self
+                .locale
+                .format("message.routines", &[("names", &names.join(", "))]);
+            self.show_message(message);
+        }
+    }
+
+    fn list_themes(&mut self) {
+        let names: Vec<&str> = theme::BUILT_IN
+            .iter()
+            .copied()
+            .chain(self.config.themes.keys().map(String::as_str))
+            .collect();
+        let message = self
+            .locale
+            .format("message.themes", &[("names", &names.join(", "))]);
+        self.show_message(message);
+    }
+
+    fn switch_theme(&mut self, name: String) {
+        if self.config.has_theme(&name) {
+            self.config.theme = name;
+        } else {
+            let message = self
+                .locale
+                .format("message.unknown_theme", &[("name", &name)]);
+            self.show_message(message);
+        }
+    }
+
+    fn open_presets(&mut self) {
+        let mut list = ListState::default();
+        if !self.config.presets.is_empty() {
+            list.select(Some(0));
+        }
+        self.presets = Some(list);
+    }
+
+    fn close_presets(&mut self) {
+        self.presets = None;
+    }
+
+    /// Opens the stats screen over the whole history.
+    fn open_stats(&mut self) {
+        let today = Local::now().date_naive();
+        self.overview = match self.history_cache.entries() {
+            Ok(entries) => Some(aggregate::overview(entries, today)),
+            Err(err) => {
+                self.show_error("message.history", err);
+                Some(Overview::default())
+            }
+        };
+    }
+
+    fn close_stats(&mut self) {
+        self.overview = None;
+        self.breakdown = None;
+    }
+
+    /// Switches the stats screen between the overview and the breakdown
+    /// per label.
+    fn toggle_breakdown(&mut self) {
+        match self.breakdown {
+            Some(_) => self.breakdown = None,
+            None => self.show_breakdown(ChartPeriod::default()),
+        }
+    }
+
+    /// Moves the breakdown on to the next period, out of the cached
+    /// history.
+    fn cycle_breakdown(&mut self) {
+        let period = self.breakdown.as_ref().map(|chart| chart.period.next());
+        self.show_breakdown(period.unwrap_or_default());
+    }
+
+    fn show_breakdown(&mut self, period: ChartPeriod) {
+        let today = Local::now().date_naive();
+        let first = period.first_day(today, self.config.week_start.weekday());
+        self.breakdown = match self.history_cache.entries() {
+            Ok(entries) => Some(aggregate::breakdown(entries, period, first, today)),
+            Err(err) => {
+                self.show_error("message.history", err);
+                Some(Breakdown {
+                    period,
+                    ..Breakdown::default()
+                })
+            }
+        };
+    }
+
+    /// The preset highlighted on the presets screen.
+    fn selected_preset(&self) -> Option<(String, String)> {
+        let index = self.presets.as_ref()?.selected()?;
+        self.config
+            .presets
+            .iter()
+            .nth(index)
+            .map(|(name, duration)| (name.clone(), duration.clone()))
+    }
+
+    /// Moves the highlight `offset` rows, wrapping around.
+    fn move_preset(&mut self, offset: isize) {
+        let count = self.config.presets.len() as isize;
+        if let (Some(list), true) = (self.presets.as_mut(), count > 0) {
+            let index = list.selected().unwrap_or(0) as isize;
+            list.select(Some((index + offset).rem_euclid(count) as usize));
+        }
+    }
+
+    fn start_preset(&mut self) {
+        let name = match self.selected_preset() {
+            Some((name, _)) => name,
+            None => return,
+        };
+        if self.strict_refuses(Action::Start) {
+            return;
+        }
+        if let Err(err) = self.launch_preset(name, App::close_presets) {
+            self.show_message(err);
+        }
+    }
+
+    /// Starts the preset `name` labelled with its name, after `close` has
+    /// put away what it was chosen from. Returns the message to show when
+    /// its duration doesn't parse.
+    fn launch_preset(&mut self, name: String, close: fn(&mut App)) -> Result<(), String> {
+        let duration = self.config.presets.get(&name).cloned().unwrap_or_default();
+        let duration = parse_duration(&duration)
+            .ok_or_else(|| format!("invalid duration `{}` in `{}`", duration, name))?;
+        close(self);
+        self.start_with(duration, Some(name));
+        Ok(())
+    }
+
+    fn prompt_preset(&mut self) {
+        self.edit_mode = true;
+        self.input_kind = InputKind::Preset;
+        self.input_error = None;
+        self.input_str.clear();
+        self.reset_cursor();
+    }
+
+    /// Adds the `name duration` typed into the input box as a preset,
+    /// replacing one of the same name.
+    fn add_preset(&mut self) {
+        let input = self.input_str.trim();
+        let (name, duration) = match input.rsplit_once(char::is_whitespace) {
+            Some((name, duration)) if !name.trim().is_empty() => (name.trim(), duration),
+            _ => {
+                self.input_error = Some(self.tr("input.name_and_duration").to_string());
+                return;
+            }
+        };
+
+        if parse_duration(duration).is_none() {
+            let error = self
+                .locale
+                .format("input.invalid_duration", &[("duration", duration)]);
+            self.input_error = Some(error);
+            return;
+        }
+
+        let name = String::from(name);
+        self.config
+            .presets
+            .insert(name.clone(), String::from(duration));
+        self.exit_edit();
+
+        if let Some(list) = self.presets.as_mut() {
+            let index = self.config.presets.keys().position(|key| *key == name);
+            list.select(index);
+        }
+        self.save_presets();
+    }
+
+    fn delete_preset(&mut self) {
+        let (name, _) = match self.selected_preset() {
+            Some(preset) => preset,
+            None => return,
+        };
+
+        self.config.presets.remove(&name);
+        if let Some(list) = self.presets.as_mut() {
+            let count = self.config.presets.len();
+            list.select(match list.selected() {
+                _ if count == 0 => None,
+                Some(index) => Some(index.min(count - 1)),
+                None => Some(0),
+            });
+        }
+        self.save_presets();
+    }
+
+    fn save_presets(&mut self) {
+        if let Err(err) = config::save_presets(&self.config.presets) {
+            self.show_error("message.presets_unsaved", err);
+        }
+    }
+
+    /// Opens the picker over the presets, the routines and the last
+    /// distinct durations of the history, newest first.
+    fn open_picker(&mut self) {
+        let presets = self
+            .config
+            .presets
+            .iter()
+            .map(|(name, duration)| picker::Entry {
+                text: name.clone(),
+                detail: duration.clone(),
+                target: picker::Target::Preset(name.clone()),
+            });
+        let routines = self
+            .config
+            .routines
+            .iter()
+            .map(|(name, routine)| picker::Entry {
+                text: name.clone(),
+                detail: routine.label.clone().unwrap_or_default(),
+                target: picker::Target::Routine(name.clone()),
+            });
+        let mut entries: Vec<picker::Entry> = presets.chain(routines).collect();
+
+        let history = match history::load() {
+            Ok(history) => history,
+            Err(err) => {
+                self.show_error("message.history_unread", err);
+                Vec::new()
+            }
+        };
+        let mut recent: Vec<u64> = Vec::new();
+        for entry in history.iter().rev() {
+            if recent.len() == picker::RECENT {
+                break;
+            }
+            if entry.outcome == Outcome::Completed && !recent.contains(&entry.duration_secs) {
+                recent.push(entry.duration_secs);
+            }
+        }
+        entries.extend(recent.into_iter().map(|secs| picker::Entry {
+            text: remain_to_fmt(secs),
+            detail: String::new(),
+            target: picker::Target::Recent(Duration::from_secs(secs)),
+        }));
+
+        self.input_str.clear();
+        self.reset_cursor();
+        self.picker = Some(Picker::new(entries));
+    }
+
+    fn close_picker(&mut self) {
+        self.picker = None;
+        self.input_str.clear();
+        self.reset_cursor();
+    }
+
+    /// Narrows the picker down to what's typed in its filter line.
+    fn filter_picker(&mut self) {
+        if let Some(picker) = self.picker.as_mut() {
+            picker.filter(&self.input_str);
+        }
+    }
+
+    /// Starts the entry highlighted in the picker.
+    fn pick(&mut self) {
+        let target = match self.picker.as_ref().and_then(Picker::selected) {
+            Some(entry) => entry.target.clone(),
+            None => return,
+        };
+        if self.strict_refuses(Action::Start) {
+            return;
+        }
+        self.close_picker();
+
+        match target {
+            picker::Target::Preset(name) => {
+                if let Err(err) = self.launch_preset(name, |_| {}) {
+                    self.show_message(err);
+                }
+            }
+            picker::Target::Routine(name) => self.start_routine(&name),
+            picker::Target::Recent(duration) => self.start_with(duration, None),
+        }
+    }
+
+    fn exit_edit(&mut self) {
+        self.edit_mode = false;
+        self.input_error = None;
+        self.completing = None;
+        self.input_str.clear();
+        self.reset_cursor();
+    }
+
+    fn reset(&mut self) {
+        if self.strict_refuses(Action::Reset) {
+            return;
+        }
+        self.end_early(Outcome::Restarted);
+
+        let timer = self.timer_mut();
+        if timer.time.as_secs() == 0 {
+            timer.time = timer.last_duration;
+        }
+        self.start_countdown();
+    }
+
+    /// Stamps the details of the current timer onto `state`.
+    fn stamp(&self, state: State) -> State {
+        state
+            .with_last_duration(self.timer().last_duration)
+            .with_label(self.timer().label.clone())
+            .with_timer(&self.timer().name)
+            .with_breaks_skipped(self.breaks_skipped.0, self.breaks_skipped.1)
+    }
+
+    /// Breaks skipped today.
+    fn skipped_today(&self) -> u32 {
+        match self.breaks_skipped {
+            (day, count) if day == Local::now().date_naive() => count,
+            _ => 0,
+        }
+    }
+
+    /// Skips the break running or lined up in cycle mode, going straight
+    /// to the next work session. Skipped breaks are counted for the day,
+    /// with a nudge once `skip_nudge` of them add up.
+    fn skip_break(&mut self) {
+        let cycle = match self.config.cycle {
+            Some(cycle) if self.timer().kind.is_break() => cycle,
+            _ => {
+                self.show_message(self.tr("message.no_break").to_string());
+                return;
+            }
+        };
+
+        self.end_early(Outcome::Abandoned);
+        let skipped = self.skipped_today() + 1;
+        self.breaks_skipped = (Local::now().date_naive(), skipped);
+        if skipped == cycle.skip_nudge {
+            let message = self
+                .locale
+                .format("message.skip_nudge", &[("count", &skipped.to_string())]);
+            self.show_message(message);
+        }
+
+        let (next, count) = next_session(
+            self.timer().kind,
+            self.cycle_count,
+            cycle.long_break_interval,
+        );
+        self.cycle_count = count;
+        let timer = self.timer_mut();
+        timer.kind = next;
+        timer.time = cycle.duration(next);
+        timer.suggestion = None;
+        self.display = self.config.display_for(next);
+        if cycle.auto_start {
+            self.start_countdown();
+        } else {
+            let timer = self.timer_mut();
+            timer.state = TimerState::Idle;
+            timer.remain = timer.time;
+            self.refresh_time_str();
+            self.save_state();
+        }
+    }
+
+    fn persist(&self, state: State) {
+        // Failing to write the state file must never interrupt the timer.
+        let _ = self.stamp(state).save();
+    }
+
+    /// Sends the state of the current timer to the observers whenever it
+    /// changed since the last time, which is at least every second while a
+    /// session runs. Observers that have gone away are dropped.
+    fn publish(&mut self) {
+        // Every timer, so the ones in the background are heard too.
+        for timer in &mut self.timers {
+            let transitions = std::mem::take(&mut timer.transitions);
+            if let Some(events) = &mut self.events {
+                for (kind, state) in &transitions {
+                    events.emit(*kind, state);
+                }
+                let state = timer
+                    .snapshot()
+                    .with_label(timer.label.clone())
+                    .with_timer(&timer.name);
+                events.observe(&state);
+            }
+        }
+        if self.observers.is_empty() {
+            return;
+        }
+
+        let state = self.stamp(self.timer().snapshot());
+        let key = (
+            state.status,
+            state.remaining_secs,
+            state.label.clone(),
+            state.timer.clone(),
+        );
+        if self.published.as_ref() == Some(&key) {
+            return;
+        }
+
+        self.observers
+            .retain(|observer| observer.send(state.clone()).is_ok());
+        self.published = Some(key);
+    }
+
+    fn save_state(&self) {
+        self.persist(self.timer().snapshot());
+    }
+
+    fn stop(&mut self) {
+        if !self.strict_refuses(Action::Stop) {
+            self.abandon();
+        }
+    }
+
+    /// Stops the session whatever strict mode says.
+    fn abandon(&mut self) {
+        self.end_early(Outcome::Abandoned);
+
+        let timer = self.timer_mut();
+        timer.stop();
+        timer.time = Duration::new(0, 0);
+        timer.label = None;
+        timer.tags.clear();
+        timer.queue = None;
+        self.start_countdown();
+    }
+}
+
+/// `30s`, `2m`, a bare number of seconds or a duration.
+fn delay_arg(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.strip_suffix('s') {
+        Some(number) => (number, 1),
+        None => match value.strip_suffix('m') {
+            Some(number) => (number, 60),
+            None => (value, 1),
+        },
+    };
+
+    match number.parse::<u64>() {
+        Ok(number) => Ok(Duration::from_secs(number * unit)),
+        Err(_) => duration_arg(value),
+    }
+}
+
+fn time_arg(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| String::from("expected HH:MM"))
+}
+
+fn duration_arg(value: &str) -> Result<Duration, String> {
+    parse_duration(value).ok_or_else(|| String::from("expected hh:mm:ss or mm:ss"))
+}
+
+/// `9m 12s`, `2h 5m` or `40s`.
+fn spelled_out(secs: u64) -> String {
+    let (hours, minutes, secs) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, _) => format!("{}m {}s", minutes, secs),
+        _ => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// The banner text for `secs` past zero. The `over` style keeps the sign
+/// out of the digits; the status line carries the word instead.
+fn overtime_fmt(secs: u64, style: OvertimeStyle) -> String {
+    match style {
+        OvertimeStyle::Plus => format!("+{}", remain_to_fmt(secs)),
+        OvertimeStyle::Minus => format!("-{}", remain_to_fmt(secs)),
+        OvertimeStyle::Over => remain_to_fmt(secs),
+    }
+}
+
+/// Parses what's typed into the duration box: what `parse_duration` takes,
+/// `:ss` for seconds, or a bare number, taken for seconds below
+/// `seconds_below` and for minutes otherwise.
+fn parse_input(input: &str, seconds_below: u64) -> Option<Duration> {
+    let input = input.trim();
+    let number = |digits: &str| -> Option<u64> {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+
+    if let Some(secs) = input.strip_prefix(':') {
+        return number(secs).map(Duration::from_secs);
+    }
+    match number(input) {
+        Some(value) if value < seconds_below => Some(Duration::from_secs(value)),
+        Some(minutes) => Some(Duration::from_secs(minutes.checked_mul(60)?)),
+        None => parse_duration(input),
+    }
+}
+
+/// Names of `presets` starting with `prefix`, ignoring case, in name
+/// order.
+fn complete_preset<'a>(presets: &'a BTreeMap<String, String>, prefix: &str) -> Vec<&'a str> {
+    let prefix = prefix.to_lowercase();
+    presets
+        .keys()
+        .filter(|name| name.to_lowercase().starts_with(&prefix))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Pattern of `hh:mm:ss` and `mm:ss`, compiled on first use.
+static DURATION_RE: OnceLock<Option<Regex>> = OnceLock::new();
+
+/// Parses `hh:mm:ss`, `mm:ss` or minutes with a fraction, e.g. `1.5` for
+/// 90 seconds. Anything else, including surrounding text, is rejected.
+fn parse_duration(duration: &str) -> Option<Duration> {
+    if let Some((whole, fraction)) = duration.split_once('.') {
+        let digits = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+        if !digits(whole) || !digits(fraction) {
+            return None;
+        }
+        let minutes: f64 = duration.parse().ok()?;
+        let secs = (minutes * 60.0).round();
+        // No longer than the longest `hh:mm:ss`.
+        return (secs < 24.0 * 3600.0).then(|| Duration::from_secs(secs as u64));
+    }
+
+    let re = DURATION_RE
+        .get_or_init(|| Regex::new(r"^(?:([01][0-9]|2[0-3]):)?([0-5][0-9]):([0-5][0-9])$").ok())
+        .as_ref()?;
+    let caps = re.captures(duration)?;
+
+    let field = |index: usize| -> Option<u64> {
+        match caps.get(index) {
+            Some(m) => m.as_str().parse().ok(),
+            None => Some(0),
+        }
+    };
+    let (h, m, s) = (field(1)?, field(2)?, field(3)?);
+
+    Some(Duration::new(3600 * h + 60 * m + s, 0))
+}
+
+/// The duration at the start of `input` with the field under `cursor`
+/// (hours, minutes or seconds) moved by `delta` of its unit, carrying into
+/// the fields around it, and the cursor kept on that field. A cursor on a
+/// colon picks the field to its right; empty input starts from `00:00` and
+/// a bare number steps its minutes. The label after the duration is kept.
+fn step_field(
+    input: &str,
+    cursor: usize,
+    delta: i64,
+    seconds_below: u64,
+) -> Option<(String, usize)> {
+    const MAX_SECS: i64 = 24 * 3600 - 1;
+
+    let end = input.find(char::is_whitespace).unwrap_or(input.len());
+    let (word, rest) = input.split_at(end);
+    if !word.is_ascii() || cursor > end {
+        return None;
+    }
+
+    let (fields, field, offset): (Vec<i64>, usize, usize) = if word.contains(':') {
+        let mut fields = Vec::new();
+        for field in word.split(':') {
+            if !field.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            fields.push(if field.is_empty() {
+                0
+            } else {
+                field.parse().ok()?
+            });
+        }
+        let field = word[..cursor].matches(':').count();
+        let field = match word.as_bytes().get(cursor) {
+            Some(b':') => field + 1,
+            _ => field,
+        };
+        let start = word
+            .split(':')
+            .take(field)
+            .map(|field| field.len() + 1)
+            .sum::<usize>();
+        (fields, field, cursor.saturating_sub(start))
+    } else {
+        let secs = match word {
+            "" => 0,
+            word => parse_input(word, seconds_below)?.as_secs() as i64,
+        };
+        (vec![secs / 60, secs % 60], 0, 0)
+    };
+    if !(2..=3).contains(&fields.len()) {
+        return None;
+    }
+
+    let unit = |index: usize, count: usize| 60_i64.pow((count - 1 - index) as u32);
+    let total: i64 = fields
+        .iter()
+        .enumerate()
+        .map(|(index, value)| value * unit(index, fields.len()))
+        .sum();
+    let secs = (total + delta * unit(field, fields.len())).clamp(0, MAX_SECS) as u64;
+
+    let count = if fields.len() == 3 || secs >= 3600 {
+        3
+    } else {
+        2
+    };
+    let field = field + count - fields.len();
+    let word = match count {
+        3 => format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        ),
+        _ => format!("{:02}:{:02}", secs / 60, secs % 60),
+    };
+    Some((format!("{}{}", word, rest), 3 * field + offset.min(2)))
+}
+
+/// `text` cut down to `width` characters, ending in `…` when cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// `remain_to_fmt` with tenths of a second, e.g. `00:07.4`.
+fn remain_to_fmt_tenths(remain: Duration) -> String {
+    format!(
+        "{}.{}",
+        remain_to_fmt(remain.as_secs()),
+        remain.subsec_millis() / 100
+    )
+}
+
+/// `fraction` of a bar `width` cells wide, drawn with eighth blocks on a
+/// dotted track.
+fn minute_bar(fraction: f32, width: usize) -> String {
+    let mut bar = eighths_bar(fraction, width);
+    let drawn = bar.chars().count();
+    bar.push_str(&"·".repeat(width - drawn));
+    bar
+}
+
+/// `fraction` of a bar `width` cells wide in eighth blocks, without
+/// the rest of the track.
+fn eighths_bar(fraction: f32, width: usize) -> String {
+    const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f32) as usize;
+    let (full, part) = (eighths / 8, eighths % 8);
+    let mut bar = "█".repeat(full.min(width));
+    if full < width && part > 0 {
+        bar.push(EIGHTHS[part - 1]);
+    }
+    bar
+}
+
+/// `secs` rounded up to a multiple of `step` seconds, no further than a
+/// day.
+fn round_up(secs: u64, step: u64) -> u64 {
+    const MAX_SECS: u64 = 24 * 3600;
+
+    match secs % step {
+        0 => secs,
+        rest => (secs + step - rest).min(MAX_SECS.max(secs)),
+    }
+}
+
+/// `secs` in minutes only, e.g. `25` or `1:05`, with a partial minute
+/// rounded up or truncated.
+fn minutes_fmt(secs: u64, round_up: bool) -> String {
+    let (mut minutes, rest) = (secs / SECS_IN_MIN as u64, secs % SECS_IN_MIN as u64);
+    if round_up && rest > 0 {
+        minutes += 1;
+    }
+    match minutes / 60 {
+        0 => minutes.to_string(),
+        hours => format!("{}:{:02}", hours, minutes % 60),
+    }
+}
+
+fn remain_to_fmt(remain: u64) -> String {
+    let (hours, minutes, seconds) = (
+        remain / SECS_IN_HOUR as u64,
+        (remain % SECS_IN_HOUR as u64) / SECS_IN_MIN as u64,
+        remain % SECS_IN_MIN as u64,
+    );
+
+    if hours == 0 {
+        format!("{:02}:{:02}", minutes, seconds)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+/// Time left until `remain.as_secs()` drops to the next whole second, so the
+/// loop can wake up exactly when the displayed value changes.
+fn until_next_second(remain: Duration) -> Duration {
+    Duration::from_nanos(remain.subsec_nanos() as u64)
+}
+
+/// Set while `--events -` has stdout, so the timer is drawn on stderr.
+static SCREEN_ON_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// The stream the timer is drawn on.
+fn screen() -> Box<dyn Write + Send> {
+    if SCREEN_ON_STDERR.load(Ordering::Relaxed) {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+/// The banner font, parsed on first use; None when it can't be loaded.
+static BANNER_FONT: OnceLock<Option<FIGfont>> = OnceLock::new();
+
+fn banner_font() -> Option<&'static FIGfont> {
+    BANNER_FONT
+        .get_or_init(|| FIGfont::standard().ok())
+        .as_ref()
+}
+
+/// Draws the banner in the font of `path`, or the standard one. Returns a
+/// warning when the file doesn't read and the standard font takes over.
+fn load_banner_font(path: Option<&Path>) -> Option<String> {
+    let path = config::expand_home(path?);
+    let (font, warning) = match FIGfont::from_file(&path.to_string_lossy()) {
+        Ok(font) => (Some(font), None),
+        Err(err) => (
+            FIGfont::standard().ok(),
+            Some(format!("font {}: {}", path.display(), err)),
+        ),
+    };
+    let _ = BANNER_FONT.set(font);
+    warning
+}
+
+/// Whether `font` can draw `c`; characters it can't are left out.
+fn has_glyph(font: Option<&FIGfont>, c: char) -> bool {
+    font.is_some_and(|font| font.fonts.contains_key(&(c as u32)))
+}
+
+/// `text` as a figlet banner, or as it is when the font isn't available.
+fn generate_content(text: &str, spacing: usize) -> Vec<String> {
+    let mut content: Vec<String> = Vec::new();
+
+    let Some(figlet) = banner_font().and_then(|font| font.convert(text)) else {
+        return vec![text.to_string()];
+    };
+    let letter_count = figlet.characters.len();
+    let mut text_height = 0;
+
+    if let Some(first) = figlet.characters.first() {
+        text_height = first.height;
+    }
+
+    let gap = " ".repeat(spacing);
+    for line_no in 0..text_height {
+        let mut line = String::from("");
+        for letter_no in 0..letter_count {
+            if letter_no > 0 {
+                line.push_str(&gap);
+            }
+            line.push_str(
+                figlet
+                    .characters
+                    .get(letter_no)
+                    .unwrap()
+                    .characters
+                    .get(line_no as usize)
+                    .unwrap(),
+            );
+        }
+        content.push(line);
+    }
+    content
+}
+
+/// `generate_content` with `spacing` between the letters, or without when
+/// that makes the banner wider than `width`.
+fn spaced_content(text: &str, spacing: usize, width: u16) -> Vec<String> {
+    let content = generate_content(text, spacing);
+    if spacing > 0 && too_wide(&content, width) {
+        generate_content(text, 0)
+    } else {
+        content
+    }
+}
+
+/// `spaced_content` of the time, without its tenths when they make the
+/// banner wider than `width`.
+fn time_content(time: &str, spacing: usize, width: u16) -> Vec<String> {
+    let content = spaced_content(time, spacing, width);
+    match time.split_once('.') {
+        Some((whole, _)) if too_wide(&content, width) => spaced_content(whole, spacing, width),
+        _ => content,
+    }
+}
+
+/// `spaced_content`, or `text` as it is when the banner would be wider
+/// than `width`.
+fn fitted_content(text: &str, spacing: usize, width: u16) -> Vec<String> {
+    let content = spaced_content(text, spacing, width);
+    if too_wide(&content, width) {
+        vec![text.to_string()]
+    } else {
+        content
+    }
+}
+
+/// The largest scale, at least 1, at which `content` fits in `width`
+/// columns and `height` lines.
+fn fill_scale(content: &[String], width: u16, height: u16) -> u16 {
+    let columns = content
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let lines = content.len().max(1);
+    (width as usize / columns)
+        .min(height as usize / lines)
+        .max(1) as u16
+}
+
+/// `content` with every character repeated `scale` times across and every
+/// line `scale` times down.
+fn scale_content(content: Vec<String>, scale: u16) -> Vec<String> {
+    if scale <= 1 {
+        return content;
+    }
+    let scale = scale as usize;
+    content
+        .iter()
+        .flat_map(|line| {
+            let wide: String = line
+                .chars()
+                .flat_map(|c| std::iter::repeat_n(c, scale))
+                .collect();
+            std::iter::repeat_n(wide, scale)
+        })
+        .collect()
+}
+
+fn too_wide(content: &[String], width: u16) -> bool {
+    content
+        .iter()
+        .any(|line| line.chars().count() > width as usize)
+}
+
+fn create_chunks(size: Rect, direction: Direction, constraints: &[Constraint]) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(size)
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    // A note asked for at the end of the last cycle comes before the summary.
+    if let (Some(list), false) = (&app.summary, app.edit_mode) {
+        return summary_ui(f, app, list);
+    }
+    if let Some(list) = &app.presets {
+        return presets_ui(f, app, list);
+    }
+    if let Some(overview) = &app.overview {
+        return stats_ui(f, app, overview);
+    }
+
+    let size = match &app.history {
+        Some(pane) => {
+            // Side by side when there's room for both, the pane under the
+            // timer otherwise.
+            let direction = if f.size().width >= HISTORY_SIDE_WIDTH {
+                Direction::Horizontal
+            } else {
+                Direction::Vertical
+            };
+            let chunks = create_chunks(
+                f.size(),
+                direction,
+                &[Constraint::Percentage(60), Constraint::Percentage(40)],
+            );
+            history_ui(f, app, pane, chunks[1]);
+            chunks[0]
+        }
+        None => f.size(),
+    };
+    let mut text: Vec<Line> = Vec::new();
+
+    let spacing = app.config.digit_spacing;
+    let content = if app.is_celebrating() {
+        fitted_content(app.config.celebration_text.as_str(), spacing, size.width)
+    } else if let Some(text) = app.done_text() {
+        fitted_content(text, spacing, size.width)
+    } else {
+        time_content(app.time_str.as_str(), spacing, size.width)
+    };
+
+    let suggestion = app.suggestion().filter(|_| !app.nag);
+    let marker = app.state_marker();
+    let breathing = app.breathing();
+    let minute = app.minute_progress();
+    let celebrated = app.celebrated_label();
+    let extra_height = MARGIN_LINES
+        + INPUT_HEIGHT
+        + usize::from(celebrated.is_some())
+        + usize::from(minute.is_some())
+        + usize::from(suggestion.is_some())
+        + usize::from(breathing.is_some())
+        + usize::from(marker.is_some());
+    // Recomputed on every draw, so the banner follows the terminal's size.
+    let room = size.height.saturating_sub(extra_height as u16);
+    let scale = app.banner_scale(&content, size.width, room);
+    let content = scale_content(content, scale);
+    let text_height = content.len() + extra_height;
+
+    if text_height as u16 > size.height {
+        return;
+    }
+
+    let blank_height: u16 = size.height - (text_height as u16);
+
+    let top_height: u16 = blank_height / 2;
+    let mut bot_height: i16 = (blank_height / 2) as i16;
+    let mut input_height: u16 = 0;
+
+    if app.edit_mode {
+        bot_height -= INPUT_HEIGHT as i16;
+        if bot_height < 0 {
+            bot_height = 0;
+        }
+        input_height = INPUT_HEIGHT as u16;
+    }
+
+    if let Some(marker) = marker {
+        text.push(marker);
+    }
+    // Frozen digits fade, so they don't pass for ticking ones.
+    for line in content {
+        if app.is_paused() {
+            text.push(Line::from(Span::styled(
+                line,
+                Style::default().add_modifier(Modifier::DIM),
+            )));
+        } else {
+            text.push(Line::from(line));
+        }
+    }
+
+    if let Some(label) = celebrated {
+        text.push(Line::from(Span::styled(
+            truncate(label, size.width as usize),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+    }
+    if let Some(minute) = minute {
+        text.push(minute);
+    }
+    if let Some(status) = app.status_text() {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(status, app.chrome_style())));
+    }
+    if let Some(suggestion) = suggestion {
+        text.push(Line::from(Span::styled(
+            truncate(suggestion, size.width as usize),
+            app.chrome_style(),
+        )));
+    }
+    if let Some(breathing) = breathing {
+        text.push(breathing);
+    }
+    if app.nag {
+        text.push(Line::from(Span::styled(
+            app.tr("status.nag"),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    let chunks = create_chunks(
+        size,
+        Direction::Vertical,
+        &[
+            Constraint::Length(top_height),
+            Constraint::Length(text_height as u16),
+            Constraint::Length(bot_height as u16),
+            Constraint::Max(input_height),
+        ],
+    );
+
+    let create_block = |title: String| {
+        Block::default()
+            .borders(Borders::NONE)
+            .style(app.config.text_style())
+            .title(Span::styled(
+                title,
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+    };
+
+    let mut banner_style = app.config.text_style();
+    if let (Some(bg), false) = (app.config.banner_background(), app.config.monochrome) {
+        banner_style = banner_style.bg(bg);
+    }
+    if let Some(warning) = app.warning() {
+        banner_style = banner_style.patch(app.config.warning_style(warning));
+    }
+    let plus = app.config.overtime_style == OvertimeStyle::Plus;
+    if app.overtime().is_some() && plus && !app.config.monochrome {
+        banner_style = banner_style.fg(Color::Red);
+    }
+
+    let paragraph = Paragraph::new(text.clone())
+        .style(banner_style)
+        .block(create_block(String::from("")))
+        .alignment(app.config.text_alignment());
+    f.render_widget(paragraph, chunks[1]);
+
+    if app.timers.len() > 1 && top_height > 0 {
+        let tabs = Tabs::new(app.tab_titles())
+            .select(app.current)
+            .style(app.config.text_style().patch(app.chrome_style()))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+        let area = Rect::new(size.x, size.y, size.width, 1);
+        f.render_widget(tabs, area);
+    }
+
+    if !app.edit_mode && bot_height > 0 {
+        if let Some(action) = app.space_action() {
+            let label = app.tr(action.label());
+            let footer = Paragraph::new(app.locale.format("footer.space", &[("action", label)]))
+                .style(app.config.text_style().add_modifier(Modifier::DIM))
+                .alignment(Alignment::Center);
+            let area = Rect::new(size.x, size.y + size.height - 1, size.width, 1);
+            f.render_widget(footer, area);
+        }
+    }
+
+    if app.edit_mode {
+        input_ui(f, app, chunks[3]);
+    }
+    if let Some(picker) = &app.picker {
+        picker_ui(f, app, picker);
+    }
+}
+
+/// Draws today's sessions into `area`, newest first.
+fn history_ui<B: Backend>(f: &mut Frame<B>, app: &App, pane: &HistoryPane, area: Rect) {
+    let selected = pane
+        .list
+        .selected()
+        .and_then(|index| pane.entries.get(index));
+    let heading = match &pane.search {
+        Some(search) => {
+            let more = if search.is_done() { "" } else { "+" };
+            app.locale.format(
+                "history.search",
+                &[
+                    ("query", search.query()),
+                    ("count", &format!("{}{}", pane.entries.len(), more)),
+                ],
+            )
+        }
+        None => app.tr("history.title").to_string(),
+    };
+    let title = match (&app.message, selected) {
+        _ if pane.typing => format!("/{}", app.input_str),
+        (_, Some(entry)) if pane.confirm => app.locale.format(
+            "history.confirm",
+            &[("time", &entry.start.format("%Y-%m-%d %H:%M").to_string())],
+        ),
+        (Some((message, _)), _) => format!("{} · {}", heading, message),
+        _ => heading,
+    };
+    let footer = match pane.search {
+        Some(_) => app.tr("history.search_footer"),
+        None => app.tr("history.footer"),
+    };
+    let block = bordered_block(app)
+        .style(app.config.text_style())
+        .title(Span::styled(
+            title,
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .title(
+            Title::from(footer)
+                .position(Position::Bottom)
+                .alignment(Alignment::Center),
+        );
+    if pane.typing {
+        f.set_cursor(area.x + 2 + app.cursor_position as u16, area.y);
+    }
+
+    if pane.entries.is_empty() {
+        let empty = match pane.search {
+            Some(_) => app.tr("history.no_match"),
+            None => app.tr("history.empty"),
+        };
+        let empty = Paragraph::new(empty)
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = pane
+        .entries
+        .iter()
+        .map(|entry| {
+            let label = match &entry.label {
+                Some(label) if !label.is_empty() => label.clone(),
+                _ => app.tr(kind_label(entry.kind)).to_string(),
+            };
+            // A search spans days, and matches tags as well.
+            let (time, label) = match &pane.search {
+                Some(_) if !entry.tags.is_empty() => (
+                    "%Y-%m-%d %H:%M",
+                    format!("{} {}", label, history::hashtags(&entry.tags)),
+                ),
+                Some(_) => ("%Y-%m-%d %H:%M", label),
+                None => ("%H:%M", label),
+            };
+            let label = format!("{:<24}", truncate(&label, 24));
+
+            let mut spans = vec![Span::raw(format!(
+                "{} {:>8}  ",
+                entry.start.format(time),
+                remain_to_fmt(entry.duration_secs)
+            ))];
+            match &pane.search {
+                Some(search) => spans.extend(highlight(&label, search.query())),
+                None => spans.push(Span::raw(label)),
+            }
+            spans.push(Span::raw(format!(" {}", entry.outcome.name())));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(list, area, &mut pane.list.clone());
+}
+
+/// `text` with every occurrence of `query` in it, ignoring case, picked
+/// out. Text that changes length when lowercased is left as it is.
+fn highlight(text: &str, query: &str) -> Vec<Span<'static>> {
+    let lower = text.to_lowercase();
+    if query.is_empty() || lower.len() != text.len() {
+        return vec![Span::raw(text.to_string())];
+    }
+
+    let style = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut from = 0;
+    for (at, found) in lower.match_indices(query) {
+        if at > from {
+            spans.push(Span::raw(text[from..at].to_string()));
+        }
+        spans.push(Span::styled(text[at..at + found.len()].to_string(), style));
+        from = at + found.len();
+    }
+    if from < text.len() {
+        spans.push(Span::raw(text[from..].to_string()));
+    }
+    spans
+}
+
+/// Draws the picker in a box centered over the timer: the filter line on
+/// top, the matching entries below.
+fn picker_ui<B: Backend>(f: &mut Frame<B>, app: &App, picker: &Picker) {
+    const WIDTH: u16 = 50;
+    const MAX_ROWS: u16 = 12;
+
+    let size = f.size();
+    let rows = (picker.matches.len() as u16).clamp(1, MAX_ROWS);
+    let (width, height) = (WIDTH.min(size.width), (rows + 3).min(size.height));
+    if width < 3 || height < 4 {
+        return;
+    }
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = bordered_block(app)
+        .style(app.config.text_style())
+        .title(Span::styled(
+            app.tr("picker.title"),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let filter = Paragraph::new(format!("/{}", app.input_str));
+    f.render_widget(filter, Rect::new(inner.x, inner.y, inner.width, 1));
+    let list_area = Rect::new(inner.x, inner.y + 1, inner.width, inner.height - 1);
+
+    if picker.matches.is_empty() {
+        let empty = Paragraph::new(app.tr("picker.empty"))
+            .style(Style::default().add_modifier(Modifier::DIM));
+        f.render_widget(empty, list_area);
+    } else {
+        let items: Vec<ListItem> = picker
+            .matches
+            .iter()
+            .map(|&index| {
+                let entry = &picker.entries[index];
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<20} {:<12} ", entry.text, entry.detail)),
+                    Span::styled(
+                        app.tr(entry.target.label()),
+                        Style::default().add_modifier(Modifier::DIM),
+                    ),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        f.render_stateful_widget(list, list_area, &mut picker.list.clone());
+    }
+
+    f.set_cursor(inner.x + 1 + app.cursor_position as u16, inner.y);
+}
+
+/// Block with the configured borders around the edit box and the overlays.
+fn bordered_block(app: &App) -> Block<'static> {
+    let mut border_style = Style::default();
+    if let (Some(color), false) = (app.config.border_fg(), app.config.monochrome) {
+        border_style = border_style.fg(color);
+    }
+
+    Block::default()
+        .borders(Borders::ALL)
+        .border_type(app.config.border_type())
+        .border_style(border_style)
+}
+
+/// Draws the input box into `area`.
+fn input_ui<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let placeholder = match app.input_kind {
+        InputKind::Duration => match &app.config.input_placeholder {
+            Some(placeholder) => placeholder.as_str(),
+            None => app.tr("input.duration_placeholder"),
+        },
+        InputKind::Command => app.tr("input.command_placeholder"),
+        InputKind::Preset => app.tr("input.preset_placeholder"),
+        InputKind::Note => "",
+    };
+    let input_text = if app.input_str.is_empty() {
+        Line::from(Span::styled(
+            placeholder,
+            Style::default().add_modifier(Modifier::DIM),
+        ))
+    } else {
+        Line::from(app.input_str.as_str())
+    };
+    let title = match (&app.input_error, app.input_kind) {
+        (Some(err), _) => Span::styled(err.as_str(), Style::default().fg(Color::Red)),
+        (None, InputKind::Duration) => Span::raw(app.duration_title()),
+        (None, InputKind::Command) => Span::raw(app.tr("input.command")),
+        (None, InputKind::Preset) => Span::raw(app.tr("input.preset")),
+        (None, InputKind::Note) => Span::raw(app.tr("input.note")),
+    };
+    let input = Paragraph::new(input_text)
+        .style(Style::default())
+        .block(bordered_block(app).title(title));
+    f.render_widget(input, area);
+    f.set_cursor(area.x + app.cursor_position as u16 + 1, area.y + 1);
+}
+
+/// Lines of the summary shown once the last cycle is done, with the
+/// breaks skipped today.
+fn summary_lines(stats: &Stats, breaks_skipped: u32, locale: &Locale) -> Vec<String> {
+    let interruptions = format!("{:.1}", stats.interruptions_per_session());
+    let completed = match stats.completion_rate() {
+        Some(rate) => locale.format("summary.of_started", &[("percent", &rate.to_string())]),
+        None => String::from("-"),
+    };
+
+    [
+        ("summary.work_sessions", stats.work_sessions.to_string()),
+        ("summary.focused", remain_to_fmt(stats.work_time.as_secs())),
+        (
+            "summary.interruptions",
+            locale.format("summary.per_session", &[("count", &interruptions)]),
+        ),
+        ("summary.completed", completed),
+        ("summary.breaks", stats.breaks.to_string()),
+        (
+            "summary.break_time",
+            remain_to_fmt(stats.break_time.as_secs()),
+        ),
+        ("summary.breaks_skipped", breaks_skipped.to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| format!("{:<16} {}", locale.get(key), value))
+    .collect()
+}
+
+/// Lines of the stats screen: the last 30 days next to the whole history.
+/// Averages are left out while there are too few sessions for them.
+fn stats_lines(overview: &Overview, locale: &Locale) -> Vec<String> {
+    let (recent, lifetime) = (&overview.last_30_days, &overview.lifetime);
+    if lifetime.started() == 0 {
+        return vec![locale.get("stats.empty").to_string()];
+    }
+
+    let cells = |totals: &aggregate::Aggregates| {
+        [
+            Some(totals.completed.to_string()),
+            Some(aggregate::hours_minutes(totals.focused_secs)),
+            totals.average_session_secs.map(remain_to_fmt),
+            totals
+                .sessions_per_active_day
+                .map(|count| format!("{:.1}", count)),
+            totals.best_weekday.clone(),
+            totals.completion_rate.map(|rate| format!("{}%", rate)),
+            totals
+                .interruptions_per_session
+                .map(|count| format!("{:.1}", count)),
+        ]
+    };
+    let keys = [
+        "stats.sessions",
+        "stats.focused",
+        "stats.average",
+        "stats.per_day",
+        "stats.best_day",
+        "stats.completed",
+        "stats.interruptions",
+    ];
+
+    let row = |label: &str, recent: Option<String>, lifetime: Option<String>| {
+        let dash = || String::from("-");
+        format!(
+            "{:<20} {:>14} {:>14}",
+            label,
+            recent.unwrap_or_else(dash),
+            lifetime.unwrap_or_else(dash)
+        )
+    };
+    let heading = |key: &str| Some(locale.get(key).to_string());
+    let mut lines = vec![row("", heading("stats.recent"), heading("stats.lifetime"))];
+    for ((key, recent), lifetime) in keys.into_iter().zip(cells(recent)).zip(cells(lifetime)) {
+        lines.push(row(locale.get(key), recent, lifetime));
+    }
+    if lifetime.started() < aggregate::MIN_SESSIONS {
+        lines.push(String::new());
+        lines.push(locale.format(
+            "stats.too_few",
+            &[("count", &aggregate::MIN_SESSIONS.to_string())],
+        ));
+    }
+    lines
+}
+
+/// Lines printed by `--stats`, e.g. `Today: 5 pomodoros, 2h05m focused.`
+fn brief_lines(brief: &aggregate::Brief, locale: &Locale) -> Vec<String> {
+    let count = |count: u32, one: &str, many: &str| {
+        let key = if count == 1 { one } else { many };
+        locale.format(key, &[("count", &count.to_string())])
+    };
+    let pomodoros = |count_of: u32| count(count_of, "brief.pomodoro", "brief.pomodoros");
+
+    vec![
+        locale.format(
+            "brief.today",
+            &[
+                ("pomodoros", &pomodoros(brief.today.completed)),
+                (
+                    "focused",
+                    &aggregate::hours_minutes(brief.today.focused_secs),
+                ),
+            ],
+        ),
+        locale.format(
+            "brief.week",
+            &[("pomodoros", &pomodoros(brief.week.completed))],
+        ),
+        locale.format(
+            "brief.streak",
+            &[("days", &count(brief.streak, "brief.day", "brief.days"))],
+        ),
+    ]
+}
+
+/// Lines of the breakdown, `width` columns wide: the name, cut short with
+/// an ellipsis when too long, a bar against the longest total, and the
+/// time. Sessions without a label are dimmed.
+fn breakdown_lines(chart: &Breakdown, width: usize, locale: &Locale) -> Vec<Line<'static>> {
+    const TIME_WIDTH: usize = 7;
+
+    if chart.totals.is_empty() {
+        return vec![Line::from(locale.get("stats.no_sessions").to_string())];
+    }
+
+    let unlabeled = locale.get("stats.unlabeled");
+    let name_of = |name: &Option<String>| name.as_deref().unwrap_or(unlabeled).to_string();
+    let longest = chart
+        .totals
+        .iter()
+        .map(|(name, _)| name_of(name).chars().count())
+        .max()
+        .unwrap_or(0);
+    let name_width = longest.min(width / 3).max(1);
+    let bar_width = width.saturating_sub(name_width + TIME_WIDTH + 2);
+    let most = chart.totals[0].1.max(1);
+
+    chart
+        .totals
+        .iter()
+        .map(|(name, secs)| {
+            let style = match name {
+                Some(_) => Style::default(),
+                None => Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC),
+            };
+            let fraction = *secs as f32 / most as f32;
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<name_width$}", truncate(&name_of(name), name_width)),
+                    style,
+                ),
+                Span::raw(" "),
+                Span::styled(
+                    format!("{:<bar_width$}", eighths_bar(fraction, bar_width)),
+                    style,
+                ),
+                Span::raw(format!(" {:>TIME_WIDTH$}", aggregate::hours_minutes(*secs))),
+            ])
+        })
+        .collect()
+}
+
+/// Draws the stats screen in place of the timer.
+fn stats_ui<B: Backend>(f: &mut Frame<B>, app: &App, overview: &Overview) {
+    let heading = match &app.breakdown {
+        Some(chart) => format!("{} · {}", app.tr("stats.title"), app.tr(chart.period.key())),
+        None => app.tr("stats.title").to_string(),
+    };
+    let title = match &app.message {
+        Some((message, _)) => format!("{} · {}", heading, message),
+        None => heading,
+    };
+    let footer = match app.breakdown {
+        Some(_) => "stats.breakdown_footer",
+        None => "stats.footer",
+    };
+    let block = bordered_block(app)
+        .style(app.config.text_style())
+        .title(Span::styled(
+            title,
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .title(
+            Title::from(app.tr(footer))
+                .position(Position::Bottom)
+                .alignment(Alignment::Center),
+        );
+    let lines: Vec<Line> = match &app.breakdown {
+        Some(chart) => {
+            let width = block.inner(f.size()).width as usize;
+            breakdown_lines(chart, width, &app.locale)
+        }
+        None => stats_lines(overview, &app.locale)
+            .into_iter()
+            .map(Line::from)
+            .collect(),
+    };
+    f.render_widget(Paragraph::new(lines).block(block), f.size());
+}
+
+/// Draws the summary in place of the timer.
+fn summary_ui<B: Backend>(f: &mut Frame<B>, app: &App, list: &ListState) {
+    let title = match &app.message {
+        Some((message, _)) => format!("{} · {}", app.tr("summary.title"), message),
+        None => app.tr("summary.title").to_string(),
+    };
+    let block = bordered_block(app)
+        .style(app.config.text_style())
+        .title(Span::styled(
+            title,
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .title(
+            Title::from(app.tr("summary.footer"))
+                .position(Position::Bottom)
+                .alignment(Alignment::Center),
+        );
+    let items: Vec<ListItem> = summary_lines(&app.stats, app.skipped_today(), &app.locale)
+        .into_iter()
+        .map(ListItem::new)
+        .collect();
+    let summary = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    f.render_stateful_widget(summary, f.size(), &mut list.clone());
+}
+
+/// Draws the presets screen in place of the timer.
+fn presets_ui<B: Backend>(f: &mut Frame<B>, app: &App, list: &ListState) {
+    let size = f.size();
+    let input_height = if app.edit_mode { INPUT_HEIGHT as u16 } else { 0 };
+    let chunks = create_chunks(
+        size,
+        Direction::Vertical,
+        &[Constraint::Min(0), Constraint::Length(input_height)],
+    );
+
+    let title = match &app.message {
+        Some((message, _)) => format!("{} · {}", app.tr("presets.title"), message),
+        None => app.tr("presets.title").to_string(),
+    };
+    let block = bordered_block(app)
+        .style(app.config.text_style())
+        .title(Span::styled(
+            title,
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .title(
+            Title::from(app.tr("presets.footer"))
+                .position(Position::Bottom)
+                .alignment(Alignment::Center),
+        );
+
+    if app.config.presets.is_empty() {
+        let empty = Paragraph::new(app.tr("presets.empty"))
+            .block(block)
+            .alignment(Alignment::Center);
+        f.render_widget(empty, chunks[0]);
+    } else {
+        let items: Vec<ListItem> = app
+            .config
+            .presets
+            .iter()
+            .map(|(name, duration)| ListItem::new(format!("{:<20} {}", name, duration)))
+            .collect();
+        let presets = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        f.render_stateful_widget(presets, chunks[0], &mut list.clone());
+    }
+
+    if app.edit_mode {
+        input_ui(f, app, chunks[1]);
+    }
+}
+
+/// Applies a terminal event to the app. Returns `true` when the app should
+/// quit.
+fn handle_event(app: &mut App, event: Event) -> bool {
+    let key = match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => key,
+        Event::Paste(text) if app.edit_mode => {
+            app.paste(&text);
+            return false;
+        }
+        Event::FocusLost => {
+            app.focus_lost();
+            return false;
+        }
+        Event::FocusGained => {
+            app.focus_gained();
+            return false;
+        }
+        _ => return false,
+    };
+
+    app.exit_at = None;
+    app.silence();
+    app.nag = false;
+
+    if app.edit_mode {
+        // Recorded for strict mode, which may refuse what `enter` starts.
+        app.press(key);
+        return handle_edit_key(app, key.code);
+    }
+
+    if app.summary.is_some() {
+        handle_summary_key(app, key.code);
+        return false;
+    }
+
+    if app.presets.is_some() {
+        handle_presets_key(app, key.code);
+        return false;
+    }
+
+    if app.overview.is_some() {
+        handle_stats_key(app, key.code);
+        return false;
+    }
+
+    if app.picker.is_some() {
+        handle_picker_key(app, key);
+        return false;
+    }
+    if app.history.is_some() && handle_history_key(app, key) {
+        return false;
+    }
+
+    if app.is_celebrating() {
+        app.end_celebration();
+    }
+
+    handle_key(app, key)
+}
+
+/// Keys of the edit box: every character goes into the input, digits and
+/// letters bound to actions included.
+fn handle_edit_key(app: &mut App, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Enter if app.input_kind == InputKind::Command => {
+            if let Some(command) = app.take_command() {
+                return apply_command(app, command);
+            }
+        }
+        KeyCode::Enter if app.input_kind == InputKind::Preset => {
+            app.add_preset();
+        }
+        KeyCode::Enter if app.input_kind == InputKind::Note => {
+            app.submit_note(false);
+        }
+        KeyCode::Enter => {
+            app.submit_time();
+        }
+        KeyCode::Tab if app.input_kind == InputKind::Command => {
+            app.complete_command();
+        }
+        KeyCode::Tab if app.input_kind == InputKind::Duration => {
+            app.complete_preset();
+        }
+        KeyCode::Char(to_insert) => {
+            app.enter_char(to_insert);
+        }
+        KeyCode::Backspace => {
+            app.delete_char();
+        }
+        KeyCode::Left => {
+            app.move_cursor_left();
+        }
+        KeyCode::Right => {
+            app.move_cursor_right();
+        }
+        KeyCode::Up if app.input_kind == InputKind::Duration => {
+            app.step_field(1);
+        }
+        KeyCode::Down if app.input_kind == InputKind::Duration => {
+            app.step_field(-1);
+        }
+        KeyCode::Esc if app.input_kind == InputKind::Note => {
+            app.submit_note(true);
+        }
+        KeyCode::Esc => {
+            app.exit_edit();
+        }
+        _ => {}
+    }
+    false
+}
+
+/// Keys outside the edit box. While no session runs, the `quick_start`
+/// keys come first and start their duration; then the keymap. A digit
+/// bound to neither opens the edit box with the digit typed in.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    if app.press(key) {
+        return false;
+    }
+    // Digits held with ctrl or alt are bindings, never durations.
+    let idle = !app.is_active()
+        && !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    if let (KeyCode::Char(c), true) = (key.code, idle) {
+        if let Some(duration) = app.config.quick_start.get(&c).copied() {
+            app.start_with(duration, None);
+            return false;
+        }
+    }
+
+    // Alt on a key bound without it runs its action, for strict mode to let
+    // through.
+    let action = match app.keymap.action(key.code, key.modifiers) {
+        Some(action) => Some(action),
+        None if key.modifiers.contains(KeyModifiers::ALT) => app
+            .keymap
+            .action(key.code, key.modifiers - KeyModifiers::ALT),
+        None => None,
+    };
+    match action {
+        Some(action) => apply_action(app, action),
+        None => {
+            if let (KeyCode::Char(c @ '0'..='9'), true) = (key.code, idle) {
+                app.start_typing(c);
+            }
+            false
+        }
+    }
+}
+
+fn handle_summary_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_summary(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_summary(1),
+        KeyCode::Char('Y') => app.copy_summary(),
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char(' ') => {
+            app.close_summary()
+        }
+        _ => {}
+    }
+}
+
+fn handle_stats_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('S') => {
+            app.close_stats()
+        }
+        KeyCode::Char('t') => app.toggle_breakdown(),
+        KeyCode::Char('p') => app.cycle_breakdown(),
+        KeyCode::Char('Y') => app.copy_today(),
+        _ => {}
+    }
+}
+
+fn handle_presets_key(app: &mut App, code: KeyCode) {
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_preset(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_preset(1),
+        KeyCode::Enter => app.start_preset(),
+        KeyCode::Char('a') => app.prompt_preset(),
+        KeyCode::Char('d') => app.delete_preset(),
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => app.close_presets(),
+        _ => {}
+    }
+}
+
+/// Keys of the history pane; the others still reach the timer. Returns
+/// whether the key was taken.
+fn handle_history_key(app: &mut App, key: KeyEvent) -> bool {
+    let code = key.code;
+    let (confirm, typing) = match &app.history {
+        Some(pane) => (pane.confirm, pane.typing),
+        None => return false,
+    };
+    if confirm {
+        app.delete_history(code == KeyCode::Char('y'));
+        return true;
+    }
+    if typing {
+        match code {
+            KeyCode::Enter => app.submit_history_search(),
+            KeyCode::Esc => {
+                if let Some(pane) = app.history.as_mut() {
+                    pane.typing = false;
+                }
+                app.input_str.clear();
+                app.reset_cursor();
+            }
+            KeyCode::Char(c) => app.enter_char(c),
+            KeyCode::Backspace => app.delete_char(),
+            KeyCode::Left => app.move_cursor_left(),
+            KeyCode::Right => app.move_cursor_right(),
+            _ => {}
+        }
+        return true;
+    }
+
+    // j/k scroll the pane while it is open; other keys bound to an action
+    // keep working on the timer.
+    match code {
+        KeyCode::Char('k') => {
+            app.move_history(-1);
+            return true;
+        }
+        KeyCode::Char('j') => {
+            app.move_history(1);
+            return true;
+        }
+        _ => {}
+    }
+    if app.keymap.action(code, key.modifiers).is_some() {
+        return false;
+    }
+    match code {
+        KeyCode::Up => app.move_history(-1),
+        KeyCode::Down => app.move_history(1),
+        KeyCode::PageUp => app.move_history(-HISTORY_PAGE),
+        KeyCode::PageDown => app.move_history(HISTORY_PAGE),
+        KeyCode::Char('d') => {
+            if let Some(pane) = app.history.as_mut() {
+                pane.confirm = pane.list.selected().is_some();
+            }
+        }
+        KeyCode::Esc => app.close_history_search(),
+        _ => return false,
+    }
+    true
+}
+
+/// Keys of the picker: the arrows or ctrl+n / ctrl+p move the highlight,
+/// anything else typed goes into the filter line.
+fn handle_picker_key(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let picker = match app.picker.as_mut() {
+        Some(picker) => picker,
+        None => return,
+    };
+    match key.code {
+        KeyCode::Up => picker.step(-1),
+        KeyCode::Char('p') if ctrl => picker.step(-1),
+        KeyCode::Down => picker.step(1),
+        KeyCode::Char('n') if ctrl => picker.step(1),
+        KeyCode::Enter => app.pick(),
+        KeyCode::Esc => app.close_picker(),
+        KeyCode::Char(c) if !ctrl => {
+            app.enter_char(c);
+            app.filter_picker();
+        }
+        KeyCode::Backspace => {
+            app.delete_char();
+            app.filter_picker();
+        }
+        KeyCode::Left => app.move_cursor_left(),
+        KeyCode::Right => app.move_cursor_right(),
+        _ => {}
+    }
+}
+
+/// Performs a keyboard action. Returns `true` when the app should quit.
+fn apply_action(app: &mut App, action: Action) -> bool {
+    match action {
+        Action::Toggle => app.toggle(),
+        Action::Start => app.start(),
+        Action::Edit => app.enter_edit(),
+        Action::Reset => app.reset(),
+        Action::Stop => app.stop(),
+        Action::ToggleDisplay => app.toggle_display(),
+        Action::Copy => app.copy_time(),
+        Action::Quit => return !app.strict_refuses(Action::Quit),
+        Action::AddMinute => app.adjust(action, SECS_IN_MIN as i64),
+        Action::SubtractMinute => app.adjust(action, -(SECS_IN_MIN as i64)),
+        Action::AddFiveMinutes => app.adjust(action, 5 * SECS_IN_MIN as i64),
+        Action::SubtractFiveMinutes => app.adjust(action, -5 * SECS_IN_MIN as i64),
+        Action::ClearDuration => app.clear_duration(),
+        Action::Palette => app.open_palette(),
+        Action::NextTimer => app.cycle_timer(1),
+        Action::PreviousTimer => app.cycle_timer(-1),
+        Action::Timer1 => app.select_numbered(1),
+        Action::Timer2 => app.select_numbered(2),
+        Action::Timer3 => app.select_numbered(3),
+        Action::Timer4 => app.select_numbered(4),
+        Action::Timer5 => app.select_numbered(5),
+        Action::Timer6 => app.select_numbered(6),
+        Action::Timer7 => app.select_numbered(7),
+        Action::Timer8 => app.select_numbered(8),
+        Action::Timer9 => app.select_numbered(9),
+        Action::Presets => app.open_presets(),
+        Action::History => app.toggle_history(),
+        Action::SearchHistory => app.prompt_history_search(),
+        Action::SkipBreak => app.skip_break(),
+        Action::RoundUp => app.round_up(),
+        Action::Stats => app.open_stats(),
+        Action::Picker => app.open_picker(),
+        Action::Interrupt => app.interrupt(),
+        Action::ReloadConfig => app.reload_config(),
+        Action::ToggleFocus => app.toggle_focus(),
+        Action::ToggleSeconds => app.toggle_seconds(),
+        Action::ToggleFill => app.toggle_fill(),
+        Action::PauseAll => app.pause_all(),
+    }
+
+    false
+}
+
+/// Runs a command palette line. Returns `true` when the app should quit.
+fn apply_command(app: &mut App, command: Command) -> bool {
+    match command {
+        Command::Action(action) => return apply_action(app, action),
+        Command::StartWith { .. } if app.strict_refuses(Action::Start) => {}
+        Command::StartWith { duration, label } => app.start_with(duration, label),
+        Command::Routine(Some(name)) => app.start_routine(&name),
+        Command::Routine(None) => app.list_routines(),
+        Command::Theme(Some(name)) => app.switch_theme(name),
+        Command::Theme(None) => app.list_themes(),
+        Command::Pause => app.pause(),
+        Command::Abandon => app.abandon(),
+        Command::Resume => app.resume(),
+        Command::Timer(name) => app.switch_to(name),
+        Command::Config { edit: false } => app.show_config_path(),
+        Command::Config { edit: true } => app.edit_config = true,
+    }
+
+    false
+}
+
+/// Hands the terminal over to `$VISUAL` or `$EDITOR` (`vi` by default) on
+/// the config file until it exits. The terminal modes the editor may have
+/// turned off are turned back on afterwards.
+fn edit_config<B: Backend>(
+    terminal: &mut Terminal<B>,
+    focus_events: bool,
+    inline: bool,
+) -> Result<(), Box<dyn Error>> {
+    let path = config::config_path().ok_or("no config directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or("empty $EDITOR")?;
+
+    disable_raw_mode()?;
+    execute!(screen(), DisableMouseCapture)?;
+    if !inline {
+        execute!(screen(), LeaveAlternateScreen)?;
+    }
+
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status();
+
+    enable_raw_mode()?;
+    if !inline {
+        execute!(screen(), EnterAlternateScreen)?;
+    }
+    execute!(screen(), EnableMouseCapture, EnableBracketedPaste)?;
+    if focus_events {
+        execute!(screen(), EnableFocusChange)?;
+    }
+    terminal.clear()?;
+
+    match status? {
+        status if status.success() => Ok(()),
+        status => Err(format!("{} exited with {}", program, status).into()),
+    }
+}
+
+fn run_app<B: Backend + Write>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    tick_rate: Duration,
+    commands: Receiver<Command>,
+) -> io::Result<()> {
+    let mut last_tick = Instant::now();
+    let mut last_wall = Local::now();
+    let mut title = String::new();
+
+    loop {
+        app.publish();
+        terminal.draw(|f| ui(f, &app))?;
+        if app.config.terminal_title && app.window_title() != title {
+            title = app.window_title();
+            execute!(terminal.backend_mut(), SetTitle(&title))?;
+        }
+
+        let mut timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if let Some((at, _)) = app.rings.iter().min_by_key(|(at, _)| *at) {
+            timeout = timeout.min(at.saturating_duration_since(Instant::now()));
+        }
+        if app.timer().state == TimerState::Running {
+            timeout = timeout.min(until_next_second(app.remaining()));
+            // The breathing pacer and the bar of the minute move more
+            // smoothly than by the second.
+            if app.shows_tenths() || app.breathing().is_some() || app.minute_progress().is_some() {
+                timeout = timeout.min(TENTHS_TICK);
+            }
+        }
+
+        if crossterm::event::poll(timeout)? && handle_event(&mut app, event::read()?) {
+            app.quit();
+            return Ok(());
+        }
+        if app.edit_config {
+            app.edit_config = false;
+            if let Err(err) =
+                edit_config(terminal, app.config.focus_lost.is_some(), app.config.inline)
+            {
+                app.show_error("message.editor", err);
+            }
+            app.reload_config();
+        }
+        while let Ok(command) = commands.try_recv() {
+            if apply_command(&mut app, command) {
+                app.quit();
+                return Ok(());
+            }
+        }
+        if last_tick.elapsed() >= tick_rate {
+            let now = Local::now();
+            app.check_clock(now - last_wall, last_tick.elapsed());
+            last_wall = now;
+            last_tick = Instant::now();
+
+            app.update_celebration();
+            app.check_schedule();
+            app.update_message();
+
+            if app.should_auto_exit() {
+                app.quit();
+                return Ok(());
+            }
+        }
+
+        app.update_countdown();
+        app.ring_alarm();
+        send_notifications(terminal, &mut app);
+        let rings = app.due_rings(Instant::now());
+        if !rings.is_empty() {
+            for ring in rings {
+                Write::write_all(terminal.backend_mut(), ring.sequence().as_bytes())?;
+            }
+            Write::flush(terminal.backend_mut())?;
+        }
+        if let Some(sequence) = app.osc52.take().as_deref().and_then(osc52::sequence) {
+            Write::write_all(terminal.backend_mut(), sequence.as_bytes())?;
+            Write::flush(terminal.backend_mut())?;
+        }
+    }
+}
+
+/// Sends the queued notifications. Escapes go straight to the terminal,
+/// past the frame diffing that would drop them.
+fn send_notifications<B: Backend + Write>(terminal: &mut Terminal<B>, app: &mut App) {
+    let kind = match app.config.notifications {
+        Some(kind) if !app.notifications.is_empty() => kind,
+        _ => return,
+    };
+
+    let notifier = notify::notifier(kind);
+    for (title, body, urgent) in std::mem::take(&mut app.notifications) {
+        let out = terminal.backend_mut();
+        let sent = if urgent {
+            notifier.notify_urgent(out, &title, &body)
+        } else {
+            notifier.notify(out, &title, &body)
+        };
+        if let Err(err) = sent {
+            app.show_error("message.notification", err);
+        }
+    }
+}
+
+/// Formats the state for `output`; `idle` stands in for the template
+/// while nothing runs.
+fn format_status(
+    state: &State,
+    format: Option<&str>,
+    output: Output,
+    idle: &str,
+) -> Result<String, Box<dyn Error>> {
+    let text = match output {
+        Output::Json => serde_json::to_string(state)?,
+        Output::Plain if state.status == Status::Idle => String::from(idle),
+        Output::Plain => template::render(format.unwrap_or("{remaining}"), state),
+        Output::I3blocks if state.status == Status::Idle => format!("{}\n{}", idle, idle),
+        Output::I3blocks => format!(
+            "{}\n{}\n{}",
+            template::render(format.unwrap_or("{icon} {remaining}"), state),
+            template::render("{remaining}", state),
+            template::render("{color}", state)
+        ),
+    };
+
+    Ok(text)
+}
+
+fn print_status(format: Option<&str>, output: Output, watch: bool) -> Result<(), Box<dyn Error>> {
+    let idle = match output {
+        Output::Json => String::new(),
+        Output::Plain | Output::I3blocks => Config::load()?.status_idle,
+    };
+
+    loop {
+        let state = match State::load()? {
+            Some(state) => state.current(),
+            None => State::idle(),
+        };
+
+        let mut stdout = io::stdout();
+        writeln!(stdout, "{}", format_status(&state, format, output, &idle)?)?;
+        stdout.flush()?;
+
+        if !watch {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    if let Some(name) = &args.profile {
+        profile::set(name)?;
+    }
+
+    if args.status {
+        return print_status(None, Output::Json, false);
+    }
+    if args.stats {
+        let today = Local::now().date_naive();
+        let mut config = Config::load()?;
+        config.lang = args.lang.clone().or(config.lang);
+        let locale = Locale::load(&config)?;
+        let first = report::week_start(today, config.week_start.weekday());
+        let entries = history::load()?;
+        if entries.is_empty() {
+            println!("{}", locale.get("brief.empty"));
+        } else {
+            for line in brief_lines(&aggregate::brief(&entries, first, today), &locale) {
+                println!("{}", line);
+            }
+        }
+        return Ok(());
+    }
+    if args.dump_config {
+        let mut config = Config::load()?;
+        for warning in config.apply_env() {
+            eprintln!("pomidor: ignoring {}", warning);
+        }
+        print!("{}", toml::to_string(&config)?);
+        return Ok(());
+    }
+    if let Some(CliCommand::Status {
+        format,
+        output,
+        watch,
+    }) = &args.command
+    {
+        let output = output.unwrap_or(match format {
+            Some(_) => Output::Plain,
+            None => Output::Json,
+        });
+        return print_status(format.as_deref(), output, *watch);
+    }
+    if let Some(CliCommand::History { action }) = &args.command {
+        match action {
+            Some(HistoryAction::Compact) => {
+                let (summed, kept) = history::compact()?;
+                println!(
+                    "summed {} sessions of the rotated logs per month, {} of this month left in the log",
+                    summed, kept
+                );
+            }
+            None => print!("{}", report::months(&history::months()?)),
+        }
+        return Ok(());
+    }
+    if let Some(CliCommand::Import { csv, map }) = &args.command {
+        let columns = csv::Columns::parse(map).map_err(|err| format!("invalid --map: {}", err))?;
+        let content = fs::read_to_string(csv)
+            .map_err(|err| format!("cannot read {}: {}", csv.display(), err))?;
+        history::rotate(Config::load()?.history_max_bytes)?;
+        let import = csv::import(&content, &columns, &history::load()?);
+        for (row, err) in &import.malformed {
+            eprintln!("pomidor: skipping row {}: {}", row, err);
+        }
+        history::insert(&import.entries)?;
+        println!(
+            "imported {} sessions, skipped {} malformed rows and {} already in the history",
+            import.entries.len(),
+            import.malformed.len(),
+            import.duplicates
+        );
+        return Ok(());
+    }
+    if let Some(CliCommand::Export { .. }) = &args.command {
+        print!("{}", csv::export(&history::load()?));
+        return Ok(());
+    }
+    if let Some(CliCommand::Stats { period, .. }) = &args.command {
+        let today = Local::now().date_naive();
+        let first = period.first_day(today, Config::load()?.week_start.weekday());
+        let export = aggregate::export(&history::load()?, period.name(), first, today);
+        println!("{}", serde_json::to_string_pretty(&export)?);
+        return Ok(());
+    }
+    if let Some(CliCommand::Report {
+        markdown,
+        week,
+        by_tag,
+        json,
+        date,
+    }) = &args.command
+    {
+        let date = date.unwrap_or_else(|| Local::now().date_naive());
+        let period = match week {
+            Some(ago) => {
+                let first = report::week_start(date, Config::load()?.week_start.weekday());
+                Period::Week(first - chrono::Days::new(7 * ago))
+            }
+            None => Period::Day(date),
+        };
+        let (first, last) = period.range();
+        let entries = history::load_between(first, last)?;
+        let report = match period {
+            _ if *json => format!("{}\n", report::json(&entries, period)?),
+            _ if *by_tag => report::by_tag(&entries, period, *markdown),
+            Period::Week(first) => report::week(&entries, first, *markdown),
+            Period::Day(date) => report::day(&entries, date, *markdown),
+        };
+        print!("{}", report);
+        return Ok(());
+    }
+
+    let mut config = Config::load()?;
+    if let Some(theme) = args.theme {
+        config.theme = theme;
+    }
+    let mut warnings = config.apply_env();
+    warnings.extend(config.check_sounds());
+    warnings.extend(config.load_themes());
+    warnings.extend(load_banner_font(config.font.as_deref()));
+    for warning in warnings {
+        eprintln!("pomidor: ignoring {}", warning);
+    }
+    if let Some(duration) = args.duration {
+        config.default_duration = duration;
+    }
+    if let Some(delay) = args.exit_after {
+        config.exit_after_finish_secs = Some(delay.as_secs());
+    }
+    if let Some(text) = args.done_text {
+        config.done_text = Some(text);
+    }
+    if let Some(lang) = args.lang {
+        config.lang = Some(lang);
+    }
+    if args.inline {
+        config.inline = true;
+    }
+    let keymap = Keymap::new(&config.keys).map_err(|err| format!("invalid [keys]: {}", err))?;
+    let queue = match &args.queue {
+        Some(path) => Some(Queue::load(path, config.bare_seconds_below)?),
+        None => None,
+    };
+    let routine = match &args.command {
+        Some(CliCommand::Run { name }) => {
+            config.clone().apply_routine(name)?;
+            Some(name.clone())
+        }
+        _ => None,
+    };
+
+    let tick_rate = Duration::from_millis(250);
+    let mut app = App::new(config, keymap);
+    if banner_font().is_none() {
+        app.show_message(app.tr("message.no_font").to_string());
+    }
+    if let Ok(Some(state)) = State::load() {
+        app.timer_mut().last_duration = Duration::from_secs(state.last_duration_secs);
+        if let Some(day) = state.skipped_on() {
+            app.breaks_skipped = (day, state.breaks_skipped);
+        }
+    }
+    if let Some(name) = routine {
+        app.start_routine(&name);
+    }
+    if let Some(queue) = queue {
+        app.start_queue(queue);
+    }
+    if let Some(time) = args.at {
+        app.schedule(time);
+    }
+
+    // Integrations running on other threads send their commands here.
+    #[cfg_attr(not(any(feature = "dbus", feature = "http")), allow(unused_variables))]
+    let (remote, commands) = mpsc::channel();
+    #[cfg(feature = "dbus")]
+    app.observers.push(dbus::spawn(remote.clone()));
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = &app.config.mqtt {
+        app.observers.push(mqtt::spawn(mqtt));
+    }
+    #[cfg(feature = "http")]
+    if let Some(addr) = args.listen {
+        let observer = http::spawn(addr, remote.clone())
+            .map_err(|err| format!("cannot listen on {}: {}", addr, err))?;
+        app.observers.push(observer);
+    }
+
+    if let Some(path) = &args.events {
+        if path.as_os_str() == "-" {
+            // The timer moves over to stderr to leave stdout to the events.
+            SCREEN_ON_STDERR.store(true, Ordering::Relaxed);
+            app.events = Some(events::spawn(io::stdout()));
+        } else {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| format!("cannot open {}: {}", path.display(), err))?;
+            app.events = Some(events::spawn(file));
+        }
+    }
+
+    // Terminals that don't report focus changes never see the request.
+    let focus_events = app.config.focus_lost.is_some();
+    let inline = app.config.inline;
+    let title = app.config.terminal_title;
+
+    install_panic_hook(screen, inline, focus_events, title);
+
+    enable_raw_mode()?;
+    let mut out = screen();
+    if !inline {
+        execute!(out, EnterAlternateScreen)?;
+    }
+    execute!(out, EnableMouseCapture, EnableBracketedPaste)?;
+    if focus_events {
+        execute!(out, EnableFocusChange)?;
+    }
+    if title {
+        execute!(out, Print(PUSH_TITLE))?;
+    }
+    let backend = CrosstermBackend::new(out);
+    let mut terminal = Terminal::new(backend)?;
+    if inline {
+        terminal.clear()?;
+    }
+
+    let res = run_app(&mut terminal, app, tick_rate, commands);
+
+    disable_raw_mode()?;
+    if inline {
+        // The prompt goes under the last frame rather than over it.
+        let bottom = terminal.size()?.height.saturating_sub(1);
+        execute!(terminal.backend_mut(), crossterm::cursor::MoveTo(0, bottom))?;
+        writeln!(terminal.backend_mut())?;
+    } else {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    }
+    // The screen may be quit in the middle of a flash.
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        Print(Ring::FlashOff.sequence())
+    )?;
+    if focus_events {
+        execute!(terminal.backend_mut(), DisableFocusChange)?;
+    }
+    if title {
+        // Terminals without a title stack are left with an empty one.
+        execute!(terminal.backend_mut(), SetTitle(""), Print(POP_TITLE))?;
+    }
+    terminal.show_cursor()?;
+
+    if let Err(err) = res {
+        println!("{err:?}");
+    }
+
+    Ok(())
+}
+
+/// Puts the terminal back on `out()` before a panic prints its message,
+/// which would otherwise land in the alternate screen and leave the shell
+/// in raw mode.
+fn install_panic_hook(
+    out: fn() -> Box<dyn Write + Send>,
+    inline: bool,
+    focus_events: bool,
+    title: bool,
+) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = restore_terminal(&mut out(), inline, focus_events, title);
+        default_hook(info);
+    }));
+}
+
+/// Writes what undoes the setup of the screen to `out`, short of the raw
+/// mode.
+fn restore_terminal(
+    out: &mut impl Write,
+    inline: bool,
+    focus_events: bool,
+    title: bool,
+) -> io::Result<()> {
+    if !inline {
+        execute!(out, LeaveAlternateScreen)?;
+    }
+    execute!(
+        out,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        crossterm::cursor::Show
+    )?;
+    if focus_events {
+        execute!(out, DisableFocusChange)?;
+    }
+    if title {
+        execute!(out, SetTitle(""), Print(POP_TITLE))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn finishing_celebrates_until_reset() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().state = TimerState::Finished;
+        app.finish();
+        assert!(app.is_celebrating());
+
+        app.reset();
+        assert_eq!(app.timer().state, TimerState::Running);
+        assert!(!app.is_celebrating());
+    }
+
+    #[test]
+    fn empty_text_turns_the_celebration_off() {
+        let config = Config {
+            celebration_text: String::new(),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.finish();
+        assert!(!app.is_celebrating());
+    }
+
+    use keymap::Keys;
+
+    fn press(app: &mut App, code: KeyCode) {
+        handle_event(app, Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    /// What the last copy put on the clipboard, the system one or the
+    /// terminal's.
+    fn copied(app: &App) -> Option<String> {
+        #[cfg(feature = "clipboard")]
+        if let Some(text) = &app.clipboard.copied {
+            return Some(text.clone());
+        }
+        app.osc52.clone()
+    }
+
+    /// A history pane listing `count` sessions, the first highlighted.
+    fn history_pane(count: usize) -> HistoryPane {
+        let line = r#"{"start":"2024-05-01T10:00:00+02:00","end":"2024-05-01T10:25:00+02:00","duration_secs":1500,"kind":"work","outcome":"completed"}"#;
+        let mut pane = HistoryPane {
+            entries: vec![serde_json::from_str(line).unwrap(); count],
+            ..HistoryPane::default()
+        };
+        pane.list.select(Some(0));
+        pane
+    }
+
+    #[test]
+    fn only_work_sessions_are_celebrated() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        app.finish();
+        assert!(!app.is_celebrating());
+        app.timer_mut().kind = SessionKind::LongBreak;
+        app.finish();
+        assert!(!app.is_celebrating());
+
+        app.timer_mut().kind = SessionKind::Work;
+        app.finish();
+        assert!(app.is_celebrating());
+    }
+
+    #[test]
+    fn rings_come_due_in_order() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        let now = Instant::now();
+        app.rings = vec![
+            (now + Duration::from_millis(400), Ring::Bell),
+            (now, Ring::FlashOn),
+            (now + Duration::from_millis(150), Ring::FlashOff),
+        ];
+        assert_eq!(app.due_rings(now), [Ring::FlashOn]);
+        assert!(app.due_rings(now).is_empty());
+        assert_eq!(
+            app.due_rings(now + Duration::from_secs(1)),
+            [Ring::FlashOff, Ring::Bell]
+        );
+    }
+
+    #[test]
+    fn any_key_silences_the_alarm_but_ends_the_flash() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.alarm = Some((Instant::now(), Alarm::new(None)));
+        app.ring(sound::alarm(3, true));
+        handle_event(&mut app, Event::Key(KeyCode::Char('x').into()));
+        assert!(app.alarm.is_none());
+        let rings = app.due_rings(Instant::now() + Duration::from_secs(5));
+        assert!(rings.iter().all(|ring| *ring == Ring::FlashOff));
+    }
+
+    #[test]
+    fn escalation_resends_the_notification_as_urgent() {
+        let config = Config {
+            notifications: Some(config::Notifications::Osc9),
+            alarm: vec![
+                config::AlarmStep {
+                    after_secs: 0,
+                    bells: 1,
+                    flash: false,
+                    notify: false,
+                },
+                config::AlarmStep {
+                    after_secs: 60,
+                    bells: 2,
+                    flash: false,
+                    notify: true,
+                },
+            ],
+            alarm_repeat_secs: Some(60),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.timer_mut().state = TimerState::Finished;
+        app.alarm = Some((Instant::now(), Alarm::new(None)));
+        app.ring_alarm();
+        assert!(app.notifications.is_empty());
+        assert_eq!(app.rings.len(), 1);
+
+        // A minute on, as if the clock had moved.
+        let (since, _) = app.alarm.as_mut().unwrap();
+        *since -= Duration::from_secs(61);
+        app.ring_alarm();
+        assert_eq!(app.rings.len(), 3);
+        let urgent: Vec<bool> = app.notifications.iter().map(|n| n.2).collect();
+        assert_eq!(urgent, [true]);
+    }
+
+    #[test]
+    fn alarm_flashes_without_the_bell() {
+        let config = Config {
+            bell: false,
+            alarm: vec![config::AlarmStep {
+                after_secs: 0,
+                bells: 2,
+                flash: true,
+                notify: false,
+            }],
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.alarm = Some((Instant::now(), Alarm::new(None)));
+        app.ring_alarm();
+
+        let rings = app.due_rings(Instant::now() + Duration::from_secs(5));
+        assert!(!rings.is_empty());
+        assert!(!rings.contains(&Ring::Bell));
+    }
+
+    #[test]
+    fn suggestion_is_picked_once_per_break() {
+        let dir = std::env::temp_dir().join(format!("pomidor-suggest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("suggestions.txt");
+        let config = Config {
+            break_suggestions: true,
+            suggestions: vec![String::from("stretch")],
+            suggestions_file: Some(file.clone()),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        app.timer_mut().time = Duration::from_secs(300);
+        app.start_countdown();
+
+        // A missing file is reported once, and the list stands in.
+        app.suggest_break_activity();
+        assert!(app
+            .message
+            .take()
+            .is_some_and(|(text, _)| text.starts_with("suggestions:")));
+        assert_eq!(app.suggestion(), Some("stretch"));
+        app.suggest_break_activity();
+        assert!(app.message.is_none());
+
+        // The file is read at the next break, and kept.
+        fs::write(&file, "walk\n\n").unwrap();
+        app.start_countdown();
+        app.suggest_break_activity();
+        assert_eq!(app.suggestion(), Some("walk"));
+        fs::remove_file(&file).unwrap();
+        app.start_countdown();
+        app.suggest_break_activity();
+        assert_eq!(app.suggestion(), Some("walk"));
+        assert!(app.message.is_none());
+
+        // Nothing to suggest is tried once too.
+        app.suggestions_file = Some(Vec::new());
+        app.start_countdown();
+        app.suggest_break_activity();
+        assert_eq!(app.timer().suggestion, Some(None));
+        assert_eq!(app.suggestion(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn queued_breaks_run_as_breaks() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.start_queue(Queue::from_expression("25m work / 5m break", 0).unwrap());
+        assert_eq!(app.timer().kind, SessionKind::Work);
+        app.next_in_queue(app.current);
+        assert_eq!(app.timer().kind, SessionKind::ShortBreak);
+        assert_eq!(app.timer().label.as_deref(), Some("break"));
+    }
+
+    #[test]
+    fn breathing_pacer_is_off_by_default() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        app.timer_mut().time = Duration::from_secs(300);
+        app.start_countdown();
+        assert!(app.breathing().is_none());
+
+        app.config.breathing.enabled = true;
+        assert!(app.breathing().is_some());
+        app.timer_mut().kind = SessionKind::Work;
+        assert!(app.breathing().is_none());
+    }
+
+    #[test]
+    fn history_pane_leaves_bound_keys_to_the_timer() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.history = Some(history_pane(3));
+        let time = app.timer().time;
+        press(&mut app, KeyCode::Char('+'));
+        assert_eq!(app.timer().time, time + Duration::from_secs(60));
+        press(&mut app, KeyCode::Char('-'));
+        assert_eq!(app.timer().time, time);
+        assert_eq!(app.history.as_ref().unwrap().list.selected(), Some(0));
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.history.as_ref().unwrap().list.selected(), Some(2));
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.history.as_ref().unwrap().confirm);
+    }
+
+    #[test]
+    fn j_and_k_scroll_the_history_pane() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.history = Some(history_pane(3));
+        let time = app.timer().time;
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.history.as_ref().unwrap().list.selected(), Some(2));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.history.as_ref().unwrap().list.selected(), Some(1));
+        assert_eq!(app.timer().time, time);
+    }
+
+    #[test]
+    fn history_pane_yields_keys_rebound_in_the_keymap() {
+        let overrides = HashMap::from([(Action::Interrupt, Keys::One(String::from("down")))]);
+        let mut app = App::new(Config::default(), Keymap::new(&overrides).unwrap());
+        app.history = Some(history_pane(3));
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.history.as_ref().unwrap().list.selected(), Some(0));
+    }
+
+    #[test]
+    fn search_action_opens_the_history_pane() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        let time = app.timer().time;
+        press(&mut app, KeyCode::Char('?'));
+        assert!(app.history.as_ref().is_some_and(|pane| pane.typing));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.input_str, "k");
+        assert_eq!(app.timer().time, time);
+    }
+
+    #[test]
+    fn ctrl_p_opens_the_picker_ahead_of_quick_start() {
+        let config = Config {
+            quick_start: HashMap::from([('p', Duration::from_secs(300))]),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        handle_event(
+            &mut app,
+            Event::Key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL)),
+        );
+        assert!(app.picker.is_some());
+        assert!(!app.is_active());
+    }
+
+    #[test]
+    fn presets_with_a_bad_duration_are_reported() {
+        let config = Config {
+            presets: [
+                (String::from("tea"), String::from("03:00")),
+                (String::from("nap"), String::from("soon")),
+            ]
+            .into_iter()
+            .collect(),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(
+            app.launch_preset(String::from("nap"), App::close_presets),
+            Err(String::from("invalid duration `soon` in `nap`"))
+        );
+        assert!(!app.is_active());
+
+        app.launch_preset(String::from("tea"), App::close_presets)
+            .unwrap();
+        assert_eq!(app.timer().label.as_deref(), Some("tea"));
+        assert_eq!(app.timer().time, Duration::from_secs(180));
+    }
+
+    fn presets(names: &[&str]) -> BTreeMap<String, String> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), String::from("05:00")))
+            .collect()
+    }
+
+    #[test]
+    fn preset_completion_matches_prefixes_ignoring_case() {
+        let presets = presets(&["Tea", "tempo", "nap"]);
+        assert!(complete_preset(&presets, "x").is_empty());
+        assert_eq!(complete_preset(&presets, "n"), ["nap"]);
+        assert_eq!(complete_preset(&presets, "TE"), ["Tea", "tempo"]);
+    }
+
+    #[test]
+    fn tab_cycles_through_the_matching_presets() {
+        let config = Config {
+            presets: presets(&["tea", "tempo", "nap"]),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.enter_edit();
+        press(&mut app, KeyCode::Char('T'));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "tea");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "tempo");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "tea");
+        assert_eq!(app.cursor_position, 3);
+    }
+
+    #[test]
+    fn tab_completes_a_single_match_or_reports_none() {
+        let config = Config {
+            presets: presets(&["tea", "nap"]),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.enter_edit();
+        press(&mut app, KeyCode::Char('N'));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "nap");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "nap");
+
+        app.exit_edit();
+        app.enter_edit();
+        press(&mut app, KeyCode::Char('x'));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "x");
+        assert_eq!(app.input_error.as_deref(), Some("no matching preset"));
+
+        app.exit_edit();
+        app.enter_edit();
+        press(&mut app, KeyCode::Char('1'));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_str, "1");
+        assert!(app.input_error.is_none());
+    }
+
+    fn stepped(input: &str, cursor: usize, delta: i64) -> Option<(String, usize)> {
+        step_field(input, cursor, delta, 0)
+    }
+
+    #[test]
+    fn step_field_carries_into_the_next_field() {
+        assert_eq!(stepped("00:59", 4, 1), Some((String::from("01:00"), 4)));
+        assert_eq!(stepped("59:30", 1, 1), Some((String::from("01:00:30"), 4)));
+        // Hours typed in stay, even at zero.
+        assert_eq!(
+            stepped("01:00:30", 4, -1),
+            Some((String::from("00:59:30"), 4))
+        );
+    }
+
+    #[test]
+    fn step_field_on_a_colon_steps_the_field_after_it() {
+        assert_eq!(stepped("25:00", 2, 1), Some((String::from("25:01"), 3)));
+        assert_eq!(
+            stepped("01:25:00", 2, 1),
+            Some((String::from("01:26:00"), 3))
+        );
+    }
+
+    #[test]
+    fn step_field_from_nothing_or_a_bare_number() {
+        assert_eq!(stepped("", 0, 1), Some((String::from("01:00"), 0)));
+        assert_eq!(stepped("", 0, -1), Some((String::from("00:00"), 0)));
+        assert_eq!(stepped("25", 2, 1), Some((String::from("26:00"), 0)));
+        assert_eq!(
+            stepped("25 write report", 1, -1),
+            Some((String::from("24:00 write report"), 0))
+        );
+        assert_eq!(stepped("soon", 0, 1), None);
+    }
+
+    #[test]
+    fn step_field_stays_within_a_day() {
+        assert_eq!(
+            stepped("23:59:59", 0, 1),
+            Some((String::from("23:59:59"), 0))
+        );
+        assert_eq!(
+            stepped("23:30:00", 4, 45),
+            Some((String::from("23:59:59"), 4))
+        );
+        assert_eq!(stepped("00:10", 1, -1), Some((String::from("00:00"), 1)));
+    }
+
+    #[test]
+    fn fractional_minutes() {
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("0.25"), Some(Duration::from_secs(15)));
+        assert_eq!(parse_duration("1.5.5"), None);
+        assert_eq!(parse_duration(".5"), None);
+        assert_eq!(parse_duration("1."), None);
+        assert_eq!(parse_duration("-1.5"), None);
+    }
+
+    #[test]
+    fn clock_durations() {
+        assert_eq!(parse_duration("25:00"), Some(Duration::from_secs(1500)));
+        assert_eq!(parse_duration("01:30:05"), Some(Duration::from_secs(5405)));
+        assert_eq!(parse_duration("24:00:00"), None);
+        assert_eq!(parse_duration("25:00 tea"), None);
+        assert_eq!(parse_duration("25"), None);
+    }
+
+    fn quick_start_on_3() -> App {
+        let config = Config {
+            quick_start: HashMap::from([('3', Duration::from_secs(180))]),
+            ..Config::default()
+        };
+        App::new(config, Keymap::default())
+    }
+
+    #[test]
+    fn digit_while_idle_quick_starts() {
+        let mut app = quick_start_on_3();
+        press(&mut app, KeyCode::Char('3'));
+        assert!(app.is_active());
+        assert!(!app.edit_mode);
+        assert_eq!(app.timer().time, Duration::from_secs(180));
+    }
+
+    #[test]
+    fn digit_in_edit_mode_is_typed() {
+        let mut app = quick_start_on_3();
+        app.enter_edit();
+        app.input_str.clear();
+        app.reset_cursor();
+        for c in ['3', '0', 'e'] {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.input_str, "30e");
+        assert!(app.edit_mode);
+        assert!(!app.is_active());
+    }
+
+    #[test]
+    fn unbound_digit_opens_the_edit_box_with_it() {
+        let mut app = quick_start_on_3();
+        press(&mut app, KeyCode::Char('7'));
+        assert!(app.edit_mode);
+        assert_eq!(app.input_str, "7");
+        assert_eq!(app.cursor_position, 1);
+    }
+
+    #[test]
+    fn bound_digit_while_idle_runs_its_action() {
+        let mut app = quick_start_on_3();
+        press(&mut app, KeyCode::Char('0'));
+        assert!(!app.edit_mode);
+        assert_eq!(app.timer().time, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn digits_are_ignored_during_a_session() {
+        let mut app = quick_start_on_3();
+        app.start_with(Duration::from_secs(1500), None);
+        press(&mut app, KeyCode::Char('3'));
+        press(&mut app, KeyCode::Char('7'));
+        assert!(!app.edit_mode);
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+    }
+
+    fn strict_work_session(escape: StrictEscape) -> App {
+        let config = Config {
+            strict: true,
+            strict_escape: escape,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.start_with(Duration::from_secs(1500), None);
+        app
+    }
+
+    #[test]
+    fn strict_mode_refuses_replacing_or_shortening_the_session() {
+        let mut app = strict_work_session(StrictEscape::Hold);
+        apply_command(
+            &mut app,
+            Command::StartWith {
+                duration: Duration::from_secs(60),
+                label: None,
+            },
+        );
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('J'));
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+        assert!(app.message.is_some());
+
+        // Paused, it's still guarded.
+        app.timer_mut().pause();
+        apply_command(
+            &mut app,
+            Command::StartWith {
+                duration: Duration::from_secs(60),
+                label: None,
+            },
+        );
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+    }
+
+    #[test]
+    fn strict_mode_lets_breaks_be() {
+        let mut app = strict_work_session(StrictEscape::Hold);
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.timer().time, Duration::from_secs(1440));
+    }
+
+    #[test]
+    fn alt_gets_through_strict_mode_with_the_alt_escape() {
+        let mut app = strict_work_session(StrictEscape::Alt);
+        press(&mut app, KeyCode::Char('s'));
+        assert!(app.is_active());
+        let message = app.message.as_ref().unwrap().0.clone();
+        assert!(message.contains("alt"), "{}", message);
+        assert!(!handle_event(
+            &mut app,
+            Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE))
+        ));
+
+        handle_event(
+            &mut app,
+            Event::Key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::ALT)),
+        );
+        assert!(!app.is_active());
+    }
+
+    #[test]
+    fn holding_does_not_get_through_the_alt_escape() {
+        let mut app = strict_work_session(StrictEscape::Alt);
+        let past = Instant::now() - STRICT_HOLD;
+        app.held = Some(Hold {
+            code: KeyCode::Char('s'),
+            alt: false,
+            since: past,
+            last: Instant::now(),
+            spent: false,
+        });
+        assert!(app.strict_refuses(Action::Stop));
+    }
+
+    #[test]
+    fn an_old_hold_gets_nothing_through() {
+        let mut app = strict_work_session(StrictEscape::Hold);
+        let past = Instant::now() - STRICT_HOLD * 2;
+        app.held = Some(Hold {
+            code: KeyCode::Char('s'),
+            alt: true,
+            since: past,
+            last: past + STRICT_HOLD,
+            spent: false,
+        });
+        assert!(app.strict_refuses(Action::Stop));
+    }
+
+    #[test]
+    fn minutes_round_up_or_truncate() {
+        assert_eq!(minutes_fmt(89, true), "2");
+        assert_eq!(minutes_fmt(89, false), "1");
+        assert_eq!(minutes_fmt(60, true), "1");
+        assert_eq!(minutes_fmt(60, false), "1");
+        assert_eq!(minutes_fmt(1, true), "1");
+        assert_eq!(minutes_fmt(1, false), "0");
+        assert_eq!(minutes_fmt(0, true), "0");
+        assert_eq!(minutes_fmt(3599, true), "1:00");
+        assert_eq!(minutes_fmt(3599, false), "59");
+        assert_eq!(minutes_fmt(3900, false), "1:05");
+    }
+
+    #[test]
+    fn todays_summary_is_copied_from_the_stats_screen() {
+        let dir = std::env::temp_dir().join(format!("pomidor-brief-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let today = Local::now().date_naive();
+        let yesterday = today - chrono::Days::new(1);
+        for (day, minute) in [(yesterday, 0), (today, 0), (today, 30)] {
+            let start = day
+                .and_hms_opt(0, minute, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .earliest()
+                .unwrap()
+                .fixed_offset();
+            let entry = history::Entry {
+                start,
+                end: start + chrono::Duration::minutes(25),
+                duration_secs: 1500,
+                kind: SessionKind::Work,
+                label: None,
+                tags: Vec::new(),
+                outcome: Outcome::Completed,
+                note: None,
+                interruptions: Vec::new(),
+            };
+            entry.append().unwrap();
+        }
+
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.open_stats();
+        handle_stats_key(&mut app, KeyCode::Char('Y'));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let week = if report::week_start(today, app.config.week_start.weekday()) <= yesterday {
+            3
+        } else {
+            2
+        };
+        assert_eq!(
+            copied(&app).unwrap(),
+            format!(
+                "Today: 2 pomodoros, 50m focused.\nWeek: {} pomodoros.\nStreak: 2 days.",
+                week
+            )
+        );
+        assert_eq!(app.message.as_ref().unwrap().0, "copied today's summary");
+        assert!(app.overview.is_some());
+    }
+
+    #[test]
+    fn brief_of_a_day() {
+        let at = |hour: u32, outcome: Outcome| {
+            let start = Local
+                .with_ymd_and_hms(2024, 5, 7, hour, 0, 0)
+                .unwrap()
+                .fixed_offset();
+            history::Entry {
+                start,
+                end: start + chrono::Duration::minutes(25),
+                duration_secs: 1500,
+                kind: SessionKind::Work,
+                label: None,
+                tags: Vec::new(),
+                outcome,
+                note: None,
+                interruptions: Vec::new(),
+            }
+        };
+        let date = |day: u32| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let entries = [
+            at(9, Outcome::Completed),
+            at(10, Outcome::Completed),
+            at(11, Outcome::Abandoned),
+        ];
+        assert_eq!(
+            brief_lines(
+                &aggregate::brief(&entries, date(6), date(7)),
+                &Locale::default()
+            ),
+            [
+                "Today: 2 pomodoros, 50m focused.",
+                "Week: 2 pomodoros.",
+                "Streak: 1 day.",
+            ]
+        );
+    }
+
+    #[test]
+    fn routine_label_is_left_out_during_breaks() {
+        let routine = config::Routine {
+            label: Some(String::from("draft")),
+            ..config::Routine::default()
+        };
+        let config = Config {
+            routines: std::collections::BTreeMap::from([(String::from("writing"), routine)]),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.start_routine("writing");
+        assert!(app.status_text().unwrap().contains("draft"));
+
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        let status = app.status_text().unwrap();
+        assert!(status.contains("writing"));
+        assert!(!status.contains("draft"));
+    }
+
+    #[test]
+    fn tenths_around_ten_seconds_and_zero() {
+        let formatted: Vec<String> = [10_000, 9_999, 9_900, 100, 99, 0]
+            .into_iter()
+            .map(|millis| remain_to_fmt_tenths(Duration::from_millis(millis)))
+            .collect();
+        assert_eq!(
+            formatted,
+            ["00:10.0", "00:09.9", "00:09.9", "00:00.1", "00:00.0", "00:00.0"]
+        );
+    }
+
+    #[test]
+    fn tenths_show_below_ten_seconds() {
+        let config = Config {
+            tenths: true,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.start_countdown();
+
+        app.timer_mut().remain = Duration::from_millis(10_000);
+        assert!(!app.shows_tenths());
+        app.timer_mut().remain = Duration::from_millis(9_999);
+        assert!(app.shows_tenths());
+        app.refresh_time_str();
+        assert_eq!(app.time_str, "00:09.9");
+    }
+
+    #[test]
+    fn tenths_dropped_from_a_banner_too_wide() {
+        let width = |text: &str| {
+            generate_content(text, 0)
+                .iter()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap() as u16
+        };
+        let (tenths, whole) = (width("00:07.4"), width("00:07"));
+        assert!(whole < tenths);
+
+        assert_eq!(
+            time_content("00:07.4", 0, tenths),
+            generate_content("00:07.4", 0)
+        );
+        assert_eq!(
+            time_content("00:07.4", 0, tenths - 1),
+            generate_content("00:07", 0)
+        );
+        assert_eq!(
+            time_content("00:07", 0, whole - 1),
+            generate_content("00:07", 0)
+        );
+    }
+
+    #[test]
+    fn overtime_styles_around_an_hour() {
+        let formatted: Vec<[String; 2]> = [
+            OvertimeStyle::Plus,
+            OvertimeStyle::Minus,
+            OvertimeStyle::Over,
+        ]
+        .into_iter()
+        .map(|style| [overtime_fmt(3599, style), overtime_fmt(3600, style)])
+        .collect();
+        assert_eq!(
+            formatted,
+            [
+                ["+59:59", "+01:00:00"],
+                ["-59:59", "-01:00:00"],
+                ["59:59", "01:00:00"]
+            ]
+        );
+    }
+
+    #[test]
+    fn glyphs_are_looked_up_in_the_font_drawing_the_banner() {
+        let mut font = FIGfont::standard().unwrap();
+        assert!(has_glyph(Some(&font), '+'));
+        assert!(has_glyph(Some(&font), '-'));
+
+        font.fonts.remove(&('+' as u32));
+        assert!(!has_glyph(Some(&font), '+'));
+        assert!(has_glyph(Some(&font), '-'));
+        assert!(!has_glyph(None, '-'));
+    }
+
+    #[test]
+    fn terminal_is_restored_as_it_was_set_up() {
+        let mut out = Vec::new();
+        restore_terminal(&mut out, false, true, false).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        for sequence in ["\x1b[?1049l", "\x1b[?2004l", "\x1b[?25h", "\x1b[?1004l"] {
+            assert!(
+                written.contains(sequence),
+                "{:?} lacks {:?}",
+                written,
+                sequence
+            );
+        }
+
+        let mut out = Vec::new();
+        restore_terminal(&mut out, true, false, true).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        assert!(!written.contains("\x1b[?1049l"));
+        assert!(!written.contains("\x1b[?1004l"));
+        assert!(written.ends_with(POP_TITLE));
+    }
+
+    #[test]
+    fn stats_screen_shows_interruptions_per_session() {
+        let overview = Overview {
+            lifetime: aggregate::Aggregates {
+                completed: 5,
+                abandoned: 1,
+                interruptions_per_session: Some(0.5),
+                ..aggregate::Aggregates::default()
+            },
+            ..Overview::default()
+        };
+        let lines = stats_lines(&overview, &Locale::default());
+        assert_eq!(
+            lines.last().map(String::as_str),
+            Some(format!("{:<20} {:>14} {:>14}", "Interruptions", "-", "0.5").as_str())
+        );
+    }
+
+    #[test]
+    fn org_clock_starts_when_the_session_did() {
+        let dir = std::env::temp_dir().join(format!("pomidor-org-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("time.org");
+        let config = Config {
+            org_file: Some(file.clone()),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start_countdown();
+        // 25 minutes of work with a 15 minute pause in between.
+        let started_at = Local::now() - chrono::Duration::minutes(40);
+        app.timer_mut().started_at = Some(started_at);
+
+        app.clock_in_org(app.current);
+        let content = fs::read_to_string(&file).unwrap();
+        let clock = content.lines().last().unwrap();
+        assert!(clock.starts_with(&format!(
+            "CLOCK: [{}]",
+            started_at.format("%Y-%m-%d %a %H:%M")
+        )));
+        assert!(clock.ends_with("=>  0:40"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn i3blocks_lines() {
+        let status = |state: &State| format_status(state, None, Output::I3blocks, "idle").unwrap();
+        // The color line is left out while idle, for the default color.
+        assert_eq!(status(&State::idle()), "idle\nidle");
+
+        let running = State::running(Duration::from_secs(1500), Duration::from_secs(1200));
+        let lines: Vec<String> = status(&running).lines().map(String::from).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "20:00");
+    }
+
+    #[test]
+    fn plain_and_json_lines() {
+        let running = State::running(Duration::from_secs(1500), Duration::from_secs(1200));
+        let polybar = Some("%{F{color}}{remaining}%{F-}");
+        let plain = format_status(&running, polybar, Output::Plain, "idle").unwrap();
+        assert_eq!(plain, "%{F#e06c75}20:00%{F-}");
+        let idle = format_status(&State::idle(), polybar, Output::Plain, "idle").unwrap();
+        assert_eq!(idle, "idle");
+
+        let json = format_status(&running, None, Output::Json, "").unwrap();
+        let read: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.remaining_secs, 1200);
+    }
+
+    #[test]
+    fn alt_digits_switch_timers_and_bare_digits_still_start() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timers.push(Timer::new("reading"));
+        let alt = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT));
+
+        handle_event(&mut app, alt('2'));
+        assert_eq!(app.current, 1);
+        assert!(!app.is_active());
+
+        handle_event(&mut app, alt('5'));
+        assert_eq!(app.current, 1);
+        assert_eq!(app.message.as_ref().unwrap().0, "no timer 5");
+
+        handle_event(&mut app, alt('1'));
+        assert_eq!(app.current, 0);
+        assert!(!app.is_active());
+
+        press(&mut app, KeyCode::Char('1'));
+        assert!(app.is_active());
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+    }
+
+    #[test]
+    fn key_releases_are_ignored_outside_the_edit_mode() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        let release = |code| {
+            Event::Key(KeyEvent::new_with_kind(
+                code,
+                KeyModifiers::NONE,
+                KeyEventKind::Release,
+            ))
+        };
+
+        assert!(!handle_event(&mut app, release(KeyCode::Char('q'))));
+        handle_event(&mut app, release(KeyCode::Char(' ')));
+        handle_event(&mut app, release(KeyCode::Char('1')));
+        handle_event(&mut app, release(KeyCode::Char('e')));
+        assert!(!app.is_active());
+        assert!(!app.edit_mode);
+
+        press(&mut app, KeyCode::Char(' '));
+        assert!(app.is_active());
+    }
+
+    #[test]
+    fn wakes_on_the_next_whole_second() {
+        let cases = [
+            (Duration::from_millis(2300), Duration::from_millis(300)),
+            (Duration::from_millis(999), Duration::from_millis(999)),
+            (Duration::from_nanos(1_000_000_001), Duration::from_nanos(1)),
+            // On the boundary already, so the loop doesn't sleep at all.
+            (Duration::from_secs(2), Duration::ZERO),
+            (Duration::ZERO, Duration::ZERO),
+        ];
+        for (remain, wait) in cases {
+            assert_eq!(until_next_second(remain), wait, "{:?}", remain);
+            assert_eq!((remain - wait).subsec_nanos(), 0);
+        }
+    }
+
+    #[test]
+    fn y_copies_the_time_shown() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(copied(&app).as_deref(), Some("25:00"));
+        assert_eq!(app.message.as_ref().unwrap().0, "copied 25:00");
+    }
+
+    #[test]
+    fn summary_is_copied_as_shown() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.open_summary();
+        handle_event(&mut app, Event::Key(KeyCode::Char('Y').into()));
+        let lines = summary_lines(&app.stats, app.skipped_today(), &app.locale);
+        assert_eq!(copied(&app), Some(lines.join("\n")));
+        assert_eq!(app.message.as_ref().unwrap().0, "copied the summary");
+    }
+
+    #[test]
+    fn progress_of_a_session_without_time() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.start();
+        app.timer_mut().pause();
+        assert_eq!(app.progress_text().as_deref(), Some("0% · 00:00 / 25:00"));
+
+        app.timer_mut().time = Duration::ZERO;
+        assert_eq!(app.progress_text().as_deref(), Some("00:00 / 00:00"));
+    }
+
+    #[test]
+    fn digits_toggle_between_remaining_and_elapsed() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        let timer = app.timer_mut();
+        timer.time = Duration::from_secs(1500);
+        timer.remain = Duration::from_secs(1200);
+        app.refresh_time_str();
+        assert_eq!(app.time_str, "20:00");
+
+        app.toggle_display();
+        assert_eq!(app.time_str, "05:00");
+        assert_eq!(app.display_indicator(), "▲");
+        app.toggle_display();
+        assert_eq!(app.time_str, "20:00");
+    }
+
+    #[test]
+    fn toggling_the_digits_says_which_time_they_show() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.toggle_display();
+        assert_eq!(app.message.as_ref().unwrap().0, "▲ elapsed time");
+        app.toggle_display();
+        assert_eq!(app.message.as_ref().unwrap().0, "@ end time");
+        app.toggle_display();
+        assert_eq!(app.message.as_ref().unwrap().0, "▼ remaining time");
+    }
+
+    #[test]
+    fn clock_follows_the_configured_format() {
+        let time = Local.with_ymd_and_hms(2024, 3, 1, 14, 5, 0).unwrap();
+        let mut app = App::new(Config::default(), Keymap::default());
+        assert_eq!(app.format_clock(time), "14:05");
+
+        app.config.clock_24h = false;
+        assert_eq!(app.format_clock(time), "2:05 PM");
+    }
+
+    #[test]
+    fn end_time_is_only_projected_while_running() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        assert_eq!(app.end_time(), None);
+
+        app.timer_mut().time = Duration::from_secs(600);
+        app.start_countdown();
+        let end = app.end_time().unwrap() - Local::now();
+        assert!((end.num_seconds() - 600).abs() <= 1);
+
+        app.stop();
+        assert_eq!(app.end_time(), None);
+    }
+
+    #[test]
+    fn stopped_sessions_can_be_started_again() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.input_str = String::from("25:00");
+        app.submit_time();
+        app.stop();
+        assert_eq!(app.timer().time, Duration::ZERO);
+
+        app.reset();
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+
+        app.stop();
+        app.enter_edit();
+        assert_eq!(app.input_str, "25:00");
+        assert_eq!(app.cursor_position, 5);
+    }
+
+    #[test]
+    fn space_starts_pauses_resumes_and_acknowledges() {
+        let config = Config {
+            default_duration: Duration::ZERO,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.space_action(), None);
+
+        app.timer_mut().last_duration = Duration::from_secs(60);
+        assert_eq!(app.space_action(), Some(SpaceAction::Start));
+        app.toggle();
+        assert!(app.is_active());
+        assert_eq!(app.space_action(), Some(SpaceAction::Pause));
+
+        app.toggle();
+        assert!(app.is_paused());
+        let remaining = app.remaining();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(app.remaining(), remaining);
+        assert_eq!(app.end_time(), None);
+        assert_eq!(app.space_action(), Some(SpaceAction::Resume));
+
+        app.toggle();
+        assert!(!app.is_paused());
+        assert_eq!(app.space_action(), Some(SpaceAction::Pause));
+
+        app.timer_mut().state = TimerState::Finished;
+        app.finish();
+        assert_eq!(app.space_action(), Some(SpaceAction::Acknowledge));
+        app.toggle();
+        assert_eq!(app.timer().state, TimerState::Idle);
+    }
+
+    #[test]
+    fn strict_mode_is_asked_about_the_action_pressed() {
+        let config = Config {
+            strict: true,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.start();
+        press(&mut app, KeyCode::Char('J'));
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+        let (message, _) = app.message.clone().unwrap();
+        assert!(message.contains("subtract_five_minutes"), "{}", message);
+
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.timer().time, Duration::from_secs(1560));
+    }
+
+    #[test]
+    fn subtracting_the_whole_session_stops_it() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.start();
+        app.timer_mut().pause();
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Char('J'));
+        }
+        assert!(!app.is_active());
+        assert_eq!(app.progress_text(), None);
+    }
+
+    #[test]
+    fn auto_exit_waits_for_a_finished_session_and_no_keys() {
+        let config = Config {
+            exit_after_finish_secs: Some(0),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert!(!app.should_auto_exit());
+
+        app.finish();
+        assert!(app.should_auto_exit());
+
+        let key = crossterm::event::KeyEvent::from(KeyCode::Char('v'));
+        assert!(!handle_event(&mut app, Event::Key(key)));
+        assert!(!app.should_auto_exit());
+    }
+
+    /// What `parse_duration` should make of `input`, in seconds, worked out
+    /// field by field.
+    fn expected_duration(input: &str) -> Option<u64> {
+        let digits = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+
+        let fields: Vec<&str> = input.split(':').collect();
+        if (2..=3).contains(&fields.len()) {
+            if !fields.iter().all(|field| field.len() == 2 && digits(field)) {
+                return None;
+            }
+            let values: Vec<u64> = fields.iter().map(|field| field.parse().unwrap()).collect();
+            let (h, m, s) = match values[..] {
+                [h, m, s] => (h, m, s),
+                [m, s] => (0, m, s),
+                _ => unreachable!(),
+            };
+            return (h < 24 && m < 60 && s < 60).then_some(3600 * h + 60 * m + s);
+        }
+
+        match input.split_once('.') {
+            Some((whole, fraction)) if digits(whole) && digits(fraction) => {
+                let secs = (input.parse::<f64>().unwrap() * 60.0).round();
+                (secs < 24.0 * 3600.0).then_some(secs as u64)
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn durations_at_the_edges() {
+        let long = "9".repeat(400);
+        let huge = format!("{}.5", long);
+        let colons = ":".repeat(1000);
+        let inputs = [
+            "",
+            ":",
+            "::",
+            "00:00",
+            "23:59:59",
+            "00:00:00:00",
+            "99999999999999999999:00",
+            "1439.99",
+            "1440.0",
+            long.as_str(),
+            huge.as_str(),
+            colons.as_str(),
+            "٢٥:٠٠",
+            "２５:００",
+            "25:00\u{301}",
+            "1.5\n",
+            " 1.5",
+            "+1.5",
+            "1e3.0",
+            "NaN.0",
+            "inf.0",
+        ];
+        for input in inputs {
+            assert_eq!(
+                parse_duration(input).map(|duration| duration.as_secs()),
+                expected_duration(input),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn any_string_parses_without_panicking(input in proptest::arbitrary::any::<String>()) {
+            proptest::prop_assert_eq!(
+                parse_duration(&input).map(|duration| duration.as_secs()),
+                expected_duration(&input)
+            );
+        }
+
+        #[test]
+        fn strings_close_to_durations(input in "[0-9:.]{0,10}|[0-9]{1,25}\\.[0-9]{1,25}") {
+            proptest::prop_assert_eq!(
+                parse_duration(&input).map(|duration| duration.as_secs()),
+                expected_duration(&input)
+            );
+        }
+
+        #[test]
+        fn clock_durations_are_read_back(h in 0u64..24, m in 0u64..60, s in 0u64..60) {
+            let input = format!("{:02}:{:02}:{:02}", h, m, s);
+            proptest::prop_assert_eq!(
+                parse_duration(&input),
+                Some(Duration::from_secs(3600 * h + 60 * m + s))
+            );
+        }
+    }
+
+    #[test]
+    fn sessions_replaced_by_another_are_abandoned() {
+        let dir = std::env::temp_dir().join(format!("pomidor-replaced-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.start();
+        app.timer_mut().skip(Duration::from_secs(600));
+
+        app.start_typing('0');
+        for c in "5:00 tea".chars() {
+            app.enter_char(c);
+        }
+        app.submit_time();
+        assert_eq!(app.stats.abandoned, 1);
+        assert_eq!(app.timer().time, Duration::from_secs(300));
+
+        app.timer_mut().skip(Duration::from_secs(60));
+        app.start_with(Duration::from_secs(600), None);
+        assert_eq!(app.stats.abandoned, 2);
+
+        let outcomes: Vec<(Outcome, Option<String>)> = history::load()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.outcome, entry.label))
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            outcomes,
+            [
+                (Outcome::Abandoned, None),
+                (Outcome::Abandoned, Some(String::from("tea")))
+            ]
+        );
+    }
+
+    #[test]
+    fn celebration_shows_the_label_of_the_session() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().label = Some(String::from("write report"));
+        app.finish();
+        assert_eq!(app.celebrated_label(), Some("write report"));
+
+        let backend = ratatui::backend::TestBackend::new(80, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| ui(f, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect();
+        assert!(screen.contains("write report"));
+
+        app.end_celebration();
+        assert_eq!(app.celebrated_label(), None);
+    }
+
+    #[test]
+    fn palette_starts_a_labelled_session() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.open_palette();
+        app.input_str = String::from("start 05:00 tea");
+        let command = app.take_command().unwrap();
+        assert!(!apply_command(&mut app, command));
+        assert!(!app.edit_mode);
+        assert!(app.is_active());
+        assert_eq!(app.timer().time, Duration::from_secs(300));
+        assert_eq!(app.timer().label.as_deref(), Some("tea"));
+
+        app.open_palette();
+        app.input_str = String::from("sto");
+        app.complete_command();
+        assert_eq!(app.input_str, "stop ");
+    }
+
+    #[test]
+    fn timers_in_the_background_keep_counting() {
+        let config = Config {
+            timers: vec![String::from("work"), String::from("tea")],
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start();
+
+        apply_action(&mut app, Action::NextTimer);
+        assert_eq!(app.current, 1);
+        assert!(!app.is_active());
+        assert!(app.timers[0].is_active());
+
+        app.update_countdown();
+        let titles = app.tab_titles();
+        assert!(titles[0].starts_with("work 2"), "{:?}", titles);
+        assert_eq!(titles[1], "tea");
+
+        apply_action(&mut app, Action::PreviousTimer);
+        assert_eq!(app.current, 0);
+        assert!(app.is_active());
+    }
+
+    #[test]
+    fn presets_are_added_started_and_deleted() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.open_presets();
+        app.prompt_preset();
+        app.input_str = String::from("tea");
+        app.add_preset();
+        assert_eq!(
+            app.input_error.as_deref(),
+            Some("expected a name and a duration")
+        );
+
+        app.input_str = String::from("green tea 03:00");
+        app.add_preset();
+        assert!(!app.edit_mode);
+        let preset = (String::from("green tea"), String::from("03:00"));
+        assert_eq!(app.selected_preset(), Some(preset));
+
+        app.start_preset();
+        assert!(app.presets.is_none());
+        assert!(app.is_active());
+        assert_eq!(app.timer().label.as_deref(), Some("green tea"));
+
+        app.open_presets();
+        app.delete_preset();
+        assert!(app.config.presets.is_empty());
+        assert_eq!(app.presets.as_ref().unwrap().selected(), None);
+    }
+
+    #[test]
+    fn lowest_warning_that_applies_wins() {
+        let warning = |threshold_secs| Warning {
+            threshold_secs,
+            color: None,
+            bold: false,
+            blink: false,
+        };
+        let config = Config {
+            warnings: vec![warning(300), warning(60)],
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.timer_mut().remain = Duration::from_secs(30);
+        assert!(app.warning().is_none());
+
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start();
+        app.timer_mut().remain = Duration::from_secs(600);
+        assert!(app.warning().is_none());
+        app.timer_mut().remain = Duration::from_secs(120);
+        assert_eq!(app.warning().unwrap().threshold_secs, 300);
+        app.timer_mut().remain = Duration::from_secs(30);
+        assert_eq!(app.warning().unwrap().threshold_secs, 60);
+    }
+
+    #[test]
+    fn cycles_end_with_a_summary() {
+        let cycle = config::Cycle {
+            long_break_interval: 2,
+            cycles: Some(2),
+            ..config::Cycle::default()
+        };
+        let config = Config {
+            cycle: Some(cycle),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.time_str, "25:00");
+
+        let mut kinds = Vec::new();
+        for _ in 0..3 {
+            app.complete(0);
+            kinds.push(app.timer().kind);
+        }
+        let (work, short_break) = (SessionKind::Work, SessionKind::ShortBreak);
+        assert_eq!(kinds, [short_break, work, work]);
+        assert!(app.summary.is_some());
+        assert_eq!(app.stats.work_sessions, 2);
+        assert_eq!(app.stats.breaks, 1);
+        assert_eq!(app.stats.break_time, cycle.short_break);
+        assert_eq!(
+            summary_lines(&app.stats, 0, &app.locale)[1],
+            "Focused          50:00"
+        );
+
+        handle_summary_key(&mut app, KeyCode::Down);
+        assert_eq!(app.summary.as_ref().unwrap().selected(), Some(1));
+        handle_summary_key(&mut app, KeyCode::Esc);
+        assert!(app.summary.is_none());
+        assert_eq!(app.stats.work_sessions, 0);
+    }
+
+    #[test]
+    fn borders_follow_the_config_unless_monochrome() {
+        let config = Config {
+            border_style: config::BorderStyle::Rounded,
+            border_color: Some(Color::Blue),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.enter_edit();
+        let corner = |app: &App| {
+            let backend = ratatui::backend::TestBackend::new(80, 30);
+            let mut terminal = Terminal::new(backend).unwrap();
+            terminal.draw(|f| ui(f, app)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            let cell = buffer.content().iter().find(|cell| cell.symbol == "╭");
+            cell.map(|cell| cell.fg)
+        };
+        assert_eq!(corner(&app), Some(Color::Blue));
+
+        app.config.monochrome = true;
+        assert_eq!(corner(&app), Some(Color::Reset));
+    }
+
+    #[test]
+    fn completed_sessions_go_into_the_history() {
+        let dir = std::env::temp_dir().join(format!("pomidor-history-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.timer_mut().label = Some(String::from("write"));
+        app.complete(0);
+
+        let entries = history::load().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].duration_secs, 1500);
+        assert_eq!(entries[0].kind, SessionKind::Work);
+        assert_eq!(entries[0].label.as_deref(), Some("write"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pastes_go_in_at_the_cursor() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        assert!(!handle_event(&mut app, Event::Paste(String::from("25:00"))));
+        assert!(app.input_str.is_empty());
+
+        app.edit_mode = true;
+        app.enter_char('é');
+        app.enter_char(':');
+        app.move_cursor_left();
+        handle_event(&mut app, Event::Paste(String::from("1\r\n2")));
+        assert_eq!(app.input_str, "é12:");
+        assert_eq!(app.cursor_position, 3);
+
+        app.input_str = String::from("5:00");
+        app.cursor_position = 0;
+        app.paste("2");
+        app.submit_time();
+        assert_eq!(app.timer().time, Duration::from_secs(1500));
+        assert!(!app.edit_mode);
+    }
+
+    #[test]
+    fn notes_are_asked_for_while_the_break_runs() {
+        let dir = std::env::temp_dir().join(format!("pomidor-notes-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let config = Config {
+            note_prompt: true,
+            cycle: Some(config::Cycle {
+                auto_start: true,
+                ..config::Cycle::default()
+            }),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        let key = |code| Event::Key(crossterm::event::KeyEvent::from(code));
+
+        app.complete(0);
+        assert!(app.edit_mode);
+        assert_eq!(app.input_kind, InputKind::Note);
+        assert_eq!(app.timer().kind, SessionKind::ShortBreak);
+        assert!(app.is_active());
+        assert!(history::load().unwrap().is_empty());
+
+        app.paste("wrote the intro");
+        handle_event(&mut app, key(KeyCode::Enter));
+        assert!(!app.edit_mode);
+
+        // Breaks aren't asked about.
+        app.complete(0);
+        assert!(!app.edit_mode);
+        app.complete(0);
+        app.paste("nothing much");
+        handle_event(&mut app, key(KeyCode::Esc));
+
+        let notes: Vec<_> = history::load()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.note)
+            .collect();
+        assert_eq!(notes, [Some(String::from("wrote the intro")), None, None]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn breaks_may_count_up_while_work_counts_down() {
+        let config = Config {
+            cycle: Some(config::Cycle::default()),
+            kind_display: [(SessionKind::ShortBreak, DisplayMode::Elapsed)].into(),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.display, DisplayMode::Remaining);
+        assert_eq!(app.time_str, "25:00");
+
+        app.complete(0);
+        assert_eq!(app.display, DisplayMode::Elapsed);
+        assert_eq!(app.time_str, "00:00");
+        app.complete(0);
+        assert_eq!(app.display, DisplayMode::Remaining);
+        assert_eq!(app.time_str, "25:00");
+    }
+
+    #[test]
+    fn interruptions_are_ticked_and_logged() {
+        let dir = std::env::temp_dir().join(format!("pomidor-interrupt-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let config = Config {
+            pause_on_interruption: true,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        apply_action(&mut app, Action::Interrupt);
+        assert!(app.timer().interruptions.is_empty());
+
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start_countdown();
+        apply_action(&mut app, Action::Interrupt);
+        assert!(app.is_paused());
+        app.resume();
+        apply_action(&mut app, Action::Interrupt);
+        assert!(app.status_text().unwrap().starts_with("'' "));
+
+        app.complete(0);
+        assert_eq!(history::load().unwrap()[0].interruptions.len(), 2);
+        let lines = summary_lines(&app.stats, 0, &app.locale);
+        assert_eq!(lines[2], "Interruptions    2.0 per session");
+
+        app.start_countdown();
+        assert!(app.timer().interruptions.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn stops_and_resets_before_zero_are_logged_unless_misfires() {
+        let dir = std::env::temp_dir().join(format!("pomidor-outcomes-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start_countdown();
+        app.stop();
+        assert_eq!(app.stats.abandoned, 0);
+        assert!(history::load().unwrap().is_empty());
+
+        app.config.misfire_secs = 0;
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start_countdown();
+        app.reset();
+        app.start_with(Duration::from_secs(300), Some(String::from("tea")));
+        app.stop();
+        app.stop();
+        assert_eq!(app.stats.abandoned, 3);
+        assert_eq!(app.stats.completion_rate(), Some(0));
+
+        let outcomes: Vec<(Outcome, Option<String>)> = history::load()
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.outcome, entry.label))
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            outcomes,
+            [
+                (Outcome::Restarted, None),
+                (Outcome::Abandoned, None),
+                (Outcome::Abandoned, Some(String::from("tea")))
+            ]
+        );
+    }
+
+    #[test]
+    fn timers_start_out_with_the_default_duration() {
+        let config = Config {
+            default_duration: Duration::from_secs(3000),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.time_str, "50:00");
+        assert_eq!(app.space_action(), Some(SpaceAction::Start));
+
+        app.switch_to(String::from("tea"));
+        assert_eq!(app.timer().time, Duration::from_secs(3000));
+        assert_eq!(app.time_str, "50:00");
+
+        assert_eq!(duration_arg("05:00"), Ok(Duration::from_secs(300)));
+        assert!(duration_arg("5 minutes").is_err());
+    }
+
+    #[test]
+    fn losing_focus_during_work_warns_or_pauses() {
+        let config = Config {
+            focus_lost: Some(FocusLost::Warn),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        handle_event(&mut app, Event::FocusLost);
+        handle_event(&mut app, Event::FocusGained);
+        assert!(!app.nag);
+
+        app.start();
+        handle_event(&mut app, Event::FocusLost);
+        assert!(!app.nag);
+        handle_event(&mut app, Event::FocusGained);
+        assert!(app.nag);
+        handle_event(&mut app, Event::Key(KeyCode::Left.into()));
+        assert!(!app.nag);
+
+        app.config.focus_lost = Some(FocusLost::Pause);
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        handle_event(&mut app, Event::FocusLost);
+        assert!(!app.is_paused());
+        app.timer_mut().kind = SessionKind::Work;
+        handle_event(&mut app, Event::FocusLost);
+        assert!(app.is_paused());
+    }
+
+    #[test]
+    fn config_command_shows_the_path_or_asks_for_the_editor() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        apply_command(&mut app, Command::Config { edit: false });
+        assert_eq!(app.message.as_ref().unwrap().0, "no config directory");
+        assert!(!app.edit_config);
+
+        apply_command(&mut app, Command::Config { edit: true });
+        assert!(app.edit_config);
+
+        app.config.bell = false;
+        app.reload_config();
+        assert!(app.config.bell);
+        assert_eq!(app.message.as_ref().unwrap().0, "config reloaded");
+    }
+
+    #[test]
+    fn clock_jumps_are_told_and_logged() {
+        let dir = std::env::temp_dir().join(format!("pomidor-clock-{}", std::process::id()));
+        state::test_dir::set(dir.clone());
+        let mut app = App::new(Config::default(), Keymap::default());
+        let tick = Duration::from_secs(1);
+        let message = |app: &App| app.message.as_ref().unwrap().0.clone();
+        app.check_clock(chrono::Duration::seconds(3), tick);
+        assert!(app.message.is_none());
+
+        app.check_clock(chrono::Duration::seconds(553), tick);
+        assert_eq!(message(&app), "system slept 9m 12s");
+
+        app.start();
+        app.check_clock(chrono::Duration::seconds(553), tick);
+        assert_eq!(message(&app), "system slept 9m 12s — timer frozen");
+        assert!(app.remaining() > Duration::from_secs(1490));
+
+        app.config.clock_jump = ClockJump::Adjust;
+        app.check_clock(chrono::Duration::seconds(600), tick);
+        assert!(app.remaining() < Duration::from_secs(901));
+        app.check_clock(chrono::Duration::seconds(-7200), tick);
+        assert_eq!(message(&app), "clock went back 2h 0m");
+
+        let events = fs::read_to_string(dir.join("events.log")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(events.lines().count(), 4);
+        assert!(events.ends_with(" clock went back 2h 0m\n"));
+    }
+
+    #[test]
+    fn reloads_defer_new_durations_for_running_sessions() {
+        // Tests read no config file, so reloading goes back to the defaults.
+        let config = Config {
+            default_duration: Duration::from_secs(3000),
+            timers: vec![String::from("work"), String::from("tea")],
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.start();
+        apply_action(&mut app, Action::ReloadConfig);
+        assert_eq!(
+            app.message.as_ref().unwrap().0,
+            "config reloaded; the new duration applies from the next session"
+        );
+        assert_eq!(app.timer().time, Duration::from_secs(3000));
+        assert_eq!(app.timers[1].time, Duration::from_secs(1500));
+
+        app.stop();
+        app.config.default_duration = Duration::from_secs(600);
+        app.timer_mut().time = Duration::from_secs(600);
+        app.reload_config();
+        assert_eq!(app.message.as_ref().unwrap().0, "config reloaded");
+        assert_eq!(app.time_str, "25:00");
+    }
+
+    #[test]
+    fn quick_start_keys_start_a_session_while_idle() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        handle_event(&mut app, Event::Key(KeyCode::Char('2').into()));
+        assert!(app.is_active());
+        assert_eq!(app.timer().time, Duration::from_secs(300));
+    }
+
+    #[test]
+    fn exit_after_takes_seconds_minutes_or_a_duration() {
+        let secs = |value| delay_arg(value).map(|delay| delay.as_secs());
+        assert_eq!(secs("30s"), Ok(30));
+        assert_eq!(secs("2m"), Ok(120));
+        assert_eq!(secs("45"), Ok(45));
+        assert_eq!(secs("01:30"), Ok(90));
+        assert!(secs("soon").is_err());
+
+        let args = Args::try_parse_from(["pomidor", "--exit-after", "2m"]).unwrap();
+        assert_eq!(args.exit_after, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn bare_numbers_are_seconds_below_the_threshold() {
+        let secs = |input, below| parse_input(input, below).map(|value| value.as_secs());
+        assert_eq!(secs(":45", 0), Some(45));
+        assert_eq!(secs("90", 0), Some(5400));
+        assert_eq!(secs("90", 100), Some(90));
+        assert_eq!(secs("120", 100), Some(7200));
+        assert_eq!(secs("01:30", 100), Some(90));
+        assert_eq!(secs(":4x", 0), None);
+        assert_eq!(secs("-5", 0), None);
+    }
+
+    #[test]
+    fn sessions_under_a_minute_show_seconds_only() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        let mut shown = |secs| {
+            let timer = app.timer_mut();
+            timer.time = Duration::from_secs(secs);
+            timer.remain = Duration::from_secs(secs);
+            app.refresh_time_str();
+            app.time_str.clone()
+        };
+        assert_eq!(shown(45), "0:45");
+        assert_eq!(shown(60), "01:00");
+    }
+
+    #[test]
+    fn focus_mode_dims_what_is_around_the_digits() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        assert_eq!(app.chrome_style(), Style::default());
+        apply_action(&mut app, Action::ToggleFocus);
+        assert!(app.chrome_style().add_modifier.contains(Modifier::DIM));
+        apply_action(&mut app, Action::ToggleFocus);
+        assert_eq!(app.chrome_style(), Style::default());
+    }
+
+    #[test]
+    fn breaks_ring_once_shortly_before_they_end() {
+        let config = Config {
+            break_end_warning_secs: Some(30),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        let timer = app.timer_mut();
+        timer.kind = SessionKind::ShortBreak;
+        timer.state = TimerState::Running;
+        timer.remain = Duration::from_secs(45);
+        app.warn_break_end(0);
+        assert!(app.message.is_none());
+
+        app.timer_mut().remain = Duration::from_secs(20);
+        app.warn_break_end(0);
+        assert!(app.message.is_some());
+        assert_eq!(app.rings.len(), 1);
+        app.warn_break_end(0);
+        assert_eq!(app.rings.len(), 1);
+
+        app.timer_mut().kind = SessionKind::Work;
+        app.timer_mut().warned = false;
+        app.warn_break_end(0);
+        assert_eq!(app.rings.len(), 1);
+    }
+
+    #[test]
+    fn end_time_takes_the_digits_and_moves_the_countdown() {
+        let config = Config {
+            display: DisplayMode::End,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.time_str, "25:00");
+
+        app.start_countdown();
+        let end = app.format_clock(app.end_time().unwrap());
+        app.refresh_time_str();
+        assert_eq!(app.time_str, end);
+        assert!(app.status_text().unwrap().contains(" left"));
+
+        app.timer_mut().pause();
+        app.refresh_time_str();
+        assert_eq!(app.time_str, "--:--");
+    }
+
+    #[test]
+    fn done_text_stands_in_for_the_digits_until_acknowledged() {
+        let config = Config {
+            done_text: Some(String::from("TEA IS READY")),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.done_text(), None);
+
+        app.timer_mut().state = TimerState::Finished;
+        assert_eq!(app.done_text(), Some("TEA IS READY"));
+        app.timer_mut().acknowledge();
+        assert_eq!(app.done_text(), None);
+
+        app.config.done_text = Some(String::new());
+        app.timer_mut().state = TimerState::Finished;
+        assert_eq!(app.done_text(), None);
+    }
+
+    #[test]
+    fn text_too_wide_for_a_banner_stays_plain() {
+        let banner = generate_content("TEA", 0);
+        let width = banner.iter().map(String::len).max().unwrap() as u16;
+        assert_eq!(fitted_content("TEA", 0, width), banner);
+        assert_eq!(fitted_content("TEA", 0, width - 1), ["TEA"]);
+    }
+
+    #[test]
+    fn letters_are_spaced_unless_too_wide() {
+        let width = |content: &[String]| content.iter().map(String::len).max().unwrap();
+        let (tight, spaced) = (generate_content("25", 0), generate_content("25", 3));
+        assert_eq!(width(&spaced), width(&tight) + 3);
+
+        let room = width(&spaced) as u16;
+        assert_eq!(spaced_content("25", 3, room), spaced);
+        assert_eq!(spaced_content("25", 3, room - 1), tight);
+    }
+
+    #[test]
+    fn window_title_shows_the_time_left_and_the_kind() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        assert_eq!(app.window_title(), "pomidor");
+
+        app.timer_mut().time = Duration::from_secs(750);
+        app.start_countdown();
+        assert_eq!(app.window_title(), "⏳ 12:30 · work");
+        app.pause();
+        app.timer_mut().remain = Duration::from_secs(601);
+        assert_eq!(app.window_title(), "⏸ 10:01 · work");
+    }
+
+    #[test]
+    fn paused_sessions_are_marked_apart_from_running_ones() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        assert!(app.state_marker().is_none());
+
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start_countdown();
+        let running = app.state_marker().unwrap();
+        assert_eq!(running.spans[0].content, "▶ running");
+        assert!(running.spans[0].style.add_modifier.contains(Modifier::DIM));
+
+        app.pause();
+        let paused = app.state_marker().unwrap();
+        assert_eq!(paused.spans[0].content, " ⏸ PAUSED ");
+        assert!(paused.spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD | Modifier::REVERSED));
+    }
+
+    #[test]
+    fn scheduled_session_starts_once_its_time_has_come() {
+        let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(time_arg("09:00"), Ok(nine));
+        assert!(time_arg("9am").is_err());
+
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().time = Duration::from_secs(3000);
+        app.schedule(Local::now().time());
+        assert!(app.scheduled.unwrap() > Local::now());
+        app.check_schedule();
+        assert!(!app.is_active());
+
+        app.scheduled = Some(Local::now());
+        app.check_schedule();
+        assert!(app.is_active());
+        assert_eq!(app.scheduled, None);
+    }
+
+    #[test]
+    fn starting_by_hand_drops_the_schedule() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().time = Duration::from_secs(3000);
+        app.schedule(Local::now().time());
+        app.start_countdown();
+        app.check_schedule();
+        assert_eq!(app.scheduled, None);
+    }
+
+    #[test]
+    fn edit_box_runs_routine_expressions() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.edit_mode = true;
+        app.input_str = String::from("25m work / 5q break");
+        app.submit_time();
+        assert!(app.edit_mode);
+        assert_eq!(
+            app.input_error.as_deref(),
+            Some("invalid duration `5q` at column 12")
+        );
+        assert_eq!(app.cursor_position, 11);
+
+        app.input_str = String::from("25m work / 5m break x2");
+        app.submit_time();
+        assert!(!app.edit_mode);
+        assert!(app.is_active());
+        assert_eq!(app.timer().label.as_deref(), Some("work"));
+        assert_eq!(app.timer().queue.as_ref().unwrap().position(), (1, 4));
+    }
+
+    #[test]
+    fn work_sessions_count_towards_the_long_break() {
+        let config = Config {
+            cycle: Some(config::Cycle::default()),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert!(app.status_text().unwrap().contains("1/4 until long break"));
+        app.cycle_count = 2;
+        assert!(app.status_text().unwrap().contains("3/4 until long break"));
+
+        app.timer_mut().kind = SessionKind::ShortBreak;
+        assert!(!app.status_text().unwrap().contains("until long break"));
+    }
+
+    #[test]
+    fn duration_pattern_is_compiled_once() {
+        assert_eq!(parse_duration("25:00"), Some(Duration::from_secs(1500)));
+        let compiled = DURATION_RE.get().and_then(Option::as_ref).unwrap();
+        assert_eq!(parse_duration("05:00"), Some(Duration::from_secs(300)));
+        let reused = DURATION_RE.get().and_then(Option::as_ref).unwrap();
+        assert!(std::ptr::eq(compiled, reused));
+    }
+
+    #[test]
+    fn minute_bar_fills_in_eighths() {
+        assert_eq!(minute_bar(0.0, 4), "····");
+        assert_eq!(minute_bar(0.375, 4), "█▌··");
+        assert_eq!(minute_bar(0.5, 4), "██··");
+        assert_eq!(minute_bar(1.0, 4), "████");
+    }
+
+    #[test]
+    fn minute_progress_shows_while_running_when_on() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().time = Duration::from_secs(1500);
+        app.start_countdown();
+        assert!(app.minute_progress().is_none());
+
+        app.config.minute_progress = true;
+        assert!(app.minute_progress().is_some());
+        app.pause();
+        assert!(app.minute_progress().is_none());
+    }
+
+    #[test]
+    fn skipped_breaks_add_up_to_a_nudge() {
+        let config = Config {
+            cycle: Some(config::Cycle {
+                skip_nudge: 2,
+                ..config::Cycle::default()
+            }),
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        app.skip_break();
+        assert_eq!(app.message.as_ref().unwrap().0, "no break to skip");
+
+        for _ in 0..2 {
+            app.timer_mut().kind = SessionKind::ShortBreak;
+            app.skip_break();
+            assert_eq!(app.timer().kind, SessionKind::Work);
+        }
+        assert_eq!(
+            app.message.as_ref().unwrap().0,
+            "2 breaks skipped today, take the next one"
+        );
+        let lines = summary_lines(&app.stats, app.skipped_today(), &app.locale);
+        assert_eq!(lines.last().unwrap(), "Breaks skipped   2");
+
+        // A count from another day is over.
+        app.breaks_skipped.0 = app.breaks_skipped.0.pred_opt().unwrap();
+        assert_eq!(app.skipped_today(), 0);
+    }
+
+    #[test]
+    fn remaining_time_rounds_up_to_the_next_step() {
+        assert_eq!(round_up(22 * 60 + 10, 300), 25 * 60);
+        assert_eq!(round_up(25 * 60, 300), 25 * 60);
+        assert_eq!(round_up(23 * 3600 + 1, 7 * 3600), 24 * 3600);
+
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.timer_mut().time = Duration::from_secs(22 * 60 + 10);
+        handle_event(&mut app, Event::Key(KeyCode::Char('u').into()));
+        assert_eq!(app.timer().time, Duration::from_secs(22 * 60 + 10));
+
+        app.start_countdown();
+        handle_event(&mut app, Event::Key(KeyCode::Char('u').into()));
+        let left = app.remaining().as_secs();
+        assert!((24 * 60 + 59..=25 * 60).contains(&left), "{}", left);
+    }
+
+    #[test]
+    fn auto_fill_magnifies_the_banner_as_far_as_it_fits() {
+        let content = vec![String::from("ab"), String::from("cd")];
+        assert_eq!(fill_scale(&content, 9, 7), 3);
+        assert_eq!(fill_scale(&content, 1, 1), 1);
+        let doubled = scale_content(content.clone(), 2);
+        assert_eq!(doubled, ["aabb", "aabb", "ccdd", "ccdd"]);
+
+        let config = Config {
+            banner_scale: 2,
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        assert_eq!(app.banner_scale(&content, 20, 20), 2);
+        assert_eq!(app.banner_scale(&content, 2, 2), 1);
+        handle_event(&mut app, Event::Key(KeyCode::Char('F').into()));
+        assert!(app.auto_fill);
+        assert_eq!(app.banner_scale(&content, 20, 20), 10);
+        handle_event(&mut app, Event::Key(KeyCode::Char('F').into()));
+        assert_eq!(app.banner_scale(&content, 20, 20), 2);
+    }
+
+    #[test]
+    fn breakdown_goes_through_the_periods() {
+        let mut app = App::new(Config::default(), Keymap::default());
+        app.overview = Some(Overview::default());
+        handle_event(&mut app, Event::Key(KeyCode::Char('t').into()));
+        let period = |app: &App| app.breakdown.as_ref().map(|chart| chart.period);
+        assert_eq!(period(&app), Some(ChartPeriod::Today));
+        handle_event(&mut app, Event::Key(KeyCode::Char('p').into()));
+        assert_eq!(period(&app), Some(ChartPeriod::Week));
+        handle_event(&mut app, Event::Key(KeyCode::Char('p').into()));
+        assert_eq!(period(&app), Some(ChartPeriod::Month));
+        handle_event(&mut app, Event::Key(KeyCode::Char('p').into()));
+        assert_eq!(period(&app), Some(ChartPeriod::Today));
+        handle_event(&mut app, Event::Key(KeyCode::Char('t').into()));
+        assert_eq!(period(&app), None);
+        assert!(app.overview.is_some());
+    }
+
+    #[test]
+    fn breakdown_cuts_long_names_short() {
+        let report = (Some(String::from("quarterly report")), 3000);
+        let chart = Breakdown {
+            period: ChartPeriod::Today,
+            totals: vec![report, (None, 1500)],
+        };
+        let lines = breakdown_lines(&chart, 30, &Locale::default());
+        let text = |line: &Line| -> String {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        };
+        let (full, half) = ("█".repeat(11), "█".repeat(5) + "▌");
+        assert_eq!(text(&lines[0]), format!("quarterly… {}     50m", full));
+        assert_eq!(text(&lines[1]), format!("(none)     {:<11}     25m", half));
+        assert!(lines[1].spans[0].style.add_modifier.contains(Modifier::DIM));
+
+        let empty = breakdown_lines(&Breakdown::default(), 30, &Locale::default());
+        assert_eq!(text(&empty[0]), "No work sessions in this period");
+    }
+
+    #[test]
+    fn pause_all_pauses_and_resumes_the_running_timers() {
+        use TimerState::{Idle, Paused, Running};
+
+        let config = Config {
+            timers: vec![
+                String::from("work"),
+                String::from("tea"),
+                String::from("read"),
+            ],
+            ..Config::default()
+        };
+        let mut app = App::new(config, Keymap::default());
+        for _ in 0..2 {
+            app.timer_mut().time = Duration::from_secs(1500);
+            app.start();
+            apply_action(&mut app, Action::NextTimer);
+        }
+        let states = |app: &App| {
+            app.timers
+                .iter()
+                .map(|timer| timer.state)
+                .collect::<Vec<_>>()
+        };
+        let paused_all = |app: &App| app.status_text().unwrap_or_default().contains("all paused");
+
+        handle_event(&mut app, Event::Key(KeyCode::Char('p').into()));
+        assert_eq!(states(&app), [Paused, Paused, Idle]);
+        assert!(paused_all(&app));
+
+        // One resumed on its own is left alone.
+        apply_action(&mut app, Action::NextTimer);
+        apply_action(&mut app, Action::Toggle);
+        assert_eq!(states(&app), [Running, Paused, Idle]);
+        assert!(paused_all(&app));
+        handle_event(&mut app, Event::Key(KeyCode::Char('p').into()));
+        assert_eq!(states(&app), [Running, Running, Idle]);
+        assert!(!paused_all(&app));
+        assert_eq!(app.message.as_ref().unwrap().0, "resumed all timers");
+    }
 }
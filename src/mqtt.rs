@@ -0,0 +1,81 @@
+use std::{
+    sync::mpsc::{self, Sender},
+    thread,
+    time::Duration,
+};
+
+use rumqttc::{Client, MqttOptions, QoS};
+
+use crate::{config::Mqtt, state::State};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RETRY_AFTER: Duration = Duration::from_secs(5);
+/// Messages queued while the broker is unreachable; anything beyond is
+/// dropped rather than blocking the timer.
+const QUEUE_SIZE: usize = 16;
+
+/// Connects to the broker from background threads and publishes the state
+/// as JSON on every transition, e.g. a session starting or completing.
+/// Returns the sender the main loop publishes its state through.
+pub fn spawn(config: &Mqtt) -> Sender<State> {
+    let (updates, receiver) = mpsc::channel::<State>();
+
+    let mut options =
+        MqttOptions::new(config.client_id.as_str(), config.host.as_str(), config.port);
+    options.set_keep_alive(KEEP_ALIVE);
+    let (client, mut connection) = Client::new(options, QUEUE_SIZE);
+
+    // Driving the connection is what keeps it alive and reconnects it after
+    // a failure.
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            if notification.is_err() {
+                thread::sleep(RETRY_AFTER);
+            }
+        }
+    });
+
+    let topic = config.topic.clone();
+    thread::spawn(move || {
+        let mut client = client;
+        let mut last: Option<State> = None;
+
+        for state in receiver {
+            if !is_transition(last.as_ref(), &state) {
+                continue;
+            }
+
+            if let Ok(payload) = serde_json::to_vec(&state) {
+                let _ = client.try_publish(topic.as_str(), QoS::AtLeastOnce, true, payload);
+            }
+            last = Some(state);
+        }
+    });
+
+    updates
+}
+
+/// Whether `state` is worth publishing after `last`: the remaining time alone
+/// changing every second is not.
+fn is_transition(last: Option<&State>, state: &State) -> bool {
+    !last.is_some_and(|last| {
+        last.status == state.status && last.timer == state.timer && last.label == state.label
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_transitions_are_published() {
+        let running = State::running(Duration::from_secs(1500), Duration::from_secs(1500));
+        assert!(is_transition(None, &running));
+
+        let later = State::running(Duration::from_secs(1500), Duration::from_secs(1400));
+        assert!(!is_transition(Some(&running), &later));
+        let desk = later.clone().with_timer("desk");
+        assert!(is_transition(Some(&running), &desk));
+        assert!(is_transition(Some(&later), &State::idle()));
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    env,
+    io::{self, Write},
+};
+
+use crate::{config::Notifications, sound};
+
+/// A way of telling that a session completed, outside the TUI.
+pub trait Notifier {
+    /// Sends the notification, writing to the terminal through `out` if it
+    /// goes that way.
+    fn notify(&self, out: &mut dyn Write, title: &str, body: &str) -> io::Result<()>;
+
+    /// Sends the notification as urgent where the notifier can tell, and
+    /// as usual otherwise.
+    fn notify_urgent(&self, out: &mut dyn Write, title: &str, body: &str) -> io::Result<()> {
+        self.notify(out, title, body)
+    }
+}
+
+/// `OSC 9`, shown by iTerm2, kitty, WezTerm and others, also over SSH.
+struct Osc9;
+
+impl Notifier for Osc9 {
+    fn notify(&self, out: &mut dyn Write, title: &str, body: &str) -> io::Result<()> {
+        let text = format!("{}: {}", title, body);
+        write!(out, "\x1b]9;{}\x07", printable(&text))?;
+        out.flush()
+    }
+}
+
+/// `OSC 777`, shown by urxvt, foot and others.
+struct Osc777;
+
+impl Notifier for Osc777 {
+    fn notify(&self, out: &mut dyn Write, title: &str, body: &str) -> io::Result<()> {
+        // The title ends at the first semicolon.
+        let title = printable(title).replace(';', ",");
+        write!(out, "\x1b]777;notify;{};{}\x07", title, printable(body))?;
+        out.flush()
+    }
+}
+
+/// Just the terminal bell, unless silent.
+struct Bell;
+
+impl Notifier for Bell {
+    fn notify(&self, out: &mut dyn Write, _title: &str, _body: &str) -> io::Result<()> {
+        if sound::is_silent() {
+            return Ok(());
+        }
+        out.write_all(b"\x07")?;
+        out.flush()
+    }
+}
+
+/// `org.freedesktop.Notifications` on the session bus.
+#[cfg(feature = "dbus")]
+struct Desktop;
+
+#[cfg(feature = "dbus")]
+impl Notifier for Desktop {
+    fn notify(&self, _out: &mut dyn Write, title: &str, body: &str) -> io::Result<()> {
+        send_desktop(title, body, NORMAL);
+        Ok(())
+    }
+
+    fn notify_urgent(&self, _out: &mut dyn Write, title: &str, body: &str) -> io::Result<()> {
+        send_desktop(title, body, CRITICAL);
+        Ok(())
+    }
+}
+
+/// Urgency levels of the notification spec.
+#[cfg(feature = "dbus")]
+const NORMAL: u8 = 1;
+#[cfg(feature = "dbus")]
+const CRITICAL: u8 = 2;
+
+#[cfg(feature = "dbus")]
+fn send_desktop(title: &str, body: &str, urgency: u8) {
+    let (title, body) = (title.to_string(), body.to_string());
+    // The bus may take a while to answer; the UI doesn't wait for it.
+    std::thread::spawn(move || {
+        let _ = desktop_notify(&title, &body, urgency);
+    });
+}
+
+#[cfg(feature = "dbus")]
+fn desktop_notify(title: &str, body: &str, urgency: u8) -> zbus::Result<()> {
+    use std::collections::HashMap;
+
+    use zbus::{blocking::Connection, zvariant::Value};
+
+    let connection = Connection::session()?;
+    let hints: HashMap<&str, Value> = HashMap::from([("urgency", Value::U8(urgency))]);
+    connection.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "pomidor",
+            0u32,
+            "",
+            title,
+            body,
+            Vec::<&str>::new(),
+            hints,
+            -1i32,
+        ),
+    )?;
+    Ok(())
+}
+
+/// The notifier for `kind`, `auto` picking one from the terminal.
+pub fn notifier(kind: Notifications) -> Box<dyn Notifier> {
+    let kind = match kind {
+        Notifications::Auto => detect(),
+        kind => kind,
+    };
+
+    match kind {
+        Notifications::Osc9 => Box::new(Osc9),
+        Notifications::Osc777 => Box::new(Osc777),
+        #[cfg(feature = "dbus")]
+        Notifications::Desktop => Box::new(Desktop),
+        _ => Box::new(Bell),
+    }
+}
+
+/// Tells from `TERM_PROGRAM` and `TERM` which escape the terminal shows,
+/// falling back to desktop notifications where built in, or the bell.
+fn detect() -> Notifications {
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+    let term = env::var("TERM").unwrap_or_default();
+
+    if matches!(program.as_str(), "iTerm.app" | "WezTerm" | "ghostty") || term == "xterm-kitty" {
+        Notifications::Osc9
+    } else if term.starts_with("rxvt") || term.starts_with("foot") {
+        Notifications::Osc777
+    } else if cfg!(feature = "dbus") {
+        Notifications::Desktop
+    } else {
+        Notifications::Bell
+    }
+}
+
+/// `text` without control characters, which would end the escape early.
+fn printable(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
@@ -0,0 +1,111 @@
+use std::{fs, io, path::Path};
+
+use chrono::{DateTime, Local};
+
+use crate::config::expand_home;
+
+const TIMESTAMP: &str = "%Y-%m-%d %a %H:%M";
+
+/// `CLOCK: [2024-05-01 Wed 10:00]--[2024-05-01 Wed 10:25] =>  0:25`, the
+/// way org-mode writes it.
+pub fn clock_line(start: DateTime<Local>, end: DateTime<Local>) -> String {
+    let minutes = (end - start).num_minutes().max(0);
+
+    format!(
+        "CLOCK: [{}]--[{}] => {:>2}:{:02}",
+        start.format(TIMESTAMP),
+        end.format(TIMESTAMP),
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Adds a clock line for the session from `start` to `end` right below
+/// `heading` in `file`, adding the heading at the end of the file when it's
+/// not there yet. The file is replaced through a rename, so a crash never
+/// leaves it half written.
+pub fn log(
+    file: &Path,
+    heading: &str,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+) -> io::Result<()> {
+    let file = expand_home(file);
+    let content = match fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    let content = insert_clock(&content, heading, &clock_line(start, end));
+
+    let mut temp = file.clone().into_os_string();
+    temp.push(".tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, &file)
+}
+
+fn insert_clock(content: &str, heading: &str, clock: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+
+    match lines.iter().position(|line| is_heading(line, heading)) {
+        Some(index) => lines.insert(index + 1, String::from(clock)),
+        None => {
+            lines.push(format!("* {}", heading));
+            lines.push(String::from(clock));
+        }
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Whether `line` is an org heading of any level titled `title`.
+fn is_heading(line: &str, title: &str) -> bool {
+    let stars = line.chars().take_while(|c| *c == '*').count();
+    stars > 0 && line[stars..].starts_with(' ') && line[stars..].trim() == title
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 5, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn clock_lines_as_org_writes_them() {
+        assert_eq!(
+            clock_line(at(1, 10, 0), at(1, 10, 25)),
+            "CLOCK: [2024-05-01 Wed 10:00]--[2024-05-01 Wed 10:25] =>  0:25"
+        );
+        assert_eq!(
+            clock_line(at(1, 9, 0), at(1, 11, 5)),
+            "CLOCK: [2024-05-01 Wed 09:00]--[2024-05-01 Wed 11:05] =>  2:05"
+        );
+        assert_eq!(
+            clock_line(at(1, 23, 50), at(2, 0, 15)),
+            "CLOCK: [2024-05-01 Wed 23:50]--[2024-05-02 Thu 00:15] =>  0:25"
+        );
+    }
+
+    #[test]
+    fn clock_goes_right_below_its_heading() {
+        let content = "* Work\n** Writing\nCLOCK: old\n* Other\n";
+        assert_eq!(
+            insert_clock(content, "Writing", "CLOCK: new"),
+            "* Work\n** Writing\nCLOCK: new\nCLOCK: old\n* Other\n"
+        );
+        assert_eq!(
+            insert_clock("* Work\n", "Writing", "CLOCK: new"),
+            "* Work\n* Writing\nCLOCK: new\n"
+        );
+        assert!(!is_heading("*Writing", "Writing"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::env;
+
+/// Most terminals drop OSC 52 sequences longer than this.
+const MAX_LEN: usize = 100_000;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Whether the terminal is likely not on the machine the system clipboard
+/// belongs to, or behind tmux, where the escape is the way to reach it.
+pub fn preferred() -> bool {
+    ["SSH_TTY", "SSH_CONNECTION", "TMUX"]
+        .iter()
+        .any(|name| env::var_os(name).is_some())
+}
+
+/// The escape putting `text` on the clipboard of the terminal, wrapped for
+/// tmux to pass it through when running inside it. `None` when it would be
+/// too long.
+pub fn sequence(text: &str) -> Option<String> {
+    let sequence = format!("\x1b]52;c;{}\x07", encode(text.as_bytes()));
+    if sequence.len() > MAX_LEN {
+        return None;
+    }
+
+    if env::var_os("TMUX").is_some() {
+        Some(format!(
+            "\x1bPtmux;{}\x1b\\",
+            sequence.replace('\x1b', "\x1b\x1b")
+        ))
+    } else {
+        Some(sequence)
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() * 4 / 3 + 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_4648_vectors() {
+        let encoded: Vec<String> = ["", "f", "fo", "foo", "foob", "fooba", "foobar"]
+            .iter()
+            .map(|text| encode(text.as_bytes()))
+            .collect();
+        assert_eq!(
+            encoded,
+            ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"]
+        );
+    }
+
+    #[test]
+    fn high_bytes() {
+        assert_eq!(encode(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(encode("żółw".as_bytes()), "xbzDs8WCdw==");
+    }
+
+    #[test]
+    fn long_text_is_refused() {
+        // 3 bytes make 4 characters, next to the 8 of the escape itself.
+        let fits = (MAX_LEN - 8) / 4 * 3;
+        assert!(sequence(&"a".repeat(fits)).is_some());
+        assert!(sequence(&"a".repeat(fits + 3)).is_none());
+    }
+}
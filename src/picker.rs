@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use ratatui::widgets::ListState;
+
+/// Distinct durations from the history offered by the picker.
+pub const RECENT: usize = 10;
+
+/// What an entry of the picker starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Preset(String),
+    Routine(String),
+    Recent(Duration),
+}
+
+impl Target {
+    /// Locale key of the word telling where the entry comes from.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Target::Preset(_) => "picker.preset",
+            Target::Routine(_) => "picker.routine",
+            Target::Recent(_) => "picker.recent",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// What the filter is matched against, e.g. the preset name.
+    pub text: String,
+    /// Shown next to the text, e.g. the preset duration.
+    pub detail: String,
+    pub target: Target,
+}
+
+/// The fuzzy picker: every entry, the ones matching the filter, best
+/// first, and the highlighted one among those.
+#[derive(Debug, Default)]
+pub struct Picker {
+    pub entries: Vec<Entry>,
+    pub matches: Vec<usize>,
+    pub list: ListState,
+}
+
+impl Picker {
+    pub fn new(entries: Vec<Entry>) -> Picker {
+        let mut picker = Picker {
+            entries,
+            ..Picker::default()
+        };
+        picker.filter("");
+        picker
+    }
+
+    /// Keeps the entries matching `query`, highlighting the best one.
+    pub fn filter(&mut self, query: &str) {
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((score(query, &entry.text)?, index)))
+            .collect();
+        // Stable, so entries scoring the same keep their order.
+        scored.sort_by_key(|(score, _)| -score);
+
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.list.select((!self.matches.is_empty()).then_some(0));
+    }
+
+    /// Moves the highlight by `offset`, wrapping around.
+    pub fn step(&mut self, offset: isize) {
+        let count = self.matches.len() as isize;
+        if count > 0 {
+            let index = self.list.selected().unwrap_or(0) as isize;
+            self.list
+                .select(Some((index + offset).rem_euclid(count) as usize));
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        let index = *self.matches.get(self.list.selected()?)?;
+        self.entries.get(index)
+    }
+}
+
+/// How well `query` matches `text` as a subsequence, ignoring case, or
+/// None when it doesn't. Runs of consecutive characters, matches at the
+/// start of a word and an early first match score higher.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut first = None;
+    let mut last: Option<usize> = None;
+    let mut from = 0;
+
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        let at = from + text[from..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == at) {
+            score += 5;
+        }
+        if at == 0 || !text[at - 1].is_alphanumeric() {
+            score += 3;
+        }
+        first.get_or_insert(at);
+        last = Some(at);
+        from = at + 1;
+    }
+
+    Some(score - first.unwrap_or(0) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picker(names: &[&str]) -> Picker {
+        Picker::new(
+            names
+                .iter()
+                .map(|name| Entry {
+                    text: name.to_string(),
+                    detail: String::new(),
+                    target: Target::Preset(name.to_string()),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn score_needs_every_character_in_order() {
+        assert_eq!(score("", "tea"), Some(0));
+        assert_eq!(score("xyz", "tea"), None);
+        assert_eq!(score("ae", "tea"), None);
+        assert_eq!(score("tea", "tea"), Some(16));
+    }
+
+    #[test]
+    fn score_ignores_case() {
+        assert_eq!(score("TEA", "tea"), score("tea", "TeA"));
+        assert_eq!(score("é", "CAFÉ"), Some(-2));
+    }
+
+    #[test]
+    fn score_prefers_runs_word_starts_and_early_matches() {
+        assert!(score("ab", "ab") > score("ab", "a b"));
+        assert!(score("w", "a wx") > score("w", "aw"));
+        assert!(score("ea", "tea") < score("te", "tea"));
+    }
+
+    #[test]
+    fn filter_orders_by_score_and_keeps_ties_in_order() {
+        let mut picker = picker(&["nap", "steam", "tea", "team"]);
+        picker.filter("tea");
+        let texts: Vec<&str> = picker
+            .matches
+            .iter()
+            .map(|&index| picker.entries[index].text.as_str())
+            .collect();
+        assert_eq!(texts, ["tea", "team", "steam"]);
+        assert_eq!(picker.selected().unwrap().text, "tea");
+
+        picker.filter("zzz");
+        assert!(picker.selected().is_none());
+    }
+
+    #[test]
+    fn step_wraps_around() {
+        let mut picker = picker(&["nap", "tea"]);
+        picker.step(-1);
+        assert_eq!(picker.selected().unwrap().text, "tea");
+        picker.step(1);
+        assert_eq!(picker.selected().unwrap().text, "nap");
+    }
+}
@@ -0,0 +1,56 @@
+use std::{path::PathBuf, sync::OnceLock};
+
+const DEFAULT: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+
+/// Profile given with `--profile`; unset means the default one.
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Makes `name` the profile of this instance. Anything that would leave
+/// the profiles directory is refused.
+pub fn set(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && !name.chars().any(char::is_control);
+    if !valid {
+        return Err(format!("invalid profile name `{}`", name));
+    }
+    if name != DEFAULT {
+        let _ = PROFILE.set(String::from(name));
+    }
+    Ok(())
+}
+
+pub fn name() -> &'static str {
+    PROFILE.get().map_or(DEFAULT, String::as_str)
+}
+
+pub fn is_default() -> bool {
+    PROFILE.get().is_none()
+}
+
+/// `dir`, or its `profiles/<name>` subdirectory under a profile other than
+/// the default one, which keeps the paths from before profiles existed.
+pub fn dir(dir: PathBuf) -> PathBuf {
+    match PROFILE.get() {
+        Some(name) => dir.join(PROFILES_DIR).join(name),
+        None => dir,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_leaving_the_profiles_directory_are_refused() {
+        for name in ["", "..", ".hidden", "a/b", "a\\b", "tab\there"] {
+            assert!(set(name).is_err(), "{:?}", name);
+        }
+        // The default profile keeps the paths from before profiles.
+        assert!(set(DEFAULT).is_ok());
+        assert!(is_default());
+        assert_eq!(dir(PathBuf::from("data")), PathBuf::from("data"));
+    }
+}
@@ -0,0 +1,374 @@
+use std::{fs, path::Path, time::Duration};
+
+use crate::{parse_input, timer::SessionKind};
+
+/// Most sessions a routine expression may expand to.
+const MAX_STEPS: usize = 1000;
+
+/// Words with their columns.
+type Words<'a> = Vec<(usize, &'a str)>;
+
+/// A session of a queue: how long it runs and what it's for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub duration: Duration,
+    pub label: Option<String>,
+    pub kind: SessionKind,
+}
+
+impl Step {
+    /// A session of `duration`, a break when `label` says so.
+    pub fn new(duration: Duration, label: Option<String>) -> Step {
+        let kind = label.as_deref().map_or(SessionKind::Work, kind_of);
+        Step {
+            duration,
+            label,
+            kind,
+        }
+    }
+}
+
+/// What's wrong with a routine expression, and the column, from 0, where
+/// it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl ExpressionError {
+    fn new(column: usize, message: impl Into<String>) -> ExpressionError {
+        ExpressionError {
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+/// Sessions run one after the other, each starting as soon as the one
+/// before completes.
+#[derive(Debug, Clone, Default)]
+pub struct Queue {
+    steps: Vec<Step>,
+    /// Index of the step to start next.
+    next: usize,
+}
+
+impl Queue {
+    /// Reads a queue file of lines like `25:00 Math`: a duration as typed
+    /// in the edit box, then an optional label. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn load(path: &Path, seconds_below: u64) -> Result<Queue, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read queue {}: {}", path.display(), err))?;
+        Queue::parse(&content, seconds_below)
+            .map_err(|err| format!("queue {}: {}", path.display(), err))
+    }
+
+    fn parse(content: &str, seconds_below: u64) -> Result<Queue, String> {
+        let mut steps = Vec::new();
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (duration, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let duration = parse_input(duration, seconds_below)
+                .filter(|duration| !duration.is_zero())
+                .ok_or_else(|| format!("line {}: invalid duration `{}`", number + 1, duration))?;
+            let label = label.trim();
+            steps.push(Step::new(
+                duration,
+                (!label.is_empty()).then(|| label.to_string()),
+            ));
+        }
+
+        if steps.is_empty() {
+            return Err(String::from("no sessions"));
+        }
+        Ok(Queue { steps, next: 0 })
+    }
+
+    /// Parses a routine expression typed in the edit box, e.g. `25m work /
+    /// 5m break x4`: sessions separated by `/`, each a duration (`25m`,
+    /// `90s`, `1h` or as typed for a single session) and an optional label,
+    /// the whole repeated N times with a trailing `xN`.
+    pub fn from_expression(input: &str, seconds_below: u64) -> Result<Queue, ExpressionError> {
+        // The words of each session, after the column of the `/` before it.
+        let mut segments: Vec<(Option<usize>, Words)> = vec![(None, Vec::new())];
+        for (column, token) in tokens(input) {
+            match token {
+                "/" => segments.push((Some(column), Vec::new())),
+                word => segments.last_mut().unwrap().1.push((column, word)),
+            }
+        }
+
+        let mut repeat = 1;
+        if let Some((_, words)) = segments.last_mut() {
+            if let Some(&(column, word)) = words.last().filter(|_| words.len() > 1) {
+                if let Some(count) = repeat_count(word) {
+                    repeat = count
+                        .filter(|count| (1..=MAX_STEPS).contains(count))
+                        .ok_or_else(|| {
+                            ExpressionError::new(column, format!("invalid repeat `{}`", word))
+                        })?;
+                    words.pop();
+                }
+            }
+        }
+
+        let mut steps = Vec::new();
+        for (separator, words) in segments {
+            let (column, duration) = match words.first() {
+                Some(&first) => first,
+                None => {
+                    let column = separator.map_or(0, |separator| separator + 1);
+                    return Err(ExpressionError::new(column, "expected a duration"));
+                }
+            };
+            let duration = expression_duration(duration, seconds_below)
+                .filter(|duration| !duration.is_zero())
+                .ok_or_else(|| {
+                    ExpressionError::new(column, format!("invalid duration `{}`", duration))
+                })?;
+            let label: Vec<&str> = words[1..].iter().map(|(_, word)| *word).collect();
+            steps.push(Step::new(
+                duration,
+                (!label.is_empty()).then(|| label.join(" ")),
+            ));
+        }
+
+        if steps.len() * repeat > MAX_STEPS {
+            return Err(ExpressionError::new(
+                input.chars().count(),
+                format!("more than {} sessions", MAX_STEPS),
+            ));
+        }
+        let steps = (0..repeat).flat_map(|_| steps.iter().cloned()).collect();
+        Ok(Queue { steps, next: 0 })
+    }
+
+    /// Takes the next step, if any is left.
+    pub fn next(&mut self) -> Option<Step> {
+        let step = self.steps.get(self.next).cloned();
+        if step.is_some() {
+            self.next += 1;
+        }
+        step
+    }
+
+    /// Number of the step running and of steps in all, from 1.
+    pub fn position(&self) -> (usize, usize) {
+        (self.next, self.steps.len())
+    }
+}
+
+/// Whether `input` is a routine expression rather than a single duration
+/// with a label: it has a `/` or ends in a repeat like `x4`.
+pub fn is_expression(input: &str) -> bool {
+    input.contains('/')
+        || input
+            .split_whitespace()
+            .skip(1)
+            .last()
+            .is_some_and(|word| repeat_count(word).is_some())
+}
+
+/// Words of `input` with their columns; `/` is a word of its own.
+fn tokens(input: &str) -> Words<'_> {
+    let mut tokens = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+
+    for (column, (index, c)) in input.char_indices().enumerate() {
+        if c.is_whitespace() || c == '/' {
+            if let Some((column, from)) = start.take() {
+                tokens.push((column, &input[from..index]));
+            }
+            if c == '/' {
+                tokens.push((column, "/"));
+            }
+        } else if start.is_none() {
+            start = Some((column, index));
+        }
+    }
+    if let Some((column, from)) = start {
+        tokens.push((column, &input[from..]));
+    }
+
+    tokens
+}
+
+/// The kind of session a label like `coffee break`, `long break` or
+/// `#break` stands for: a break when it has the word `break`, long after
+/// `long`, and work otherwise.
+fn kind_of(label: &str) -> SessionKind {
+    let words: Vec<String> = label
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .map(|word| word.trim_start_matches('#').to_lowercase())
+        .collect();
+    match words.iter().position(|word| word == "break") {
+        Some(at) if at > 0 && words[at - 1] == "long" => SessionKind::LongBreak,
+        Some(_) => SessionKind::ShortBreak,
+        None => SessionKind::Work,
+    }
+}
+
+/// `Some` for a word like `x4`, with the count when it's a number.
+fn repeat_count(word: &str) -> Option<Option<usize>> {
+    let count = word.strip_prefix('x')?;
+    if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(count.parse().ok())
+}
+
+/// `25m`, `90s`, `1h`, `1.5m`, or a duration as typed in the edit box.
+fn expression_duration(word: &str, seconds_below: u64) -> Option<Duration> {
+    let unit = match word.chars().last()? {
+        'h' => 3600.0,
+        'm' => 60.0,
+        's' => 1.0,
+        _ => return parse_input(word, seconds_below),
+    };
+    let number = &word[..word.len() - 1];
+    let valid = !number.is_empty()
+        && !number.starts_with('.')
+        && !number.ends_with('.')
+        && number.bytes().filter(|b| *b == b'.').count() <= 1
+        && number.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+    if !valid {
+        return None;
+    }
+    let secs = number.parse::<f64>().ok()? * unit;
+    Some(Duration::from_secs(secs.round() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(queue: &Queue) -> Vec<SessionKind> {
+        queue.steps.iter().map(|step| step.kind).collect()
+    }
+
+    #[test]
+    fn breaks_go_by_the_label() {
+        use SessionKind::*;
+        assert_eq!(kind_of("break"), ShortBreak);
+        assert_eq!(kind_of("Coffee Break"), ShortBreak);
+        assert_eq!(kind_of("long break"), LongBreak);
+        assert_eq!(kind_of("long-break"), LongBreak);
+        assert_eq!(kind_of("#break"), ShortBreak);
+        assert_eq!(kind_of("work"), Work);
+        assert_eq!(kind_of("breakfast"), Work);
+    }
+
+    #[test]
+    fn routine_expression_alternates_work_and_breaks() {
+        let queue = Queue::from_expression("25m work / 5m break / 15m long break x2", 0).unwrap();
+        use SessionKind::*;
+        assert_eq!(
+            kinds(&queue),
+            [Work, ShortBreak, LongBreak, Work, ShortBreak, LongBreak]
+        );
+    }
+
+    #[test]
+    fn queue_file_steps_have_kinds() {
+        let queue = Queue::parse("25:00 Math\n05:00 break\n\n# done\n25:00", 0).unwrap();
+        assert_eq!(
+            queue.steps,
+            [
+                Step {
+                    duration: Duration::from_secs(1500),
+                    label: Some(String::from("Math")),
+                    kind: SessionKind::Work,
+                },
+                Step {
+                    duration: Duration::from_secs(300),
+                    label: Some(String::from("break")),
+                    kind: SessionKind::ShortBreak,
+                },
+                Step {
+                    duration: Duration::from_secs(1500),
+                    label: None,
+                    kind: SessionKind::Work,
+                },
+            ]
+        );
+    }
+
+    fn error(input: &str) -> (usize, String) {
+        let err = Queue::from_expression(input, 0).unwrap_err();
+        (err.column, err.message)
+    }
+
+    #[test]
+    fn errors_point_at_their_column() {
+        assert_eq!(
+            error("25m work / 5q break"),
+            (11, String::from("invalid duration `5q`"))
+        );
+        assert_eq!(
+            error("25m / 0m rest"),
+            (6, String::from("invalid duration `0m`"))
+        );
+        assert_eq!(
+            error("1..5m / 5m"),
+            (0, String::from("invalid duration `1..5m`"))
+        );
+        // Columns count characters, not bytes.
+        assert_eq!(
+            error("25m café / x"),
+            (11, String::from("invalid duration `x`"))
+        );
+    }
+
+    #[test]
+    fn empty_segments() {
+        let expected = |column| (column, String::from("expected a duration"));
+        assert_eq!(error("/ 5m"), expected(0));
+        assert_eq!(error("25m / / 5m"), expected(5));
+        assert_eq!(error("25m /"), expected(5));
+        // A repeat alone is no session.
+        assert_eq!(
+            error("25m work /   x3"),
+            (13, String::from("invalid duration `x3`"))
+        );
+    }
+
+    #[test]
+    fn repeat_bounds() {
+        let steps = |input| Queue::from_expression(input, 0).map(|queue| queue.steps.len());
+        assert_eq!(steps("25m work / 5m break x1"), Ok(2));
+        assert_eq!(steps("25m work / 5m break x500"), Ok(1000));
+        assert_eq!(
+            error("25m work x0"),
+            (9, String::from("invalid repeat `x0`"))
+        );
+        assert_eq!(
+            error("25m work / 5m break x501"),
+            (24, String::from("more than 1000 sessions"))
+        );
+        assert_eq!(
+            error("25m x1001"),
+            (4, String::from("invalid repeat `x1001`"))
+        );
+        assert_eq!(
+            error("25m x99999999999999999999999"),
+            (4, String::from("invalid repeat `x99999999999999999999999`"))
+        );
+    }
+
+    #[test]
+    fn repeat_needs_a_session_before_it() {
+        // A lone `x4` is a label-less duration that doesn't parse.
+        assert_eq!(error("x4"), (0, String::from("invalid duration `x4`")));
+        assert!(!is_expression("x4"));
+        assert!(is_expression("25m x4"));
+        assert!(is_expression("25m / 5m"));
+        assert!(!is_expression("25:00 write x-ray report"));
+    }
+}
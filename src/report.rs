@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, NaiveDate, Weekday};
+use serde::Serialize;
+
+use crate::{
+    history::{hashtags, Entry, Month, Outcome},
+    remain_to_fmt,
+    stats::completion_rate,
+    timer::SessionKind,
+};
+
+/// Sessions without tags are listed under this.
+const NO_TAG: &str = "(none)";
+
+/// Date a session belongs to: the one it started on, where it started,
+/// even when it ran past midnight.
+fn local_date(entry: &Entry) -> NaiveDate {
+    entry.start.date_naive()
+}
+
+fn local_time(time: DateTime<FixedOffset>) -> String {
+    time.format("%H:%M").to_string()
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Totals {
+    work_sessions: u32,
+    abandoned: u32,
+    focused_secs: u64,
+    breaks: u32,
+}
+
+impl Totals {
+    fn of<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Totals {
+        let mut totals = Totals::default();
+        for entry in entries {
+            if entry.kind.is_break() {
+                totals.breaks += 1;
+                continue;
+            }
+
+            match entry.outcome {
+                Outcome::Completed => {
+                    totals.work_sessions += 1;
+                    totals.focused_secs += entry.duration_secs;
+                }
+                Outcome::Abandoned | Outcome::Restarted => totals.abandoned += 1,
+            }
+        }
+        totals
+    }
+
+    fn describe(&self) -> String {
+        let abandoned = match completion_rate(self.work_sessions, self.abandoned) {
+            Some(rate) if self.abandoned > 0 => {
+                format!(", {} abandoned ({}% completed)", self.abandoned, rate)
+            }
+            _ => String::new(),
+        };
+
+        format!(
+            "{} work sessions{}, {} focused, {} breaks",
+            self.work_sessions,
+            abandoned,
+            remain_to_fmt(self.focused_secs),
+            self.breaks
+        )
+    }
+}
+
+/// Label of the session with its tags, followed by its note, if any.
+fn label(entry: &Entry) -> String {
+    let mut label = match (&entry.label, entry.kind) {
+        (Some(label), _) => label.clone(),
+        (None, SessionKind::Work) => String::new(),
+        (None, kind) => format!("({})", kind.name()),
+    };
+    if !entry.tags.is_empty() {
+        if !label.is_empty() {
+            label.push(' ');
+        }
+        label.push_str(&hashtags(&entry.tags));
+    }
+
+    match &entry.note {
+        Some(note) if label.is_empty() => note.clone(),
+        Some(note) => format!("{}: {}", label, note),
+        None => label,
+    }
+}
+
+/// Pipes would end the cell early.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn day_entries(entries: &[Entry], date: NaiveDate) -> Vec<&Entry> {
+    entries
+        .iter()
+        .filter(|entry| local_date(entry) == date)
+        .collect()
+}
+
+fn day_section(entries: &[&Entry], date: NaiveDate, markdown: bool) -> String {
+    let mut out = String::new();
+    let title = date.format("%Y-%m-%d %a");
+
+    if markdown {
+        out.push_str(&format!("## {}\n\n", title));
+        if entries.is_empty() {
+            out.push_str("No sessions.\n");
+            return out;
+        }
+
+        out.push_str("| Start | Duration | Label | Outcome |\n");
+        out.push_str("|-------|----------|-------|---------|\n");
+        for entry in entries {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                local_time(entry.start),
+                remain_to_fmt(entry.duration_secs),
+                escape_cell(&label(entry)),
+                entry.outcome.name()
+            ));
+        }
+        out.push_str(&format!(
+            "\n**Total:** {}\n",
+            Totals::of(entries.iter().copied()).describe()
+        ));
+    } else {
+        out.push_str(&format!("{}\n", title));
+        if entries.is_empty() {
+            out.push_str("  no sessions\n");
+            return out;
+        }
+
+        for entry in entries {
+            out.push_str(&format!(
+                "  {}  {:>8}  {:<9}  {}\n",
+                local_time(entry.start),
+                remain_to_fmt(entry.duration_secs),
+                entry.outcome.name(),
+                label(entry)
+            ));
+        }
+        out.push_str(&format!(
+            "  total: {}\n",
+            Totals::of(entries.iter().copied()).describe()
+        ));
+    }
+
+    out
+}
+
+/// Sessions started on `date`, with a totals line.
+pub fn day(entries: &[Entry], date: NaiveDate, markdown: bool) -> String {
+    day_section(&day_entries(entries, date), date, markdown)
+}
+
+/// First day of the week `date` falls in, weeks starting on `first`.
+pub fn week_start(date: NaiveDate, first: Weekday) -> NaiveDate {
+    let offset = (date.weekday().num_days_from_monday() + 7 - first.num_days_from_monday()) % 7;
+    date - Days::new(offset as u64)
+}
+
+/// The days a report covers.
+#[derive(Debug, Clone, Copy)]
+pub enum Period {
+    Day(NaiveDate),
+    /// Seven days from the given one.
+    Week(NaiveDate),
+}
+
+impl Period {
+    /// First and last day covered.
+    pub fn range(&self) -> (NaiveDate, NaiveDate) {
+        match *self {
+            Period::Day(date) => (date, date),
+            Period::Week(first) => (first, first + Days::new(6)),
+        }
+    }
+
+    fn days(&self) -> Vec<NaiveDate> {
+        match *self {
+            Period::Day(date) => vec![date],
+            Period::Week(first) => (0..7).map(|offset| first + Days::new(offset)).collect(),
+        }
+    }
+
+    fn title(&self) -> String {
+        match *self {
+            Period::Day(date) => date.format("%Y-%m-%d %a").to_string(),
+            Period::Week(first) if first.weekday() == Weekday::Mon => {
+                let week = first.iso_week();
+                format!("Week {}, {}", week.week(), week.year())
+            }
+            Period::Week(first) => format!("Week of {}", first.format("%Y-%m-%d")),
+        }
+    }
+
+    fn entries<'a>(&self, entries: &'a [Entry]) -> Vec<&'a Entry> {
+        self.days()
+            .into_iter()
+            .flat_map(|day| day_entries(entries, day))
+            .collect()
+    }
+}
+
+/// A line of the week table: work sessions of a day, or of the week.
+#[derive(Debug, Serialize)]
+struct Row {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    completed: u32,
+    abandoned: u32,
+    focused_minutes: u64,
+    /// Percentage of the started work sessions that were abandoned.
+    abandonment_rate: Option<u32>,
+}
+
+impl Row {
+    fn of(date: Option<NaiveDate>, totals: Totals) -> Row {
+        Row {
+            date: date.map(|date| date.format("%Y-%m-%d").to_string()),
+            completed: totals.work_sessions,
+            abandoned: totals.abandoned,
+            focused_minutes: totals.focused_secs / 60,
+            // The share of the other outcome, worked out the same way.
+            abandonment_rate: completion_rate(totals.abandoned, totals.work_sessions),
+        }
+    }
+
+    fn abandoned(&self) -> String {
+        match self.abandonment_rate {
+            Some(rate) => format!("{} ({}%)", self.abandoned, rate),
+            None => self.abandoned.to_string(),
+        }
+    }
+}
+
+/// Work sessions of the week starting on `first`, a line per day and a
+/// total line.
+pub fn week(entries: &[Entry], first: NaiveDate, markdown: bool) -> String {
+    let period = Period::Week(first);
+    let rows: Vec<(String, Row)> = period
+        .days()
+        .into_iter()
+        .map(|day| {
+            let totals = Totals::of(day_entries(entries, day));
+            (
+                day.format("%Y-%m-%d %a").to_string(),
+                Row::of(Some(day), totals),
+            )
+        })
+        .collect();
+    let total = Row::of(None, Totals::of(period.entries(entries)));
+
+    let mut out = String::new();
+    if markdown {
+        out.push_str(&format!("# {}\n\n", period.title()));
+        out.push_str("| Day | Completed | Focused (min) | Abandoned |\n");
+        out.push_str("|-----|-----------|---------------|-----------|\n");
+        for (day, row) in &rows {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                day,
+                row.completed,
+                row.focused_minutes,
+                row.abandoned()
+            ));
+        }
+        out.push_str(&format!(
+            "| **Total** | {} | {} | {} |\n",
+            total.completed,
+            total.focused_minutes,
+            total.abandoned()
+        ));
+    } else {
+        out.push_str(&format!("{}\n", period.title()));
+        out.push_str(&format!(
+            "  {:<14}  {:>9}  {:>13}  {:>9}\n",
+            "day", "completed", "focused (min)", "abandoned"
+        ));
+        let total_line = (String::from("total"), total);
+        for (day, row) in rows.iter().chain([&total_line]) {
+            out.push_str(&format!(
+                "  {:<14}  {:>9}  {:>13}  {:>9}\n",
+                day,
+                row.completed,
+                row.focused_minutes,
+                row.abandoned()
+            ));
+        }
+    }
+
+    out
+}
+
+/// The per-day lines and the total of `period` as JSON.
+pub fn json(entries: &[Entry], period: Period) -> Result<String, serde_json::Error> {
+    let days = period.days();
+    let rows: Vec<Row> = days
+        .iter()
+        .map(|day| Row::of(Some(*day), Totals::of(day_entries(entries, *day))))
+        .collect();
+    let total = Row::of(None, Totals::of(period.entries(entries)));
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "from": days[0].format("%Y-%m-%d").to_string(),
+        "to": days[days.len() - 1].format("%Y-%m-%d").to_string(),
+        "days": rows,
+        "total": total,
+    }))
+}
+
+/// Focused time per tag over `period`, longest first. A completed session
+/// counts towards each of its tags; untagged ones go under `(none)`.
+pub fn by_tag(entries: &[Entry], period: Period, markdown: bool) -> String {
+    let title = period.title();
+    let entries = period.entries(entries);
+
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    let focused = |entry: &&&Entry| !entry.kind.is_break() && entry.outcome == Outcome::Completed;
+    for entry in entries.iter().filter(focused) {
+        if entry.tags.is_empty() {
+            *totals.entry(NO_TAG).or_default() += entry.duration_secs;
+        }
+        for tag in &entry.tags {
+            *totals.entry(tag.as_str()).or_default() += entry.duration_secs;
+        }
+    }
+    let mut totals: Vec<(&str, u64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut out = String::new();
+    if markdown {
+        out.push_str(&format!("## Tags, {}\n\n", title));
+        if totals.is_empty() {
+            out.push_str("No sessions.\n");
+            return out;
+        }
+
+        out.push_str("| Tag | Focused |\n");
+        out.push_str("|-----|---------|\n");
+        for (tag, secs) in totals {
+            out.push_str(&format!(
+                "| {} | {} |\n",
+                escape_cell(&tag_name(tag)),
+                remain_to_fmt(secs)
+            ));
+        }
+    } else {
+        out.push_str(&format!("Tags, {}\n", title));
+        if totals.is_empty() {
+            out.push_str("  no sessions\n");
+            return out;
+        }
+
+        let width = totals
+            .iter()
+            .map(|(tag, _)| tag_name(tag).chars().count())
+            .max()
+            .unwrap_or(0);
+        for (tag, secs) in totals {
+            out.push_str(&format!(
+                "  {:<width$}  {:>8}\n",
+                tag_name(tag),
+                remain_to_fmt(secs),
+                width = width
+            ));
+        }
+    }
+
+    out
+}
+
+fn tag_name(tag: &str) -> String {
+    match tag {
+        NO_TAG => String::from(NO_TAG),
+        tag => format!("#{}", tag),
+    }
+}
+
+/// Totals per month, a line each.
+pub fn months(months: &[Month]) -> String {
+    if months.is_empty() {
+        return String::from("no sessions\n");
+    }
+
+    let mut out = format!(
+        "{:<7}  {:>9}  {:>9}  {:>9}  {:>6}\n",
+        "month", "completed", "abandoned", "focused", "breaks"
+    );
+    for month in months {
+        out.push_str(&format!(
+            "{:<7}  {:>9}  {:>9}  {:>9}  {:>6}\n",
+            month.month,
+            month.work_sessions,
+            month.abandoned,
+            remain_to_fmt(month.focused_secs),
+            month.breaks
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn session(start: &str, minutes: u64, kind: SessionKind, outcome: Outcome) -> Entry {
+        let start = DateTime::parse_from_rfc3339(start).unwrap();
+        Entry {
+            start,
+            end: start + chrono::Duration::minutes(minutes as i64),
+            duration_secs: minutes * 60,
+            kind,
+            label: None,
+            tags: Vec::new(),
+            outcome,
+            note: None,
+            interruptions: Vec::new(),
+        }
+    }
+
+    fn work(start: &str, minutes: u64, outcome: Outcome) -> Entry {
+        session(start, minutes, SessionKind::Work, outcome)
+    }
+
+    /// Three weeks of sessions, 2024-05-06 being a Monday.
+    fn history() -> Vec<Entry> {
+        vec![
+            work("2024-04-29T10:00:00+02:00", 25, Outcome::Completed),
+            work("2024-05-05T10:00:00+02:00", 25, Outcome::Completed),
+            work("2024-05-06T10:00:00+02:00", 25, Outcome::Completed),
+            work("2024-05-06T11:00:00+02:00", 25, Outcome::Completed),
+            work("2024-05-06T12:00:00+02:00", 10, Outcome::Abandoned),
+            work("2024-05-07T10:00:00+02:00", 50, Outcome::Completed),
+            session(
+                "2024-05-07T10:50:00+02:00",
+                5,
+                SessionKind::ShortBreak,
+                Outcome::Completed,
+            ),
+            work("2024-05-12T10:00:00+02:00", 5, Outcome::Restarted),
+            work("2024-05-13T10:00:00+02:00", 25, Outcome::Completed),
+        ]
+    }
+
+    fn lines(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    #[test]
+    fn week_counts_completed_minutes_only() {
+        assert_eq!(
+            week(&history(), date("2024-05-06"), false),
+            lines(&[
+                "Week 19, 2024",
+                "  day             completed  focused (min)  abandoned",
+                "  2024-05-06 Mon          2             50    1 (33%)",
+                "  2024-05-07 Tue          1             50     0 (0%)",
+                "  2024-05-08 Wed          0              0          0",
+                "  2024-05-09 Thu          0              0          0",
+                "  2024-05-10 Fri          0              0          0",
+                "  2024-05-11 Sat          0              0          0",
+                "  2024-05-12 Sun          0              0   1 (100%)",
+                "  total                   3            100    2 (40%)",
+            ])
+        );
+    }
+
+    #[test]
+    fn weeks_starting_on_sunday() {
+        let first = week_start(date("2024-05-13"), Weekday::Sun);
+        assert_eq!(first, date("2024-05-12"));
+        let report = week(&history(), first, true);
+        assert!(report.starts_with("# Week of 2024-05-12\n"));
+        assert!(report.ends_with("| **Total** | 1 | 25 | 1 (50%) |\n"));
+    }
+
+    #[test]
+    fn json_of_each_week() {
+        let totals: Vec<(u64, u64, u64)> = ["2024-04-29", "2024-05-06", "2024-05-13"]
+            .iter()
+            .map(|first| {
+                let report = json(&history(), Period::Week(date(first))).unwrap();
+                let total = &serde_json::from_str::<serde_json::Value>(&report).unwrap()["total"];
+                (
+                    total["completed"].as_u64().unwrap(),
+                    total["abandoned"].as_u64().unwrap(),
+                    total["focused_minutes"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(totals, [(2, 0, 50), (3, 2, 100), (1, 0, 25)]);
+    }
+
+    /// A day of sessions as logged, the last one running past midnight.
+    fn day_history() -> Vec<Entry> {
+        include_str!("../tests/fixtures/report-day.jsonl")
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn day_as_text() {
+        assert_eq!(
+            day(&day_history(), date("2024-05-07"), false),
+            lines(&[
+                "2024-05-07 Tue",
+                "  09:00     25:00  completed  fix a|b #pom: merged",
+                "  09:30     05:00  completed  (short break)",
+                "  10:00     10:00  abandoned  #pom #review",
+                "  23:50     25:00  completed  late",
+                "  total: 2 work sessions, 1 abandoned (66% completed), 50:00 focused, 1 breaks",
+            ])
+        );
+    }
+
+    #[test]
+    fn day_as_markdown() {
+        assert_eq!(
+            day(&day_history(), date("2024-05-07"), true),
+            lines(&[
+                "## 2024-05-07 Tue",
+                "",
+                "| Start | Duration | Label | Outcome |",
+                "|-------|----------|-------|---------|",
+                "| 09:00 | 25:00 | fix a\\|b #pom: merged | completed |",
+                "| 09:30 | 05:00 | (short break) | completed |",
+                "| 10:00 | 10:00 | #pom #review | abandoned |",
+                "| 23:50 | 25:00 | late | completed |",
+                "",
+                "**Total:** 2 work sessions, 1 abandoned (66% completed), 50:00 focused, 1 breaks",
+            ])
+        );
+    }
+
+    #[test]
+    fn sessions_past_midnight_stay_on_the_day_they_started() {
+        let entries = day_history();
+        assert_eq!(
+            day(&entries, date("2024-05-08"), false),
+            "2024-05-08 Wed\n  no sessions\n"
+        );
+        assert_eq!(
+            day(&entries, date("2024-05-08"), true),
+            "## 2024-05-08 Wed\n\nNo sessions.\n"
+        );
+    }
+
+    #[test]
+    fn json_of_a_day() {
+        let report = json(&day_history(), Period::Day(date("2024-05-07"))).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&report).unwrap(),
+            serde_json::json!({
+                "from": "2024-05-07",
+                "to": "2024-05-07",
+                "days": [{
+                    "date": "2024-05-07",
+                    "completed": 2,
+                    "abandoned": 1,
+                    "focused_minutes": 50,
+                    "abandonment_rate": 33,
+                }],
+                "total": {
+                    "completed": 2,
+                    "abandoned": 1,
+                    "focused_minutes": 50,
+                    "abandonment_rate": 33,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn tags_of_completed_sessions() {
+        let period = Period::Day(date("2024-05-07"));
+        assert_eq!(
+            by_tag(&day_history(), period, false),
+            lines(&[
+                "Tags, 2024-05-07 Tue",
+                "  (none)     25:00",
+                "  #pom       25:00",
+            ])
+        );
+        assert_eq!(
+            by_tag(&day_history(), period, true),
+            lines(&[
+                "## Tags, 2024-05-07 Tue",
+                "",
+                "| Tag | Focused |",
+                "|-----|---------|",
+                "| (none) | 25:00 |",
+                "| #pom | 25:00 |",
+            ])
+        );
+        assert_eq!(
+            by_tag(&day_history(), Period::Day(date("2024-05-08")), false),
+            "Tags, 2024-05-08 Wed\n  no sessions\n"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+const RING_GAP: Duration = Duration::from_millis(400);
+const FLASH: Duration = Duration::from_millis(150);
+/// Players tried in turn for sound files: PulseAudio, ALSA and macOS.
+const PLAYERS: &[&str] = &["paplay", "aplay", "afplay"];
+
+/// Whether `silent` is set in the config; no bell rings while it is.
+static SILENT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_silent(silent: bool) {
+    SILENT.store(silent, Ordering::Relaxed);
+}
+
+pub fn is_silent() -> bool {
+    SILENT.load(Ordering::Relaxed)
+}
+
+/// What the alarm writes to the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ring {
+    Bell,
+    /// Switches the terminal to reverse video.
+    FlashOn,
+    FlashOff,
+}
+
+impl Ring {
+    pub fn sequence(self) -> &'static str {
+        match self {
+            Ring::Bell => "\x07",
+            Ring::FlashOn => "\x1b[?5h",
+            Ring::FlashOff => "\x1b[?5l",
+        }
+    }
+}
+
+/// A ring of the bell right away, unless silent.
+pub fn bell() -> Vec<(Duration, Ring)> {
+    if is_silent() {
+        Vec::new()
+    } else {
+        vec![(Duration::ZERO, Ring::Bell)]
+    }
+}
+
+/// When each part of `bells` rings of the bell goes off, from now, the
+/// screen flashing along when `flash` is set, or instead when silent. The
+/// rings are spaced out, so the UI thread writes them as they come due.
+pub fn alarm(bells: u32, flash: bool) -> Vec<(Duration, Ring)> {
+    schedule(bells, flash, is_silent())
+}
+
+fn schedule(bells: u32, flash: bool, silent: bool) -> Vec<(Duration, Ring)> {
+    let flash = flash || silent;
+    let mut schedule = Vec::new();
+    for ring in 0..bells.max(1) {
+        let at = RING_GAP * ring;
+        if ring < bells && !silent {
+            schedule.push((at, Ring::Bell));
+        }
+        if flash {
+            schedule.push((at, Ring::FlashOn));
+            schedule.push((at + FLASH, Ring::FlashOff));
+        }
+    }
+    schedule
+}
+
+/// Plays the sound file at `path` with the first player found, unless
+/// silent. Fails when there is no player to play it with.
+pub fn play(path: &Path) -> io::Result<()> {
+    if is_silent() {
+        return Ok(());
+    }
+
+    for player in PLAYERS {
+        let spawned = Command::new(player)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                // Waited on, so it doesn't linger as a zombie once done.
+                thread::spawn(move || child.wait());
+                return Ok(());
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no sound player (paplay, aplay or afplay) found",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: Duration = Duration::ZERO;
+
+    #[test]
+    fn bells_are_spaced_out() {
+        assert_eq!(
+            schedule(2, false, false),
+            [(NOW, Ring::Bell), (RING_GAP, Ring::Bell)]
+        );
+        assert!(schedule(0, false, false).is_empty());
+    }
+
+    #[test]
+    fn flash_goes_with_every_bell() {
+        assert_eq!(
+            schedule(2, true, false),
+            [
+                (NOW, Ring::Bell),
+                (NOW, Ring::FlashOn),
+                (FLASH, Ring::FlashOff),
+                (RING_GAP, Ring::Bell),
+                (RING_GAP, Ring::FlashOn),
+                (RING_GAP + FLASH, Ring::FlashOff)
+            ]
+        );
+        assert_eq!(
+            schedule(0, true, false),
+            [(NOW, Ring::FlashOn), (FLASH, Ring::FlashOff)]
+        );
+    }
+
+    #[test]
+    fn silent_flashes_instead() {
+        assert_eq!(
+            schedule(2, false, true),
+            [
+                (NOW, Ring::FlashOn),
+                (FLASH, Ring::FlashOff),
+                (RING_GAP, Ring::FlashOn),
+                (RING_GAP + FLASH, Ring::FlashOff)
+            ]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    error::Error,
+    fs, io,
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{profile, timer::SessionKind};
+
+const STATE_FILE: &str = "state.json";
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Idle,
+    Running,
+    Paused,
+    Finished,
+}
+
+/// Snapshot of the timer persisted by a running instance so it can be
+/// queried from the outside.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct State {
+    pub status: Status,
+    pub duration_secs: u64,
+    pub remaining_secs: u64,
+    /// Unix timestamp at which a running session reaches zero.
+    pub ends_at: Option<i64>,
+    pub updated_at: i64,
+    /// Length of the most recently entered session, kept across stops and
+    /// restarts so it can be started again with a single key.
+    #[serde(default)]
+    pub last_duration_secs: u64,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Name of the timer the snapshot describes.
+    #[serde(default)]
+    pub timer: Option<String>,
+    /// Work or a break; None in states written before it was kept.
+    #[serde(default)]
+    pub kind: Option<SessionKind>,
+    /// Breaks skipped on `day`, e.g. `2024-05-01`.
+    #[serde(default)]
+    pub breaks_skipped: u32,
+    #[serde(default)]
+    pub day: Option<String>,
+}
+
+impl State {
+    pub fn idle() -> State {
+        State {
+            status: Status::Idle,
+            duration_secs: 0,
+            remaining_secs: 0,
+            ends_at: None,
+            updated_at: Local::now().timestamp(),
+            last_duration_secs: 0,
+            label: None,
+            timer: None,
+            kind: None,
+            breaks_skipped: 0,
+            day: None,
+        }
+    }
+
+    pub fn running(duration: Duration, remain: Duration) -> State {
+        let now = Local::now().timestamp();
+        State {
+            status: Status::Running,
+            duration_secs: duration.as_secs(),
+            remaining_secs: remain.as_secs(),
+            ends_at: Some(now + remain.as_secs() as i64),
+            updated_at: now,
+            last_duration_secs: 0,
+            label: None,
+            timer: None,
+            kind: None,
+            breaks_skipped: 0,
+            day: None,
+        }
+    }
+
+    pub fn paused(duration: Duration, remain: Duration) -> State {
+        State {
+            status: Status::Paused,
+            duration_secs: duration.as_secs(),
+            remaining_secs: remain.as_secs(),
+            ends_at: None,
+            updated_at: Local::now().timestamp(),
+            last_duration_secs: 0,
+            label: None,
+            timer: None,
+            kind: None,
+            breaks_skipped: 0,
+            day: None,
+        }
+    }
+
+    pub fn finished(duration: Duration) -> State {
+        State {
+            status: Status::Finished,
+            duration_secs: duration.as_secs(),
+            remaining_secs: 0,
+            ends_at: None,
+            updated_at: Local::now().timestamp(),
+            last_duration_secs: 0,
+            label: None,
+            timer: None,
+            kind: None,
+            breaks_skipped: 0,
+            day: None,
+        }
+    }
+
+    pub fn with_last_duration(mut self, duration: Duration) -> State {
+        self.last_duration_secs = duration.as_secs();
+        self
+    }
+
+    pub fn with_label(mut self, label: Option<String>) -> State {
+        self.label = label;
+        self
+    }
+
+    pub fn with_timer(mut self, name: &str) -> State {
+        self.timer = Some(String::from(name));
+        self
+    }
+
+    pub fn with_kind(mut self, kind: SessionKind) -> State {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn with_breaks_skipped(mut self, day: NaiveDate, count: u32) -> State {
+        self.day = Some(day.format(DAY_FORMAT).to_string());
+        self.breaks_skipped = count;
+        self
+    }
+
+    /// The day `breaks_skipped` counts the skipped breaks of.
+    pub fn skipped_on(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.day.as_deref()?, DAY_FORMAT).ok()
+    }
+
+    /// Reads the persisted state, if any.
+    pub fn load() -> Result<Option<State>, Box<dyn Error>> {
+        let path = match state_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = state_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Brings a running snapshot up to date with the wall clock.
+    pub fn current(mut self) -> State {
+        if let (Status::Running, Some(ends_at)) = (self.status, self.ends_at) {
+            let remaining = ends_at - Local::now().timestamp();
+            if remaining > 0 {
+                self.remaining_secs = remaining as u64;
+            } else {
+                self.status = Status::Finished;
+                self.remaining_secs = 0;
+                self.ends_at = None;
+            }
+        }
+        self
+    }
+}
+
+/// Where the history and the state of the current profile are kept.
+pub fn data_dir() -> Option<PathBuf> {
+    base_dir().map(profile::dir)
+}
+
+#[cfg(not(test))]
+fn base_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("pomidor"))
+}
+
+/// Tests keep their data away from the user's, in a directory of the test's
+/// own when it sets one.
+#[cfg(test)]
+fn base_dir() -> Option<PathBuf> {
+    Some(test_dir::get())
+}
+
+pub fn state_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(STATE_FILE))
+}
+
+#[cfg(test)]
+pub mod test_dir {
+    use std::{cell::RefCell, path::PathBuf};
+
+    thread_local! {
+        static DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    }
+
+    pub fn get() -> PathBuf {
+        DIR.with(|dir| dir.borrow().clone())
+            .unwrap_or_else(|| std::env::temp_dir().join("pomidor-tests"))
+    }
+
+    /// Keeps the data of the rest of the current test in `dir`.
+    pub fn set(dir: PathBuf) {
+        DIR.with(|current| *current.borrow_mut() = Some(dir));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_state_loads_back() {
+        let dir = std::env::temp_dir().join(format!("pomidor-state-{}", std::process::id()));
+        test_dir::set(dir.clone());
+
+        let state = State::running(Duration::from_secs(1500), Duration::from_secs(1200))
+            .with_timer("timer");
+        state.save().unwrap();
+        let loaded = State::load().unwrap().unwrap();
+        assert_eq!(loaded.status, Status::Running);
+        assert_eq!(loaded.remaining_secs, 1200);
+        assert_eq!(loaded.timer.as_deref(), Some("timer"));
+        assert_eq!(state_path(), Some(dir.join(STATE_FILE)));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn running_state_past_its_end_is_finished() {
+        let mut state = State::running(Duration::from_secs(60), Duration::from_secs(60));
+        state.ends_at = Some(Local::now().timestamp() - 1);
+        let state = state.current();
+        assert_eq!(state.status, Status::Finished);
+        assert_eq!(state.remaining_secs, 0);
+        assert_eq!(state.ends_at, None);
+    }
+
+    #[test]
+    fn skipped_breaks_keep_their_day() {
+        let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let state = State::idle().with_breaks_skipped(day, 2);
+        assert_eq!(state.day.as_deref(), Some("2024-05-01"));
+        assert_eq!(state.skipped_on(), Some(day));
+        assert_eq!(State::idle().skipped_on(), None);
+    }
+}
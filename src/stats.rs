@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use crate::timer::SessionKind;
+
+/// Completed sessions of the current run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    pub work_sessions: u32,
+    pub work_time: Duration,
+    pub breaks: u32,
+    pub break_time: Duration,
+    pub interruptions: u32,
+    /// Work sessions stopped or reset before they completed.
+    pub abandoned: u32,
+}
+
+impl Stats {
+    pub fn record(&mut self, kind: SessionKind, duration: Duration, interruptions: u32) {
+        if kind.is_break() {
+            self.breaks += 1;
+            self.break_time += duration;
+        } else {
+            self.work_sessions += 1;
+            self.work_time += duration;
+            self.interruptions += interruptions;
+        }
+    }
+
+    pub fn record_abandoned(&mut self, kind: SessionKind) {
+        if !kind.is_break() {
+            self.abandoned += 1;
+        }
+    }
+
+    /// Percentage of the started work sessions that completed.
+    pub fn completion_rate(&self) -> Option<u32> {
+        completion_rate(self.work_sessions, self.abandoned)
+    }
+
+    /// Average interruptions per work session.
+    pub fn interruptions_per_session(&self) -> f64 {
+        if self.work_sessions == 0 {
+            return 0.0;
+        }
+        self.interruptions as f64 / self.work_sessions as f64
+    }
+}
+
+/// Percentage of `completed + abandoned` sessions that completed; `None`
+/// when none started.
+pub fn completion_rate(completed: u32, abandoned: u32) -> Option<u32> {
+    let started = completed + abandoned;
+    (started > 0).then(|| completed * 100 / started)
+}
@@ -0,0 +1,156 @@
+use crate::{
+    remain_to_fmt,
+    state::{State, Status},
+};
+
+/// Fills `{remaining}`, `{elapsed}`, `{percent}`, `{phase}`, `{timer}`,
+/// `{label}`, `{icon}` and `{color}` in `template` from `state`. Unknown
+/// placeholders are left as they are.
+pub fn render(template: &str, state: &State) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let value = rest
+            .find('}')
+            .and_then(|close| Some((close, value(&rest[1..close], state)?)));
+        match value {
+            Some((close, value)) => {
+                output.push_str(&value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+fn value(name: &str, state: &State) -> Option<String> {
+    let elapsed = state.duration_secs.saturating_sub(state.remaining_secs);
+
+    let value = match name {
+        "remaining" => remain_to_fmt(state.remaining_secs),
+        "elapsed" => remain_to_fmt(elapsed),
+        "percent" if state.duration_secs > 0 => {
+            format!("{}%", elapsed * 100 / state.duration_secs)
+        }
+        "percent" => String::from("0%"),
+        "phase" => String::from(state.kind.map_or("", |kind| kind.name())),
+        "timer" => state.timer.clone().unwrap_or_default(),
+        "label" => state.label.clone().unwrap_or_default(),
+        "icon" => String::from(icon(state)),
+        "color" => String::from(color(state.status)),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// 🍅 during work and ☕ during breaks, whether running or not.
+fn icon(state: &State) -> &'static str {
+    match (state.status, state.kind) {
+        (Status::Idle, _) => "·",
+        (_, Some(kind)) if kind.is_break() => "☕",
+        _ => "🍅",
+    }
+}
+
+/// `#rrggbb` color of the state, as polybar and i3blocks expect it.
+fn color(status: Status) -> &'static str {
+    match status {
+        Status::Running => "#e06c75",
+        Status::Paused => "#e5c07b",
+        Status::Finished => "#98c379",
+        Status::Idle => "#abb2bf",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::timer::SessionKind;
+
+    const KINDS: [SessionKind; 3] = [
+        SessionKind::Work,
+        SessionKind::ShortBreak,
+        SessionKind::LongBreak,
+    ];
+
+    fn running(duration: u64, remaining: u64) -> State {
+        State::running(
+            Duration::from_secs(duration),
+            Duration::from_secs(remaining),
+        )
+    }
+
+    /// Seconds of `mm:ss` or `hh:mm:ss`.
+    fn secs(time: &str) -> u64 {
+        time.split(':')
+            .fold(0, |secs, part| secs * 60 + part.parse::<u64>().unwrap())
+    }
+
+    #[test]
+    fn elapsed_and_remaining_add_up() {
+        for duration in (0..=3 * 3600).step_by(599) {
+            for remaining in (0..=duration).step_by(97) {
+                let state = running(duration, remaining);
+                let elapsed = secs(&render("{elapsed}", &state));
+                assert_eq!(secs(&render("{remaining}", &state)), remaining);
+                assert_eq!(elapsed + remaining, duration);
+
+                let percent = render("{percent}", &state);
+                let percent: u64 = percent.strip_suffix('%').unwrap().parse().unwrap();
+                assert!(percent <= 100);
+                assert_eq!(percent, elapsed * 100 / duration.max(1));
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_placeholders_and_stray_braces_stay() {
+        let pieces = ["{", "}", "{nope}", "a", "{}", " ", "{{x}"];
+        for first in pieces {
+            for second in pieces {
+                for third in pieces {
+                    let template = format!("{}{}{}", first, second, third);
+                    assert_eq!(render(&template, &running(60, 30)), template);
+                    let known = format!("{}{{remaining}}{}", template, template);
+                    assert_eq!(
+                        render(&known, &running(60, 30)),
+                        format!("{}00:30{}", template, template)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn icon_and_phase_follow_the_kind_of_session() {
+        for kind in KINDS {
+            let states = [
+                running(1500, 600),
+                State::paused(Duration::from_secs(1500), Duration::from_secs(600)),
+                State::finished(Duration::from_secs(1500)),
+            ];
+            for state in states {
+                let state = state.with_kind(kind).with_timer("desk");
+                let icon = if kind.is_break() { "☕" } else { "🍅" };
+                assert_eq!(render("{icon}", &state), icon);
+                assert_eq!(render("{phase}", &state), kind.name());
+                assert_eq!(render("{timer}", &state), "desk");
+            }
+            assert_eq!(render("{icon}", &State::idle().with_kind(kind)), "·");
+        }
+        assert_eq!(render("{icon} {phase}", &running(60, 30)), "🍅 ");
+    }
+}
@@ -0,0 +1,98 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{color, config_dir, Align, BorderStyle};
+
+/// Themes that need no file: gray text, or the terminal's own foreground.
+pub const BUILT_IN: &[&str] = &["gray", "adaptive"];
+const THEMES_DIR: &str = "themes";
+
+/// A theme read from `themes/NAME.toml` in the config directory. What it
+/// leaves out comes from the config.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Color of the text.
+    #[serde(with = "color::option")]
+    pub fg: Option<Color>,
+    /// Background of the banner area.
+    #[serde(with = "color::option")]
+    pub bg: Option<Color>,
+    /// Color of the digits under `[[warnings]]` that don't set their own.
+    #[serde(with = "color::option")]
+    pub warn: Option<Color>,
+    pub border_style: Option<BorderStyle>,
+    #[serde(with = "color::option")]
+    pub border_color: Option<Color>,
+    pub alignment: Option<Align>,
+}
+
+/// Reads the themes of the themes directory, by file name. Files that
+/// can't be read or parsed are skipped, with a warning each.
+pub fn load_all() -> (BTreeMap<String, Theme>, Vec<String>) {
+    match config_dir().map(|dir| dir.join(THEMES_DIR)) {
+        Some(dir) if dir.is_dir() => load_dir(&dir),
+        _ => (BTreeMap::new(), Vec::new()),
+    }
+}
+
+fn load_dir(dir: &Path) -> (BTreeMap<String, Theme>, Vec<String>) {
+    let mut themes = BTreeMap::new();
+    let mut warnings = Vec::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warnings.push(format!("themes: cannot read {}: {}", dir.display(), err));
+            return (themes, warnings);
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let theme = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| toml::from_str::<Theme>(&content).map_err(|err| err.to_string()));
+        match theme {
+            Ok(theme) => {
+                themes.insert(name, theme);
+            }
+            Err(err) => warnings.push(format!("theme {}: {}", path.display(), err.trim())),
+        }
+    }
+
+    (themes, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_files_that_do_not_parse_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("pomidor-themes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let nord = "fg = \"cyan\"\nalignment = \"left\"\n";
+        fs::write(dir.join("nord.toml"), nord).unwrap();
+        fs::write(dir.join("broken.toml"), "fg = \"#88c0d0\"\nshadow = true\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a theme").unwrap();
+
+        let (themes, warnings) = load_dir(&dir);
+        assert_eq!(themes.keys().collect::<Vec<_>>(), ["nord"]);
+        assert_eq!(themes["nord"].fg, Some(Color::Cyan));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("broken.toml"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,384 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{events::Kind, queue::Queue, state::State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerState {
+    Idle,
+    Running,
+    Paused,
+    Finished,
+}
+
+/// What a session is for, in cycle mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl SessionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionKind::Work => "work",
+            SessionKind::ShortBreak => "short break",
+            SessionKind::LongBreak => "long break",
+        }
+    }
+
+    pub fn is_break(&self) -> bool {
+        *self != SessionKind::Work
+    }
+}
+
+/// Session following a completed `kind`, `count` being the number of work
+/// sessions completed since the last long break. Returns the next kind and
+/// the count to carry on with: every `interval`-th work session is followed
+/// by a long break, which starts the count over. An `interval` of 0 means
+/// short breaks only.
+pub fn next_session(kind: SessionKind, count: u32, interval: u32) -> (SessionKind, u32) {
+    match kind {
+        SessionKind::Work if interval > 0 && count + 1 >= interval => (SessionKind::LongBreak, 0),
+        SessionKind::Work => (SessionKind::ShortBreak, count + 1),
+        SessionKind::ShortBreak | SessionKind::LongBreak => (SessionKind::Work, count),
+    }
+}
+
+/// A single named countdown. The app keeps several of them and shows one at
+/// a time.
+pub struct Timer {
+    pub name: String,
+    pub time: Duration,
+    pub last_duration: Duration,
+    pub label: Option<String>,
+    /// Tags of the session, from `#tags` in its label.
+    pub tags: Vec<String>,
+    pub kind: SessionKind,
+    /// Whether the heads-up before the end of the session went out.
+    pub warned: bool,
+    /// Something to do during the current break, picked once it starts;
+    /// `Some(None)` once picking found nothing to suggest.
+    pub suggestion: Option<Option<String>>,
+    /// Sessions to run after the current one, started from a queue file.
+    pub queue: Option<Queue>,
+    /// When the current session was interrupted.
+    pub interruptions: Vec<DateTime<FixedOffset>>,
+    /// When the current session started on the clock; pauses since then
+    /// don't move it.
+    pub started_at: Option<DateTime<Local>>,
+    pub state: TimerState,
+    pub remain: Duration,
+    start: Instant,
+    deadline: Duration,
+    /// When the session reached zero, while it's finished.
+    finished_at: Option<Instant>,
+    /// Changes of state since the app last took them, each with the
+    /// session as it was then.
+    pub transitions: Vec<(Kind, State)>,
+}
+
+impl Timer {
+    pub fn new(name: impl Into<String>) -> Timer {
+        Timer {
+            name: name.into(),
+            time: Duration::new(0, 0),
+            last_duration: Duration::new(0, 0),
+            label: None,
+            tags: Vec::new(),
+            kind: SessionKind::Work,
+            warned: false,
+            suggestion: None,
+            queue: None,
+            interruptions: Vec::new(),
+            started_at: None,
+            state: TimerState::Idle,
+            remain: Duration::new(0, 0),
+            start: Instant::now(),
+            deadline: Duration::new(0, 0),
+            finished_at: None,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Whether a session is counting down, including while paused.
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, TimerState::Running | TimerState::Paused)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == TimerState::Paused
+    }
+
+    pub fn remaining(&self) -> Duration {
+        match self.state {
+            TimerState::Running => self.deadline.saturating_sub(self.start.elapsed()),
+            TimerState::Paused => self.deadline,
+            TimerState::Idle | TimerState::Finished => Duration::new(0, 0),
+        }
+    }
+
+    /// Starts counting down `time` from the top; a zero `time` leaves the
+    /// timer idle. A session under way counts as stopped.
+    pub fn start_countdown(&mut self) {
+        if self.is_active() {
+            self.record(Kind::Stopped);
+        }
+        self.deadline = self.time;
+        self.start = Instant::now();
+        self.remain = self.deadline;
+        self.interruptions.clear();
+        self.warned = false;
+        self.suggestion = None;
+
+        self.started_at = None;
+
+        if self.time.as_secs() > 0 {
+            self.state = TimerState::Running;
+            self.started_at = Some(Local::now());
+            self.last_duration = self.time;
+            self.record(Kind::Started);
+        } else {
+            self.state = TimerState::Idle;
+        }
+    }
+
+    /// Advances the countdown. Returns `true` when the session has just
+    /// completed.
+    pub fn update(&mut self) -> bool {
+        if self.state != TimerState::Running {
+            return false;
+        }
+
+        let elapsed = self.start.elapsed();
+
+        if self.deadline < elapsed {
+            self.remain = Duration::new(0, 0);
+            self.state = TimerState::Finished;
+            self.finished_at = Some(Instant::now());
+            self.record(Kind::Finished);
+            return true;
+        }
+
+        self.remain = self.deadline - elapsed;
+        false
+    }
+
+    pub fn pause(&mut self) {
+        if self.state != TimerState::Running {
+            return;
+        }
+
+        self.deadline = self.remaining();
+        self.remain = self.deadline;
+        self.state = TimerState::Paused;
+        self.record(Kind::Paused);
+    }
+
+    pub fn resume(&mut self) {
+        if self.state != TimerState::Paused {
+            return;
+        }
+
+        self.start = Instant::now();
+        self.state = TimerState::Running;
+        self.record(Kind::Resumed);
+    }
+
+    /// Ends the session under way without finishing it.
+    pub fn stop(&mut self) {
+        if self.is_active() {
+            self.record(Kind::Stopped);
+            self.state = TimerState::Idle;
+            self.remain = Duration::new(0, 0);
+        }
+    }
+
+    pub fn acknowledge(&mut self) {
+        if self.state == TimerState::Finished {
+            self.state = TimerState::Idle;
+        }
+    }
+
+    /// Adds `delta_secs` (or removes, when negative) to the remaining time
+    /// of an active session, or to the pending duration otherwise, never
+    /// going below zero.
+    pub fn adjust(&mut self, delta_secs: i64) {
+        let apply = |duration: Duration| {
+            Duration::from_secs(duration.as_secs().saturating_add_signed(delta_secs))
+        };
+
+        if self.is_active() {
+            self.time = apply(self.time);
+            self.deadline = apply(self.remaining());
+            self.start = Instant::now();
+            self.remain = self.deadline;
+
+            // A session shortened to nothing is over; one with time behind
+            // it runs out on the next update.
+            if self.time.is_zero() {
+                self.stop();
+            } else if self.deadline.is_zero() {
+                self.resume();
+            }
+        } else {
+            self.acknowledge();
+            self.time = apply(self.time);
+            self.remain = self.time;
+        }
+    }
+
+    /// Time since the session reached zero, until it's acknowledged.
+    pub fn overtime(&self) -> Option<Duration> {
+        match (self.state, self.finished_at) {
+            (TimerState::Finished, Some(at)) => Some(at.elapsed()),
+            _ => None,
+        }
+    }
+
+    /// Counts `skipped` as elapsed for a running session, e.g. after the
+    /// system slept.
+    pub fn skip(&mut self, skipped: Duration) {
+        if self.state == TimerState::Running {
+            self.deadline = self.deadline.saturating_sub(skipped);
+        }
+    }
+
+    /// Notes the change of state `kind` with the session as it is now.
+    fn record(&mut self, kind: Kind) {
+        let state = self
+            .snapshot()
+            .with_label(self.label.clone())
+            .with_timer(&self.name);
+        self.transitions.push((kind, state));
+    }
+
+    /// Snapshot of the timer for the state file.
+    pub fn snapshot(&self) -> State {
+        let state = match self.state {
+            TimerState::Idle => State::idle(),
+            TimerState::Running => State::running(self.time, self.remaining()),
+            TimerState::Paused => State::paused(self.time, self.deadline),
+            TimerState::Finished => State::finished(self.time),
+        };
+        state.with_kind(self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kinds of the sessions following `steps` completions from a fresh
+    /// work session.
+    fn run(interval: u32, steps: usize) -> Vec<SessionKind> {
+        let (mut kind, mut count) = (SessionKind::Work, 0);
+        (0..steps)
+            .map(|_| {
+                (kind, count) = next_session(kind, count, interval);
+                kind
+            })
+            .collect()
+    }
+
+    #[test]
+    fn long_break_after_the_interval() {
+        use SessionKind::*;
+        assert_eq!(
+            run(4, 8),
+            [ShortBreak, Work, ShortBreak, Work, ShortBreak, Work, LongBreak, Work]
+        );
+    }
+
+    #[test]
+    fn boundary_of_the_interval() {
+        assert_eq!(
+            next_session(SessionKind::Work, 2, 4),
+            (SessionKind::ShortBreak, 3)
+        );
+        assert_eq!(
+            next_session(SessionKind::Work, 3, 4),
+            (SessionKind::LongBreak, 0)
+        );
+    }
+
+    #[test]
+    fn count_wraps_around_after_a_long_break() {
+        let kinds = run(2, 12);
+        let long: Vec<usize> = (0..kinds.len())
+            .filter(|&index| kinds[index] == SessionKind::LongBreak)
+            .collect();
+        assert_eq!(long, [2, 6, 10]);
+        assert_eq!(
+            next_session(SessionKind::LongBreak, 0, 2),
+            (SessionKind::Work, 0)
+        );
+    }
+
+    #[test]
+    fn breaks_keep_the_count() {
+        assert_eq!(
+            next_session(SessionKind::ShortBreak, 3, 4),
+            (SessionKind::Work, 3)
+        );
+    }
+
+    #[test]
+    fn interval_of_one_is_long_breaks_only() {
+        use SessionKind::*;
+        assert_eq!(run(1, 4), [LongBreak, Work, LongBreak, Work]);
+    }
+
+    #[test]
+    fn interval_of_zero_is_short_breaks_only() {
+        assert!(run(0, 20)
+            .iter()
+            .all(|kind| *kind != SessionKind::LongBreak));
+        assert_eq!(
+            next_session(SessionKind::Work, u32::MAX - 1, 0).0,
+            SessionKind::ShortBreak
+        );
+    }
+
+    #[test]
+    fn pauses_keep_the_start_of_the_session() {
+        let mut timer = Timer::new("timer");
+        timer.time = Duration::from_secs(1500);
+        timer.start_countdown();
+        let started_at = timer.started_at;
+        assert!(started_at.is_some());
+
+        std::thread::sleep(Duration::from_millis(2));
+        timer.pause();
+        timer.resume();
+        assert_eq!(timer.started_at, started_at);
+
+        timer.time = Duration::new(0, 0);
+        timer.start_countdown();
+        assert_eq!(timer.started_at, None);
+    }
+
+    #[test]
+    fn adjusting_down_to_zero_ends_the_session() {
+        let mut timer = Timer::new("timer");
+        timer.time = Duration::from_secs(1500);
+        timer.start_countdown();
+        timer.skip(Duration::from_secs(600));
+        timer.pause();
+        timer.adjust(-1000);
+        assert_eq!(timer.time, Duration::from_secs(500));
+        assert_eq!(timer.state, TimerState::Running);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(timer.update());
+
+        timer.acknowledge();
+        timer.time = Duration::from_secs(1500);
+        timer.start_countdown();
+        timer.adjust(-1500);
+        assert_eq!(timer.state, TimerState::Idle);
+    }
+}